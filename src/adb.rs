@@ -0,0 +1,300 @@
+// Header line printed by `adb devices` before the device list
+const DEVICES_HEADER: &str = "List of devices attached";
+
+// State adb reports for a device that is online and usable
+pub const STATE_DEVICE: &str = "device";
+
+// Serial prefix used by adb for local emulators
+const EMULATOR_SERIAL_PREFIX: &str = "emulator-";
+
+/// One device line from `adb devices` / `adb devices -l`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdbDevice {
+  pub serial: String,
+  /// `device`, `offline`, `unauthorized`, `no permissions (...)`, ...
+  pub state: String,
+  /// `key:value` fields from the long format, in output order
+  pub properties: Vec<(String, String)>,
+}
+
+impl AdbDevice {
+  pub fn property(&self, key: &str) -> Option<&str> {
+    self
+      .properties
+      .iter()
+      .find(|(k, _)| k == key)
+      .map(|(_, v)| v.as_str())
+  }
+
+  pub fn is_online(&self) -> bool {
+    self.state == STATE_DEVICE
+  }
+
+  pub fn is_emulator(&self) -> bool {
+    self.serial.starts_with(EMULATOR_SERIAL_PREFIX)
+  }
+}
+
+/// Whether a line is part of the banner adb prints while auto-starting its server
+/// (e.g. `* daemon not running; starting now at tcp:5037`)
+pub fn is_daemon_banner(line: &str) -> bool {
+  line.trim_start().starts_with("* daemon")
+}
+
+/// Split a `key:value` property token; keys are lowercase words like
+/// `usb`, `product`, `model`, `device` or `transport_id`
+fn split_property(token: &str) -> Option<(&str, &str)> {
+  let (key, value) = token.split_once(':')?;
+  let is_key = !key.is_empty() && key.chars().all(|c| c.is_ascii_lowercase() || c == '_');
+  (is_key && !value.is_empty()).then_some((key, value))
+}
+
+/// Parse one device line into serial, state and properties.
+///
+/// The state runs from the serial to the first property token, so multi-word
+/// states like `no permissions (...); see [http://...]` stay intact while
+/// properties are never read as state text.
+fn parse_device_line(line: &str) -> Option<AdbDevice> {
+  let mut tokens = line.split_whitespace();
+  let serial = tokens.next()?.to_string();
+
+  let mut state: Vec<&str> = Vec::new();
+  let mut properties: Vec<(String, String)> = Vec::new();
+  for token in tokens {
+    match split_property(token) {
+      Some((key, value)) if !state.is_empty() => {
+        properties.push((key.to_string(), value.to_string()))
+      }
+      // A stray word after the properties started belongs to the previous value
+      _ if !properties.is_empty() => {
+        if let Some((_, value)) = properties.last_mut() {
+          value.push(' ');
+          value.push_str(token);
+        }
+      }
+      _ => state.push(token),
+    }
+  }
+
+  if state.is_empty() {
+    return None;
+  }
+  Some(AdbDevice {
+    serial,
+    state: state.join(" "),
+    properties,
+  })
+}
+
+/// Parse `adb devices` or `adb devices -l` output.
+///
+/// Everything up to the `List of devices attached` header is skipped; when the
+/// header is missing, daemon banners and blank lines are skipped instead.
+/// CRLF line endings are accepted.
+pub fn parse_devices(stdout: &str) -> Vec<AdbDevice> {
+  let lines: Vec<&str> = stdout.lines().map(|l| l.trim_end_matches('\r')).collect();
+  let start = lines
+    .iter()
+    .position(|l| l.trim() == DEVICES_HEADER)
+    .map_or(0, |i| i + 1);
+
+  lines[start..]
+    .iter()
+    .filter(|l| !l.trim().is_empty() && !is_daemon_banner(l))
+    .filter_map(|l| parse_device_line(l))
+    .collect()
+}
+
+/// AVD name from `adb emu avd name` output (the name followed by `OK`)
+pub fn parse_emu_avd_name(stdout: &str) -> Option<String> {
+  stdout
+    .lines()
+    .map(str::trim)
+    .find(|l| !l.is_empty() && *l != "OK" && !is_daemon_banner(l))
+    .map(str::to_string)
+}
+
+/// The first IPv4 address in `ip -f inet addr show <iface>` output (`inet
+/// 10.0.2.16/24 brd ...`). Older toolboxes print `inet addr:10.0.2.16`; a
+/// missing interface or an error message gives `None`.
+pub fn parse_inet_addr(stdout: &str) -> Option<std::net::Ipv4Addr> {
+  stdout.lines().find_map(|line| {
+    let rest = line.trim().strip_prefix("inet ")?.trim_start();
+    let addr = rest.strip_prefix("addr:").unwrap_or(rest);
+    let addr = addr.split(|c: char| c == '/' || c.is_whitespace()).next()?;
+    addr.parse().ok()
+  })
+}
+
+/// adb commands that change what adbd may do on an engineering build
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdbdCommand {
+  Root,
+  Unroot,
+  Remount,
+  DisableVerity,
+  EnableVerity,
+}
+
+impl AdbdCommand {
+  /// The adb subcommand, also the name `emulaunch adb` accepts
+  pub const NAMES: [&'static str; 5] = [
+    "root",
+    "unroot",
+    "remount",
+    "disable-verity",
+    "enable-verity",
+  ];
+
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "root" => Some(Self::Root),
+      "unroot" => Some(Self::Unroot),
+      "remount" => Some(Self::Remount),
+      "disable-verity" => Some(Self::DisableVerity),
+      "enable-verity" => Some(Self::EnableVerity),
+      _ => None,
+    }
+  }
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Self::Root => "root",
+      Self::Unroot => "unroot",
+      Self::Remount => "remount",
+      Self::DisableVerity => "disable-verity",
+      Self::EnableVerity => "enable-verity",
+    }
+  }
+
+  /// Whether adbd restarts after this succeeds, so the device drops off
+  /// adb for a moment
+  pub fn restarts_adbd(self) -> bool {
+    matches!(self, Self::Root | Self::Unroot)
+  }
+}
+
+/// What a reply means, matched on a lowercase substring. The first match
+/// wins, so more specific texts come first.
+enum Reply {
+  Done(&'static str),
+  /// Succeeded, but only takes effect after a reboot
+  NeedsReboot(&'static str),
+  Refused(&'static str),
+}
+
+// Replies from adbd across Android releases and emulator images
+const REPLIES: &[(AdbdCommand, &str, Reply)] = &[
+  (
+    AdbdCommand::Root,
+    "already running as root",
+    Reply::Done("adbd is already running as root"),
+  ),
+  (
+    AdbdCommand::Root,
+    "cannot run as root in production builds",
+    Reply::Refused(
+      "adbd can't run as root on a production build. Use a system image without \
+       Google Play (Google APIs or AOSP) for root access",
+    ),
+  ),
+  (
+    AdbdCommand::Root,
+    "restarting adbd as root",
+    Reply::Done("adbd restarted as root"),
+  ),
+  (
+    AdbdCommand::Unroot,
+    "not running as root",
+    Reply::Done("adbd is already running as non root"),
+  ),
+  (
+    AdbdCommand::Unroot,
+    "restarting adbd as non root",
+    Reply::Done("adbd restarted as non root"),
+  ),
+  (
+    AdbdCommand::Remount,
+    "not running as root",
+    Reply::Refused("adbd isn't running as root. Run `emulaunch adb {name} root` first"),
+  ),
+  (
+    AdbdCommand::Remount,
+    "disable-verity",
+    Reply::Refused(
+      "verity is enabled. Run `emulaunch adb {name} disable-verity`, restart the \
+       emulator, then remount again",
+    ),
+  ),
+  (
+    AdbdCommand::Remount,
+    "reboot",
+    Reply::NeedsReboot("Remount set up"),
+  ),
+  (
+    AdbdCommand::Remount,
+    "remount succeeded",
+    Reply::Done("Partitions remounted read-write"),
+  ),
+  (
+    AdbdCommand::DisableVerity,
+    "only works for userdebug builds",
+    Reply::Refused("verity can only be changed on a userdebug build"),
+  ),
+  (
+    AdbdCommand::DisableVerity,
+    "already disabled",
+    Reply::Done("Verity is already disabled"),
+  ),
+  (
+    AdbdCommand::DisableVerity,
+    "disabled",
+    Reply::NeedsReboot("Verity disabled"),
+  ),
+  (
+    AdbdCommand::EnableVerity,
+    "only works for userdebug builds",
+    Reply::Refused("verity can only be changed on a userdebug build"),
+  ),
+  (
+    AdbdCommand::EnableVerity,
+    "already enabled",
+    Reply::Done("Verity is already enabled"),
+  ),
+  (
+    AdbdCommand::EnableVerity,
+    "enabled",
+    Reply::NeedsReboot("Verity enabled"),
+  ),
+];
+
+/// Interpret what adb printed (stdout and stderr together) for an adbd
+/// command on the device called `name`. Known replies become a message or
+/// an error with what to do next; anything else is an error quoting adb.
+pub fn interpret_adbd_reply(
+  command: AdbdCommand,
+  name: &str,
+  output: &str,
+) -> Result<String, String> {
+  let text = output
+    .lines()
+    .map(|l| l.trim_end_matches('\r').trim())
+    .filter(|l| !l.is_empty() && !is_daemon_banner(l))
+    .collect::<Vec<_>>()
+    .join("\n");
+  let lower = text.to_lowercase();
+  let reply = REPLIES
+    .iter()
+    .find(|(cmd, needle, _)| *cmd == command && lower.contains(needle))
+    .map(|(_, _, reply)| reply);
+  match reply {
+    Some(Reply::Done(msg)) => Ok(msg.to_string()),
+    Some(Reply::NeedsReboot(msg)) => Ok(format!(
+      "{}; restart the emulator for it to take effect",
+      msg
+    )),
+    Some(Reply::Refused(msg)) => Err(msg.replace("{name}", name)),
+    None if text.is_empty() => Err(format!("adb {} printed nothing", command.name())),
+    None => Err(format!("adb {} failed: {}", command.name(), text)),
+  }
+}
@@ -0,0 +1,241 @@
+use crate::config;
+use crate::filelock::{self, FileLock};
+use crate::history;
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+// Bumped when the bundle layout changes incompatibly
+const BUNDLE_VERSION: u32 = 1;
+
+const MANIFEST_FILE: &str = "manifest.json";
+const CONFIG_FILE: &str = "config.toml";
+const HISTORY_FILE: &str = "history.jsonl";
+
+/// What a bundled file is; decides where it is installed on import
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum FileKind {
+  Config,
+  History,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BundleFile {
+  kind: FileKind,
+  /// File name inside the bundle
+  name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+  version: u32,
+  files: Vec<BundleFile>,
+}
+
+/// Whether a bundle path names a gzipped tarball rather than a directory
+fn is_archive(path: &Path) -> bool {
+  let name = path.to_string_lossy();
+  name.ends_with(".tar.gz") || name.ends_with(".tgz")
+}
+
+/// Fresh scratch directory for packing or unpacking an archive
+fn staging_dir() -> Result<PathBuf, String> {
+  crate::filelock::create_temp_dir("emulaunch-bundle")
+}
+
+fn run_tar(args: &[&std::ffi::OsStr]) -> Result<(), String> {
+  let output = crate::runner::output(std::process::Command::new("tar").args(args))
+    .map_err(|e| format!("Failed to run tar: {}", e))?;
+  if !output.status.success() {
+    return Err(format!(
+      "tar failed: {}",
+      String::from_utf8_lossy(&output.stderr).trim()
+    ));
+  }
+  Ok(())
+}
+
+/// Where a bundled file of this kind is installed on this machine
+fn install_path(kind: FileKind) -> Result<PathBuf, String> {
+  match kind {
+    FileKind::Config => config::active_config_path().or_else(config::preferred_config_path),
+    FileKind::History => history::history_path(),
+  }
+  .ok_or_else(|| config::ERR_NO_HOME.to_string())
+}
+
+/// Write the bundle files into `dir`, returning a summary line per file
+fn write_bundle_dir(dir: &Path, include_history: bool) -> Result<Vec<String>, String> {
+  let mut sources = Vec::new();
+  let config_path = config::active_config_path()
+    .ok_or_else(|| "No config file to export; create one first".to_string())?;
+  sources.push((FileKind::Config, CONFIG_FILE, config_path));
+  if include_history {
+    match history::history_path() {
+      Some(path) if path.exists() => sources.push((FileKind::History, HISTORY_FILE, path)),
+      _ => {}
+    }
+  }
+
+  std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+  let mut summary = Vec::new();
+  let mut files = Vec::new();
+  for (kind, name, source) in sources {
+    std::fs::copy(&source, dir.join(name))
+      .map_err(|e| format!("Failed to copy {}: {}", source.display(), e))?;
+    summary.push(format!("  {} <- {}", name, source.display()));
+    files.push(BundleFile {
+      kind,
+      name: name.to_string(),
+    });
+  }
+
+  let manifest = Manifest {
+    version: BUNDLE_VERSION,
+    files,
+  };
+  let json = serde_json::to_string_pretty(&manifest).map_err(|e| e.to_string())?;
+  std::fs::write(dir.join(MANIFEST_FILE), json)
+    .map_err(|e| format!("Failed to write manifest: {}", e))?;
+  Ok(summary)
+}
+
+/// Export the active config (and optionally the launch history) to a
+/// directory or a `.tar.gz` archive
+pub fn export(target: &Path, include_history: bool) -> Result<String, String> {
+  if target.exists() && (is_archive(target) || target.join(MANIFEST_FILE).exists()) {
+    return Err(format!("{} already exists", target.display()));
+  }
+
+  let summary = if is_archive(target) {
+    let staging = staging_dir()?;
+    let summary = write_bundle_dir(&staging, include_history);
+    let packed = summary.and_then(|s| {
+      run_tar(&[
+        "-czf".as_ref(),
+        target.as_os_str(),
+        "-C".as_ref(),
+        staging.as_os_str(),
+        ".".as_ref(),
+      ])
+      .map(|_| s)
+    });
+    let _ = std::fs::remove_dir_all(&staging);
+    packed?
+  } else {
+    write_bundle_dir(target, include_history)?
+  };
+
+  Ok(format!(
+    "Exported to {}:\n{}",
+    target.display(),
+    summary.join("\n")
+  ))
+}
+
+/// Read and validate the manifest of an unpacked bundle
+fn read_manifest(dir: &Path) -> Result<Manifest, String> {
+  let contents = std::fs::read_to_string(dir.join(MANIFEST_FILE))
+    .map_err(|_| format!("{} is not an emulaunch bundle (no manifest)", dir.display()))?;
+  let manifest: Manifest =
+    serde_json::from_str(&contents).map_err(|e| format!("Invalid bundle manifest: {}", e))?;
+  if manifest.version != BUNDLE_VERSION {
+    return Err(format!(
+      "Unsupported bundle version {} (expected {})",
+      manifest.version, BUNDLE_VERSION
+    ));
+  }
+  // Names are looked up inside the bundle only; anything path-like is rejected
+  if let Some(bad) = manifest
+    .files
+    .iter()
+    .find(|f| f.name.is_empty() || f.name.contains(['/', '\\']) || f.name == "." || f.name == "..")
+  {
+    return Err(format!(
+      "Invalid file name in bundle manifest: {}",
+      bad.name
+    ));
+  }
+  Ok(manifest)
+}
+
+/// Install the files of an unpacked bundle, relocated to this machine's
+/// config and data directories
+fn install_bundle_dir(dir: &Path, force: bool) -> Result<String, String> {
+  let manifest = read_manifest(dir)?;
+  let mut plan = Vec::new();
+  for file in &manifest.files {
+    let source = dir.join(&file.name);
+    if !source.is_file() {
+      return Err(format!("Bundle is missing {}", file.name));
+    }
+    plan.push((source, install_path(file.kind)?));
+  }
+
+  let existing: Vec<&PathBuf> = plan
+    .iter()
+    .map(|(_, dest)| dest)
+    .filter(|d| d.exists())
+    .collect();
+  let lines: Vec<String> = plan
+    .iter()
+    .map(|(source, dest)| {
+      let note = match (dest.exists(), force) {
+        (true, true) => " (replaced)",
+        (true, false) => " (exists)",
+        _ => "",
+      };
+      format!(
+        "  {} -> {}{}",
+        source.file_name().unwrap_or_default().to_string_lossy(),
+        dest.display(),
+        note
+      )
+    })
+    .collect();
+  if !existing.is_empty() && !force {
+    return Err(format!(
+      "Import would overwrite existing files; pass --force to replace them:\n{}",
+      lines.join("\n")
+    ));
+  }
+
+  for (source, dest) in &plan {
+    let contents = std::fs::read_to_string(source)
+      .map_err(|e| format!("Failed to read {}: {}", source.display(), e))?;
+    if let Some(parent) = dest.parent() {
+      std::fs::create_dir_all(parent)
+        .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let _lock = FileLock::acquire(dest)?;
+    filelock::write_atomic(dest, &contents)?;
+  }
+
+  Ok(format!(
+    "Imported {} file(s):\n{}",
+    plan.len(),
+    lines.join("\n")
+  ))
+}
+
+/// Import a bundle from a directory or a `.tar.gz` archive
+pub fn import(source: &Path, force: bool) -> Result<String, String> {
+  if !source.exists() {
+    return Err(format!("{} does not exist", source.display()));
+  }
+  if source.is_dir() {
+    return install_bundle_dir(source, force);
+  }
+
+  let staging = staging_dir()?;
+  let result = run_tar(&[
+    "-xzf".as_ref(),
+    source.as_os_str(),
+    "-C".as_ref(),
+    staging.as_os_str(),
+  ])
+  .and_then(|_| install_bundle_dir(&staging, force));
+  let _ = std::fs::remove_dir_all(&staging);
+  result
+}
@@ -0,0 +1,114 @@
+//! Copying text to the system clipboard (`y`/`Y` in the TUI).
+//!
+//! Locally a clipboard tool is used when one is installed (`pbcopy`,
+//! `wl-copy`, `xclip`, `xsel`, `clip.exe`). Over SSH, or when there is no
+//! tool, the text goes to the terminal as an OSC 52 sequence, which the
+//! terminal on the user's machine puts on its clipboard.
+
+use crate::config;
+use crate::runner;
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// How the text reached the clipboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+  Tool(&'static str),
+  Osc52,
+}
+
+/// Copy `text`, writing any OSC 52 sequence to `terminal`. Errors when no
+/// tool is found and the terminal can't take OSC 52.
+pub fn copy(text: &str, terminal: &mut impl Write) -> Result<Method, String> {
+  // A tool on the remote machine would fill the wrong clipboard
+  if !over_ssh() {
+    if let Some((tool, args)) = find_tool() {
+      pipe_to(tool, args, text)?;
+      return Ok(Method::Tool(tool));
+    }
+  }
+  if !terminal_takes_osc52() {
+    return Err("no clipboard tool found".to_string());
+  }
+  write!(terminal, "\x1b]52;c;{}\x07", base64(text.as_bytes()))
+    .and_then(|_| terminal.flush())
+    .map_err(|e| format!("Failed to write to the terminal: {}", e))?;
+  Ok(Method::Osc52)
+}
+
+fn over_ssh() -> bool {
+  ["SSH_TTY", "SSH_CONNECTION"]
+    .iter()
+    .any(|var| std::env::var_os(var).is_some_and(|v| !v.is_empty()))
+}
+
+// The Linux console and dumb terminals ignore it
+fn terminal_takes_osc52() -> bool {
+  std::env::var("TERM").is_ok_and(|term| !term.is_empty() && term != "dumb" && term != "linux")
+}
+
+/// The first clipboard tool on PATH that fits the session
+fn find_tool() -> Option<(&'static str, &'static [&'static str])> {
+  let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some();
+  let x11 = std::env::var_os("DISPLAY").is_some();
+  let tools: [(&str, &[&str], bool); 6] = [
+    ("pbcopy", &[], cfg!(target_os = "macos")),
+    ("wl-copy", &[], wayland),
+    ("xclip", &["-selection", "clipboard"], x11),
+    ("xsel", &["--clipboard", "--input"], x11),
+    // Windows, and WSL through its interop
+    ("clip.exe", &[], true),
+    ("clip", &[], cfg!(windows)),
+  ];
+  tools
+    .into_iter()
+    .filter(|&(_, _, usable)| usable)
+    .find(|&(tool, ..)| config::command_exists(tool))
+    .map(|(tool, args, _)| (tool, args))
+}
+
+fn pipe_to(tool: &str, args: &[&str], text: &str) -> Result<(), String> {
+  let mut child = runner::spawn(
+    Command::new(tool)
+      .args(args)
+      .stdin(Stdio::piped())
+      .stdout(Stdio::null())
+      .stderr(Stdio::null()),
+  )
+  .map_err(|e| format!("Failed to run {}: {}", tool, e))?;
+  if let Some(mut stdin) = child.stdin.take() {
+    stdin
+      .write_all(text.as_bytes())
+      .map_err(|e| format!("Failed to write to {}: {}", tool, e))?;
+  }
+  let status = child
+    .wait()
+    .map_err(|e| format!("Failed to run {}: {}", tool, e))?;
+  if !status.success() {
+    return Err(format!("{} exited with {}", tool, status));
+  }
+  Ok(())
+}
+
+/// Standard base64 with padding, for the OSC 52 payload
+fn base64(bytes: &[u8]) -> String {
+  const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+  let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+  for chunk in bytes.chunks(3) {
+    let b = [
+      chunk[0],
+      *chunk.get(1).unwrap_or(&0),
+      *chunk.get(2).unwrap_or(&0),
+    ];
+    let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+    for i in 0..4 {
+      if i <= chunk.len() {
+        out.push(ALPHABET[((n >> (18 - 6 * i)) & 0x3f) as usize] as char);
+      } else {
+        out.push('=');
+      }
+    }
+  }
+  out
+}
@@ -0,0 +1,61 @@
+//! Calendar time from the system clock, for names such as screenshot
+//! files. Always UTC, so no time zone database is needed.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A UTC date and time of day, to the second
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UtcTime {
+  pub year: i64,
+  pub month: u32,
+  pub day: u32,
+  pub hour: u32,
+  pub minute: u32,
+  pub second: u32,
+}
+
+impl UtcTime {
+  pub fn now() -> Self {
+    let secs = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map_or(0, |d| d.as_secs());
+    Self::from_unix(secs)
+  }
+
+  pub fn from_unix(secs: u64) -> Self {
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let time = (secs % 86_400) as u32;
+    UtcTime {
+      year,
+      month,
+      day,
+      hour: time / 3600,
+      minute: time % 3600 / 60,
+      second: time % 60,
+    }
+  }
+
+  /// `YYYYMMDD-HHMMSS`, which sorts by time
+  pub fn compact(&self) -> String {
+    format!(
+      "{:04}{:02}{:02}-{:02}{:02}{:02}",
+      self.year, self.month, self.day, self.hour, self.minute, self.second
+    )
+  }
+}
+
+/// Year, month and day of a count of days since 1970-01-01, from Howard
+/// Hinnant's `civil_from_days`
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+  let days = days + 719_468;
+  let era = days.div_euclid(146_097);
+  let day_of_era = days.rem_euclid(146_097);
+  let year_of_era =
+    (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+  let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let mp = (5 * day_of_year + 2) / 153;
+  let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+  let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+  let year = year_of_era + era * 400 + i64::from(month <= 2);
+  (year, month, day)
+}
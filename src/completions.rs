@@ -0,0 +1,190 @@
+//! Shell completion scripts.
+//!
+//! clap_complete generates the static part (subcommands and flags). Device
+//! names can't be known ahead of time, so each script gets a small addition
+//! that calls the hidden `__complete-names` subcommand. Device-name
+//! arguments are found by their `ValueHint::Other` marker, so a new
+//! subcommand taking a device only needs the same attribute.
+
+use clap::{ArgAction, ValueHint};
+use clap_complete::Shell;
+
+const BIN_NAME: &str = "emulaunch";
+
+/// Subcommands that take a device name as their first positional argument
+struct DeviceSubcommands {
+  /// The name may span several words (`open iPhone 15 Pro`)
+  multi_word: Vec<String>,
+  /// Only the first positional is a name
+  single_word: Vec<String>,
+  /// Long options that take a value, whose value isn't a positional
+  value_options: Vec<String>,
+}
+
+fn device_subcommands(command: &clap::Command) -> DeviceSubcommands {
+  let mut subcommands = DeviceSubcommands {
+    multi_word: Vec::new(),
+    single_word: Vec::new(),
+    value_options: Vec::new(),
+  };
+  let all_args = command
+    .get_arguments()
+    .chain(command.get_subcommands().flat_map(|s| s.get_arguments()));
+  for arg in all_args {
+    if let Some(long) = arg.get_long() {
+      let option = format!("--{}", long);
+      if arg.get_action().takes_values() && !subcommands.value_options.contains(&option) {
+        subcommands.value_options.push(option);
+      }
+    }
+  }
+  for sub in command.get_subcommands() {
+    let device_arg = sub
+      .get_positionals()
+      .find(|a| a.get_value_hint() == ValueHint::Other);
+    match device_arg {
+      Some(arg) if matches!(arg.get_action(), ArgAction::Append) => {
+        subcommands.multi_word.push(sub.get_name().to_string())
+      }
+      Some(_) => subcommands.single_word.push(sub.get_name().to_string()),
+      None => {}
+    }
+  }
+  subcommands
+}
+
+// Wraps the generated `_emulaunch`: device names for device arguments,
+// everything else as generated. Names are quoted with `printf %q`.
+const BASH_NAMES: &str = r#"
+_emulaunch_device_names() {
+    local cur="${COMP_WORDS[COMP_CWORD]}" sub="" positionals=0 i
+    for ((i = 1; i < COMP_CWORD; i++)); do
+        case "${COMP_WORDS[i]}" in
+            @VALUE_OPTIONS@) ((i++)) ;;
+            -*) ;;
+            *)
+                if [[ -z $sub ]]; then
+                    sub="${COMP_WORDS[i]}"
+                else
+                    ((positionals++))
+                fi
+                ;;
+        esac
+    done
+    case "$sub" in
+        @MULTI@) ;;
+        @SINGLE@) ((positionals == 0)) || { _emulaunch "$@"; return; } ;;
+        *) _emulaunch "$@"; return ;;
+    esac
+    if [[ $cur == -* ]]; then
+        _emulaunch "$@"
+        return
+    fi
+    local name quoted
+    COMPREPLY=()
+    while IFS= read -r name; do
+        printf -v quoted '%q' "$name"
+        [[ $quoted == "$cur"* ]] && COMPREPLY+=("$quoted")
+    done < <(emulaunch __complete-names 2>/dev/null)
+}
+
+complete -F _emulaunch_device_names -o bashdefault -o default emulaunch
+"#;
+
+// Device arguments are generated with an empty action; point them at a
+// function instead. compadd quotes the names itself.
+const ZSH_NAMES: &str = r#"
+(( $+functions[_emulaunch_device_names] )) ||
+_emulaunch_device_names() {
+    local -a names
+    names=("${(@f)$(emulaunch __complete-names 2>/dev/null)}")
+    compadd -a names
+}
+"#;
+
+// fish escapes candidates itself
+const FISH_NAMES: &str = r#"
+function __fish_emulaunch_wants_device
+    set -l sub
+    set -l positionals 0
+    set -l skip 0
+    for token in (commandline -opc)[2..-1]
+        if test $skip = 1
+            set skip 0
+            continue
+        end
+        switch $token
+            case @VALUE_OPTIONS@
+                set skip 1
+            case '-*'
+            case '*'
+                if test -z "$sub"
+                    set sub $token
+                else
+                    set positionals (math $positionals + 1)
+                end
+        end
+    end
+    contains -- "$sub" @MULTI@
+    and return 0
+    contains -- "$sub" @SINGLE@
+    and test $positionals -eq 0
+end
+
+complete -c emulaunch -n "__fish_emulaunch_wants_device" -f -a "(emulaunch __complete-names)"
+"#;
+
+// Inserted into a subcommand's case; names with spaces are single-quoted
+const POWERSHELL_NAMES: &str = r#"            emulaunch __complete-names 2>$null | ForEach-Object {
+                $text = if ($_ -match '\s') { "'" + ($_ -replace "'", "''") + "'" } else { $_ }
+                [CompletionResult]::new($text, $_, [CompletionResultType]::ParameterValue, $_)
+            }
+"#;
+
+fn fill(template: &str, subcommands: &DeviceSubcommands, separator: &str) -> String {
+  template
+    .replace(
+      "@VALUE_OPTIONS@",
+      &subcommands.value_options.join(separator),
+    )
+    .replace("@MULTI@", &subcommands.multi_word.join(separator))
+    .replace("@SINGLE@", &subcommands.single_word.join(separator))
+}
+
+/// The completion script for `shell`, including device name completion
+/// for bash, zsh, fish and powershell
+pub fn script(shell: Shell, command: &mut clap::Command) -> String {
+  let mut out = Vec::new();
+  clap_complete::generate(shell, command, BIN_NAME, &mut out);
+  let script = String::from_utf8_lossy(&out).into_owned();
+  let subcommands = device_subcommands(command);
+
+  match shell {
+    Shell::Bash => script + &fill(BASH_NAMES, &subcommands, "|"),
+    Shell::Fish => script + &fill(FISH_NAMES, &subcommands, " "),
+    Shell::Zsh => {
+      let script = script.replace(":' \\\n", ":_emulaunch_device_names' \\\n");
+      // Define the function before the trailing `compdef`/dispatch block
+      match script.rfind("\nif [ \"$funcstack[1]\"") {
+        Some(at) => format!("{}{}{}", &script[..at], ZSH_NAMES, &script[at..]),
+        None => script + ZSH_NAMES,
+      }
+    }
+    Shell::PowerShell => {
+      let mut script = script.replace(
+        "$_.CompletionText -like \"$wordToComplete*\"",
+        "$_.CompletionText -like \"$wordToComplete*\" -or $_.ListItemText -like \"$wordToComplete*\"",
+      );
+      for sub in subcommands
+        .multi_word
+        .iter()
+        .chain(&subcommands.single_word)
+      {
+        let case = format!("        '{};{}' {{\n", BIN_NAME, sub);
+        script = script.replace(&case, &format!("{}{}", case, POWERSHELL_NAMES));
+      }
+      script
+    }
+    _ => script,
+  }
+}
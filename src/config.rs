@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
 pub struct Config {
@@ -10,9 +11,132 @@ pub struct Config {
   #[serde(default)]
   pub xcrun_cmd: Option<String>,
   #[serde(default)]
+  pub avdmanager_cmd: Option<String>,
+  /// Genymotion Desktop's command-line tool; its devices are listed with the AVDs
+  #[serde(default)]
+  pub gmtool_cmd: Option<String>,
+  #[serde(default)]
   pub theme: Option<String>,
   #[serde(default)]
   pub theme_overrides: Option<ThemeOverrides>,
+  /// `"explicit"` (default), `"auto"` or `"implicit"` filter focus in the TUI
+  #[serde(default)]
+  pub filter_focus: Option<String>,
+  /// Initial TUI sort order: `"state"` (default), `"name"` or `"recent"`
+  #[serde(default)]
+  pub sort: Option<String>,
+  /// Start the TUI with the Android section hidden (`a` shows it)
+  #[serde(default)]
+  pub hide_android: Option<bool>,
+  /// Start the TUI with the iOS section hidden (`s` shows it)
+  #[serde(default)]
+  pub hide_ios: Option<bool>,
+  /// TUI sections that start collapsed: `"recent"`, `"android"`, `"ios"`
+  #[serde(default)]
+  pub collapsed_sections: Option<Vec<String>>,
+  /// Open the TUI without checking Android device states first
+  #[serde(default)]
+  pub fast_mode: Option<bool>,
+  /// Draw the TUI below the prompt instead of on the alternate screen
+  #[serde(default)]
+  pub inline: Option<bool>,
+  /// Seconds before a simctl call is killed and retried
+  #[serde(default)]
+  pub simctl_timeout_secs: Option<u64>,
+  /// How many times a failed or timed-out simctl call is retried
+  #[serde(default)]
+  pub simctl_retries: Option<u32>,
+  /// Most devices the TUI list shows at once; 0 shows all
+  #[serde(default)]
+  pub max_devices: Option<usize>,
+  /// Moving past the end of the TUI list continues at the other end
+  /// (default true)
+  #[serde(default)]
+  pub wrap_navigation: Option<bool>,
+  /// Nerd Font platform and state icons in front of each device (default
+  /// false, as other fonts show them as boxes)
+  #[serde(default)]
+  pub icons: Option<bool>,
+  /// Last launched devices repeated in a Recent section atop the TUI list
+  /// (default 3, 0 for none)
+  #[serde(default)]
+  pub recent_devices: Option<usize>,
+  /// Bring up Simulator.app after booting an iOS simulator (default true)
+  #[serde(default)]
+  pub ios_open_gui: Option<bool>,
+  /// Check GitHub for a newer release once a day (off by default)
+  #[serde(default)]
+  pub update_check: Option<bool>,
+  /// Ring the terminal bell in the TUI when a device launched this session
+  /// finishes booting
+  #[serde(default)]
+  pub bell_on_boot: Option<bool>,
+  /// Also ring for every state change of any device
+  #[serde(default)]
+  pub bell_on_any_change: Option<bool>,
+  /// Run this program with the device name instead of ringing the bell
+  #[serde(default)]
+  pub notify_cmd: Option<String>,
+  /// Reopen the TUI with the last run's filter and selection
+  #[serde(default)]
+  pub restore_session: Option<bool>,
+  /// Most Android emulators `open` boots at once; the rest wait their turn
+  /// (default 1, 0 for no limit)
+  #[serde(default)]
+  pub max_parallel_boots: Option<usize>,
+  /// Seconds between automatic re-listings in the TUI; 0 turns them off
+  #[serde(default)]
+  pub refresh_interval_secs: Option<u64>,
+  /// Record launches, boots, stops and TUI sessions for `emulaunch stats`
+  #[serde(default)]
+  pub metrics: Option<bool>,
+  /// Extra `emulator` arguments for every AVD launch, passed verbatim
+  #[serde(default)]
+  pub android_launch_args: Option<Vec<String>>,
+  /// TUI key overrides, keyed by action (`[keys]`, `quit = ["q", "ctrl-c"]`)
+  #[serde(default)]
+  pub keys: Option<BTreeMap<String, KeySpec>>,
+  /// Per-device settings, keyed by AVD id (`[devices.Pixel_7]`)
+  #[serde(default)]
+  pub devices: Option<HashMap<String, DeviceConfig>>,
+  /// Keys that aren't settings, kept so `config lint` can report them
+  #[serde(flatten, skip_serializing_if = "BTreeMap::is_empty")]
+  pub unknown: BTreeMap<String, toml::Value>,
+}
+
+/// The keys for one action in `[keys]`: one descriptor or a list of them
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum KeySpec {
+  One(String),
+  Many(Vec<String>),
+}
+
+impl KeySpec {
+  pub fn descriptors(&self) -> Vec<&str> {
+    match self {
+      KeySpec::One(desc) => vec![desc.as_str()],
+      KeySpec::Many(descs) => descs.iter().map(String::as_str).collect(),
+    }
+  }
+}
+
+/// Settings for one device
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+pub struct DeviceConfig {
+  /// Launch the emulator fullscreen (Android only)
+  #[serde(default)]
+  pub fullscreen: Option<bool>,
+  /// Launch with the emulator window hidden (Android only)
+  #[serde(default)]
+  pub hide_window: Option<bool>,
+  /// Extra `emulator` arguments after the global `android_launch_args`
+  /// (Android only)
+  #[serde(default)]
+  pub launch_args: Option<Vec<String>>,
+  /// Keys that aren't settings, kept so `config lint` can report them
+  #[serde(flatten, skip_serializing_if = "BTreeMap::is_empty")]
+  pub unknown: BTreeMap<String, toml::Value>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
@@ -34,11 +158,45 @@ pub struct ThemeOverrides {
   #[serde(default)]
   pub filter_active_fg: Option<String>,
   #[serde(default)]
+  pub filter_mode_fg: Option<String>,
+  #[serde(default)]
   pub selection_bg: Option<String>,
   #[serde(default)]
   pub help_key_fg: Option<String>,
   #[serde(default)]
   pub help_text_fg: Option<String>,
+  #[serde(default)]
+  pub icon_android_fg: Option<String>,
+  #[serde(default)]
+  pub icon_ios_fg: Option<String>,
+  /// Keys that aren't a slot (usually typos), kept so they can be reported
+  #[serde(flatten, skip_serializing_if = "BTreeMap::is_empty")]
+  pub unknown: BTreeMap<String, toml::Value>,
+}
+
+impl ThemeOverrides {
+  /// Every slot by name, with its configured value
+  pub fn slots(&self) -> [(&'static str, Option<&str>); 14] {
+    [
+      ("header_fg", self.header_fg.as_deref()),
+      ("name_fg", self.name_fg.as_deref()),
+      ("state_booted_fg", self.state_booted_fg.as_deref()),
+      ("state_shutdown_fg", self.state_shutdown_fg.as_deref()),
+      ("state_unknown_fg", self.state_unknown_fg.as_deref()),
+      ("meta_fg", self.meta_fg.as_deref()),
+      (
+        "filter_placeholder_fg",
+        self.filter_placeholder_fg.as_deref(),
+      ),
+      ("filter_active_fg", self.filter_active_fg.as_deref()),
+      ("filter_mode_fg", self.filter_mode_fg.as_deref()),
+      ("selection_bg", self.selection_bg.as_deref()),
+      ("help_key_fg", self.help_key_fg.as_deref()),
+      ("help_text_fg", self.help_text_fg.as_deref()),
+      ("icon_android_fg", self.icon_android_fg.as_deref()),
+      ("icon_ios_fg", self.icon_ios_fg.as_deref()),
+    ]
+  }
 }
 
 #[derive(Debug)]
@@ -49,6 +207,12 @@ pub enum CommandNotFoundError {
   Adb {
     suggestion: String,
   },
+  Avdmanager {
+    suggestion: String,
+  },
+  Gmtool {
+    suggestion: String,
+  },
   #[cfg(target_os = "macos")]
   Xcrun {
     suggestion: String,
@@ -66,6 +230,14 @@ impl std::fmt::Display for CommandNotFoundError {
         write!(f, "ADB command not found. {}\n\nPlease configure it in your config file:\n{}\n\nOr set the ADB_CMD environment variable.",
                        suggestion, get_config_paths_display())
       }
+      CommandNotFoundError::Avdmanager { suggestion } => {
+        write!(f, "avdmanager command not found. {}\n\nPlease configure it in your config file:\n{}\n\nOr set the AVDMANAGER_CMD environment variable.",
+                       suggestion, get_config_paths_display())
+      }
+      CommandNotFoundError::Gmtool { suggestion } => {
+        write!(f, "gmtool command not found. {}\n\nPlease configure it in your config file:\n{}\n\nOr set the GMTOOL_CMD environment variable.",
+                       suggestion, get_config_paths_display())
+      }
       #[cfg(target_os = "macos")]
       CommandNotFoundError::Xcrun { suggestion } => {
         write!(f, "xcrun command not found. {}\n\nPlease configure it in your config file:\n{}\n\nOr set the XCRUN_CMD environment variable.",
@@ -78,61 +250,206 @@ impl std::fmt::Display for CommandNotFoundError {
 impl std::error::Error for CommandNotFoundError {}
 
 // Error message constants
-const SUGGESTION_ANDROID_SDK: &str = "Install Android SDK or add it to PATH.\n\
+const SUGGESTION_ANDROID_SDK: &str = "Install Android SDK and set ANDROID_HOME, or add the emulator to PATH.\n\
 Common locations:\n  macOS: ~/Library/Android/sdk/emulator/emulator\n  Linux: ~/Android/Sdk/emulator/emulator\n  Windows: %LOCALAPPDATA%\\Android\\Sdk\\emulator\\emulator.exe";
-const SUGGESTION_ADB: &str = "Install Android SDK Platform-Tools or add it to PATH.\n\
+const SUGGESTION_ADB: &str = "Install Android SDK Platform-Tools and set ANDROID_HOME, or add adb to PATH.\n\
 Common locations:\n  macOS: ~/Library/Android/sdk/platform-tools/adb\n  Linux: ~/Android/Sdk/platform-tools/adb\n  Windows: %LOCALAPPDATA%\\Android\\Sdk\\platform-tools\\adb.exe";
+const SUGGESTION_AVDMANAGER: &str = "Install the Android SDK Command-line Tools (SDK Manager > SDK Tools) or add avdmanager to PATH.\n\
+Common locations:\n  macOS: ~/Library/Android/sdk/cmdline-tools/latest/bin/avdmanager\n  Linux: ~/Android/Sdk/cmdline-tools/latest/bin/avdmanager\n  Windows: %LOCALAPPDATA%\\Android\\Sdk\\cmdline-tools\\latest\\bin\\avdmanager.bat";
+const SUGGESTION_GMTOOL: &str = "Install Genymotion Desktop or add its gmtool to PATH.\n\
+Common locations:\n  macOS: /Applications/Genymotion.app/Contents/MacOS/gmtool\n  Linux: ~/genymotion/gmtool\n  Windows: C:\\Program Files\\Genymobile\\Genymotion\\gmtool.exe";
 #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
 const SUGGESTION_XCRUN: &str = "Install Xcode Command Line Tools: xcode-select --install";
 
-/// Platform-specific Android SDK paths
-fn get_android_emulator_paths() -> Vec<PathBuf> {
-  let home = match dirs::home_dir() {
-    Some(h) => h,
-    None => return Vec::new(),
-  };
+pub const ERR_NO_HOME: &str = "home directory could not be determined";
 
-  #[cfg(target_os = "macos")]
-  return vec![home.join("Library/Android/sdk/emulator/emulator")];
+// Devices the Recent section lists unless `recent_devices` says otherwise
+const DEFAULT_RECENT_DEVICES: usize = 3;
 
-  #[cfg(target_os = "linux")]
-  return vec![home.join("Android/Sdk/emulator/emulator")];
+/// Resolve the user's home directory.
+///
+/// Tries `dirs::home_dir()`, then the `HOME` and `USERPROFILE` environment
+/// variables, so every consumer agrees on one answer.
+pub fn home_dir() -> Option<PathBuf> {
+  home_dir_from(dirs::home_dir(), |key| std::env::var_os(key))
+}
 
-  #[cfg(target_os = "windows")]
-  return vec![home.join("AppData/Local/Android/Sdk/emulator/emulator.exe")];
+/// `home_dir` with injectable sources, so resolution can be exercised without
+/// touching the process environment
+fn home_dir_from<F>(dirs_home: Option<PathBuf>, env_var: F) -> Option<PathBuf>
+where
+  F: Fn(&str) -> Option<std::ffi::OsString>,
+{
+  dirs_home
+    .or_else(|| env_var("HOME").map(PathBuf::from))
+    .or_else(|| env_var("USERPROFILE").map(PathBuf::from))
+    .filter(|p| !p.as_os_str().is_empty())
+}
 
-  #[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
-  return Vec::new();
+/// `base` with each of `parts` joined on, so every separator is the OS's
+/// own rather than a `/` inside one component
+pub fn join_components(base: &Path, parts: &[&str]) -> PathBuf {
+  parts
+    .iter()
+    .fold(base.to_path_buf(), |path, part| path.join(part))
 }
 
-/// Platform-specific ADB paths
-fn get_adb_paths() -> Vec<PathBuf> {
-  let home = match dirs::home_dir() {
-    Some(h) => h,
-    None => return Vec::new(),
-  };
+/// Directory for emulaunch's own state (history), e.g. `~/.local/share/emulaunch`
+pub fn data_dir() -> Option<PathBuf> {
+  dirs::data_local_dir()
+    .map(|d| d.join("emulaunch"))
+    .or_else(|| home_dir().map(|h| h.join(".emulaunch")))
+}
 
-  #[cfg(target_os = "macos")]
-  return vec![home.join("Library/Android/sdk/platform-tools/adb")];
+// Environment variables naming the SDK root, in precedence order
+pub const SDK_ROOT_VARS: [&str; 2] = ["ANDROID_HOME", "ANDROID_SDK_ROOT"];
 
-  #[cfg(target_os = "linux")]
-  return vec![home.join("Android/Sdk/platform-tools/adb")];
+/// SDK roots from `ANDROID_HOME` and `ANDROID_SDK_ROOT`, with the variable
+/// each came from; values that aren't directories are left out
+fn env_sdk_roots() -> Vec<(PathBuf, &'static str)> {
+  let mut roots: Vec<(PathBuf, &'static str)> = Vec::new();
+  for var in SDK_ROOT_VARS {
+    if let Some(dir) = std::env::var_os(var)
+      .map(PathBuf::from)
+      .filter(|d| d.is_dir())
+    {
+      if !roots.iter().any(|(root, _)| *root == dir) {
+        roots.push((dir, var));
+      }
+    }
+  }
+  roots
+}
 
-  #[cfg(target_os = "windows")]
-  return vec![home.join("AppData/Local/Android/Sdk/platform-tools/adb.exe")];
+/// The Android SDK root: `ANDROID_HOME`, then `ANDROID_SDK_ROOT`, then the
+/// SDK containing the resolved emulator (`<sdk>/emulator/emulator`)
+pub fn android_sdk_root() -> Option<PathBuf> {
+  if let Some((dir, _)) = env_sdk_roots().into_iter().next() {
+    return Some(dir);
+  }
+  let emulator = PathBuf::from(get_android_emulator_cmd().ok()?);
+  let sdk = emulator.parent()?.parent()?;
+  sdk.join("emulator").is_dir().then(|| sdk.to_path_buf())
+}
 
-  #[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
-  return Vec::new();
+/// What the SDK root variables say, for `config show` and not-found errors:
+/// the roots searched, values that were ignored, or that neither is set
+fn describe_sdk_root_env() -> Vec<String> {
+  let set: Vec<(&str, PathBuf)> = SDK_ROOT_VARS
+    .iter()
+    .filter_map(|var| Some((*var, PathBuf::from(std::env::var_os(var)?))))
+    .collect();
+  if set.is_empty() {
+    return vec![format!("{} are not set", SDK_ROOT_VARS.join(" and "))];
+  }
+  set
+    .into_iter()
+    .map(|(var, dir)| {
+      if dir.is_dir() {
+        format!("{}={} (searched)", var, dir.display())
+      } else {
+        format!("{}={} is not a directory (ignored)", var, dir.display())
+      }
+    })
+    .collect()
+}
+
+/// `describe_sdk_root_env` as one paragraph of a not-found error
+fn sdk_root_note() -> String {
+  format!("SDK root: {}", describe_sdk_root_env().join("; "))
+}
+
+/// Where Android Studio installs the SDK on this OS
+fn default_sdk_dir(home: &Path) -> PathBuf {
+  if cfg!(target_os = "macos") {
+    join_components(home, &["Library", "Android", "sdk"])
+  } else if cfg!(target_os = "windows") {
+    join_components(home, &["AppData", "Local", "Android", "Sdk"])
+  } else {
+    join_components(home, &["Android", "Sdk"])
+  }
+}
+
+/// SDK roots to look for tools in: the SDK root variables, then the
+/// default install location
+fn sdk_roots() -> Vec<PathBuf> {
+  let mut roots: Vec<PathBuf> = env_sdk_roots().into_iter().map(|(dir, _)| dir).collect();
+  if let Some(home) = home_dir() {
+    let default = default_sdk_dir(&home);
+    if !roots.contains(&default) {
+      roots.push(default);
+    }
+  }
+  roots
+}
+
+/// `parts` under every SDK root, in search order
+fn sdk_paths(parts: &[&str]) -> Vec<PathBuf> {
+  sdk_roots()
+    .iter()
+    .map(|sdk| join_components(sdk, parts))
+    .collect()
+}
+
+/// Android SDK paths for the emulator
+fn get_android_emulator_paths() -> Vec<PathBuf> {
+  let exe = format!("emulator{}", std::env::consts::EXE_SUFFIX);
+  sdk_paths(&["emulator", &exe])
+}
+
+/// Android SDK paths for adb
+fn get_adb_paths() -> Vec<PathBuf> {
+  let exe = format!("adb{}", std::env::consts::EXE_SUFFIX);
+  sdk_paths(&["platform-tools", &exe])
+}
+
+/// Android SDK paths for avdmanager (current cmdline-tools, then the
+/// deprecated `tools` package)
+fn get_avdmanager_paths() -> Vec<PathBuf> {
+  let exe = if cfg!(target_os = "windows") {
+    "avdmanager.bat"
+  } else {
+    "avdmanager"
+  };
+  sdk_roots()
+    .iter()
+    .flat_map(|sdk| {
+      [
+        join_components(sdk, &["cmdline-tools", "latest", "bin", exe]),
+        join_components(sdk, &["tools", "bin", exe]),
+      ]
+    })
+    .collect()
+}
+
+/// Where the Genymotion Desktop installers put gmtool
+fn get_gmtool_paths() -> Vec<PathBuf> {
+  let mut paths = Vec::new();
+  if cfg!(target_os = "macos") {
+    paths.push(PathBuf::from(
+      "/Applications/Genymotion.app/Contents/MacOS/gmtool",
+    ));
+  } else if cfg!(target_os = "windows") {
+    paths.push(PathBuf::from(
+      r"C:\Program Files\Genymobile\Genymotion\gmtool.exe",
+    ));
+  } else {
+    if let Some(home) = home_dir() {
+      paths.push(home.join("genymotion").join("gmtool"));
+    }
+    paths.push(PathBuf::from("/opt/genymobile/genymotion/gmtool"));
+  }
+  paths
 }
 
 pub fn get_config_paths() -> Vec<PathBuf> {
   let mut paths = Vec::new();
 
-  if let Some(home_dir) = dirs::home_dir() {
+  if let Some(home) = home_dir() {
     // XDG-style path (~/.config/emulaunch/config.toml)
     // Checked explicitly because dirs::config_dir() returns
     // ~/Library/Application Support on macOS, not ~/.config
-    paths.push(home_dir.join(".config").join("emulaunch").join("config.toml"));
+    paths.push(home.join(".config").join("emulaunch").join("config.toml"));
 
     // Platform config dir (~/Library/Application Support on macOS, etc.)
     if let Some(config_dir) = dirs::config_dir() {
@@ -143,7 +460,7 @@ pub fn get_config_paths() -> Vec<PathBuf> {
     }
 
     // Legacy fallback
-    paths.push(home_dir.join(".emulaunch").join("config.toml"));
+    paths.push(home.join(".emulaunch").join("config.toml"));
   }
 
   paths
@@ -168,21 +485,589 @@ pub fn load_config() -> Option<Config> {
   None
 }
 
-fn command_exists(cmd: &str) -> bool {
+/// Problems in the loaded config that don't stop it from loading, such as
+/// misspelled theme slots; shown on stderr by commands and as a toast in
+/// the TUI
+pub fn warnings() -> Vec<String> {
+  let Some(config) = load_config() else {
+    return Vec::new();
+  };
+  let mut warnings = config
+    .theme_overrides
+    .map(|ov| crate::theme::validate_overrides(&ov))
+    .unwrap_or_default();
+  if config.keys.is_some() {
+    warnings.extend(
+      crate::keys::KeyMap::build(config.keys.as_ref())
+        .1
+        .iter()
+        .map(ToString::to_string),
+    );
+  }
+  warnings
+}
+
+/// The `[devices.<id>]` settings for a device, or defaults when it has none
+pub fn device_config(id: &str) -> DeviceConfig {
+  load_config()
+    .and_then(|c| c.devices?.remove(id))
+    .unwrap_or_default()
+}
+
+/// Configured `emulator` arguments for an AVD: the global
+/// `android_launch_args`, then its `[devices.<id>]` `launch_args`
+pub fn android_launch_args(id: &str) -> Vec<String> {
+  let Some(mut config) = load_config() else {
+    return Vec::new();
+  };
+  let device = config
+    .devices
+    .as_mut()
+    .and_then(|devices| devices.remove(id))
+    .and_then(|d| d.launch_args);
+  config
+    .android_launch_args
+    .into_iter()
+    .chain(device)
+    .flatten()
+    .collect()
+}
+
+/// Whether booting an iOS simulator also opens Simulator.app
+pub fn ios_open_gui() -> bool {
+  load_config().and_then(|c| c.ios_open_gui).unwrap_or(true)
+}
+
+/// Whether TUI navigation wraps around the ends of the list
+pub fn wrap_navigation() -> bool {
+  load_config()
+    .and_then(|c| c.wrap_navigation)
+    .unwrap_or(true)
+}
+
+/// Whether device rows start with Nerd Font icons
+pub fn icons() -> bool {
+  load_config().and_then(|c| c.icons).unwrap_or(false)
+}
+
+/// How many devices the TUI's Recent section lists; 0 leaves it out
+pub fn recent_devices() -> usize {
+  load_config()
+    .and_then(|c| c.recent_devices)
+    .unwrap_or(DEFAULT_RECENT_DEVICES)
+}
+
+/// How many emulators may boot at once when `open` is given several;
+/// `None` for no limit. Simulators are never queued.
+pub fn max_parallel_boots() -> Option<usize> {
+  match load_config().and_then(|c| c.max_parallel_boots) {
+    Some(0) => None,
+    Some(max) => Some(max),
+    None => Some(1),
+  }
+}
+
+/// Whether usage is recorded for `emulaunch stats` (off by default)
+pub fn metrics_enabled() -> bool {
+  load_config().and_then(|c| c.metrics).unwrap_or(false)
+}
+
+/// When the TUI should get the user's attention about a state change
+#[derive(Debug, Clone, Default)]
+pub struct BootNotify {
+  /// Devices launched this session reaching Booted
+  pub on_boot: bool,
+  /// Any state change of any device
+  pub on_any_change: bool,
+  /// Run instead of ringing the bell, with the device name as its argument
+  pub command: Option<String>,
+}
+
+/// The `bell_on_boot`, `bell_on_any_change` and `notify_cmd` settings
+pub fn boot_notify() -> BootNotify {
+  let config = load_config().unwrap_or_default();
+  BootNotify {
+    on_boot: config.bell_on_boot.unwrap_or(false),
+    on_any_change: config.bell_on_any_change.unwrap_or(false),
+    command: config.notify_cmd.filter(|c| !c.trim().is_empty()),
+  }
+}
+
+/// Whether a config file exists at any of the searched locations
+pub fn config_file_exists() -> bool {
+  active_config_path().is_some()
+}
+
+/// The config file that is loaded: the first searched location that exists
+pub fn active_config_path() -> Option<PathBuf> {
+  get_config_paths().into_iter().find(|p| p.exists())
+}
+
+/// Where a new config file is created: the first searched location
+pub fn preferred_config_path() -> Option<PathBuf> {
+  get_config_paths().into_iter().next()
+}
+
+/// Whether none of the Android or iOS tools can be resolved
+pub fn no_tools_resolved() -> bool {
+  #[cfg(target_os = "macos")]
+  if get_xcrun_cmd().is_ok() {
+    return false;
+  }
+  get_android_emulator_cmd().is_err() && get_adb_cmd().is_err()
+}
+
+/// Human-readable list of the places searched for tools
+pub fn searched_locations() -> Vec<String> {
+  let mut locations = vec![
+    "ANDROID_EMULATOR_CMD and ADB_CMD environment variables".to_string(),
+    "emulator and adb on PATH".to_string(),
+  ];
+  locations.extend(
+    get_android_emulator_paths()
+      .into_iter()
+      .chain(get_adb_paths())
+      .map(|p| p.display().to_string()),
+  );
+  #[cfg(target_os = "macos")]
+  locations.push("xcrun on PATH".to_string());
+  locations
+}
+
+/// Android tools found from a path the user typed
+#[derive(Debug, Clone, Default)]
+pub struct DetectedTools {
+  pub android_emulator_cmd: Option<String>,
+  pub adb_cmd: Option<String>,
+}
+
+/// Detect the emulator and adb from an SDK directory, one of the tool
+/// executables, or a command name on PATH
+pub fn detect_tools(input: &str) -> DetectedTools {
+  let input = input.trim();
+  let mut tools = DetectedTools::default();
+  if input.is_empty() {
+    return tools;
+  }
+
+  let path = match input.strip_prefix("~/") {
+    Some(rest) => home_dir().map_or_else(|| PathBuf::from(input), |h| h.join(rest)),
+    None => PathBuf::from(input),
+  };
+  let exe = |name: &str| format!("{}{}", name, std::env::consts::EXE_SUFFIX);
+  let found = |p: PathBuf| p.is_file().then(|| p.to_string_lossy().to_string());
+
+  // A tool executable: look for its sibling under the same SDK root
+  let sdk_root = if path.is_file() {
+    let name = path
+      .file_name()
+      .and_then(|n| n.to_str())
+      .unwrap_or_default();
+    if name == exe("adb") {
+      tools.adb_cmd = found(path.clone());
+    } else if name == exe("emulator") {
+      tools.android_emulator_cmd = found(path.clone());
+    }
+    path.parent().and_then(|p| p.parent()).map(PathBuf::from)
+  } else if path.is_dir() {
+    Some(path.clone())
+  } else {
+    None
+  };
+
+  if let Some(root) = sdk_root {
+    if tools.android_emulator_cmd.is_none() {
+      tools.android_emulator_cmd = found(root.join("emulator").join(exe("emulator")))
+        .or_else(|| found(root.join(exe("emulator"))));
+    }
+    if tools.adb_cmd.is_none() {
+      tools.adb_cmd = found(root.join("platform-tools").join(exe("adb")))
+        .or_else(|| found(root.join(exe("adb"))));
+    }
+  } else if command_exists(input) {
+    match input {
+      "adb" => tools.adb_cmd = Some(input.to_string()),
+      "emulator" => tools.android_emulator_cmd = Some(input.to_string()),
+      _ => {}
+    }
+  }
+
+  tools
+}
+
+/// Contents of a starter config, with detected tool paths filled in
+pub fn starter_config(tools: &DetectedTools) -> String {
+  let line = |key: &str, value: &Option<String>| match value {
+    Some(v) => format!("{} = {}\n", key, toml::Value::String(v.clone())),
+    None => format!("# {} = \"{}\"\n", key, key.trim_end_matches("_cmd")),
+  };
+  let mut contents = String::from(
+    "# emulaunch configuration\n\
+     # Command paths (optional — auto-detected by default)\n",
+  );
+  contents.push_str(&line("android_emulator_cmd", &tools.android_emulator_cmd));
+  contents.push_str(&line("adb_cmd", &tools.adb_cmd));
+  contents.push_str("# xcrun_cmd = \"xcrun\"  # macOS only\n\n# theme = \"default\"\n");
+  contents
+}
+
+/// Create a starter config at the preferred path, refusing to overwrite one
+pub fn init_config(tools: &DetectedTools) -> Result<PathBuf, String> {
+  write_config(&starter_config(tools), false)
+}
+
+/// Every setting, commented out, as written by `config init`
+const EXAMPLE_CONFIG: &str = r##"# emulaunch configuration
+# Every setting is optional; remove the leading `#` to change one.
+
+# Command paths (auto-detected by default)
+# android_emulator_cmd = "emulator"
+# adb_cmd = "adb"
+# xcrun_cmd = "xcrun"  # macOS only
+# avdmanager_cmd = "avdmanager"  # used by `create android`
+# gmtool_cmd = "gmtool"  # Genymotion Desktop, listed when found
+
+# default, catppuccin-mocha, catppuccin-latte, dracula, tokyo-night, gruvbox-dark, nord
+# theme = "default"
+
+# Filter focus in the TUI: "explicit", "auto" (typing an unbound key starts
+# the filter too) or "implicit"
+# filter_focus = "explicit"
+
+# How the TUI orders devices within each section: "state" (booted first),
+# "name" or "recent" (last launched first); `o` cycles through them
+# sort = "state"
+
+# Start the TUI with a section hidden; `a` and `s` toggle them while it runs.
+# A hidden platform isn't listed until it is shown
+# hide_android = false
+# hide_ios = false
+
+# Sections that start collapsed to their header ("recent", "android", "ios");
+# Enter, Space or h/l on a header fold them while the TUI runs
+# collapsed_sections = ["ios"]
+
+# Always open the TUI in fast mode (same as --fast)
+# fast_mode = false
+
+# Draw the TUI in 15 rows below the prompt, keeping the scrollback in view,
+# instead of on the whole screen (same as --inline)
+# inline = false
+
+# simctl calls that hang or fail are killed after this many seconds and retried
+# simctl_timeout_secs = 60
+# simctl_retries = 1
+
+# Most devices the TUI lists at once; 0 lists all
+# max_devices = 200
+
+# Moving down from the last device selects the first, and up from the first
+# selects the last
+# wrap_navigation = true
+
+# Start each device row with its platform's icon and a booted/shut down dot.
+# Needs a Nerd Font (https://www.nerdfonts.com); other fonts show boxes
+# icons = false
+
+# The last launched devices are repeated in a Recent section at the top of
+# the TUI list; 0 leaves it out
+# recent_devices = 3
+
+# Open Simulator.app after booting an iOS simulator
+# ios_open_gui = true
+
+# Check GitHub for a newer release once a day
+# update_check = false
+
+# Ring the terminal bell when a device launched from the TUI finishes booting;
+# bell_on_any_change rings for every state change; notify_cmd runs a program
+# with the device name instead of ringing
+# bell_on_boot = false
+# bell_on_any_change = false
+# notify_cmd = "/path/to/notify"
+
+# Per-slot color overrides using hex values
+# [theme_overrides]
+# selection_bg = "#313244"
+
+# Reopen the TUI with the filter and selection it was closed with
+# restore_session = false
+
+# Android emulators `open` boots at once when given several (0 for no limit);
+# the rest start as earlier ones finish booting
+# max_parallel_boots = 1
+
+# Seconds between automatic device re-listings in the TUI (0 turns them off)
+# refresh_interval_secs = 5
+
+# Record launches, boot times and stops in the data dir for `emulaunch stats`
+# metrics = false
+
+# Extra emulator arguments for every AVD, one array item per argument
+# android_launch_args = ["-gpu", "host"]
+
+# TUI keys per action, replacing that action's defaults (one key or a list)
+# [keys]
+# quit = ["ctrl-c", "esc"]
+# down = ["ctrl-n", "down"]
+
+# Per-AVD settings, keyed by AVD id; launch_args follow android_launch_args
+# [devices.Pixel_7]
+# fullscreen = true
+# launch_args = ["-dns-server", "8.8.8.8"]
+"##;
+
+/// Save `theme = "<name>"` to the config file, creating it if needed. Only
+/// that line changes: an existing top-level `theme` is replaced, then a
+/// commented-out one, otherwise it goes before the first table. A file that
+/// can't be read, or where that edit would change anything else, is left alone.
+pub fn save_theme(name: &str) -> Result<PathBuf, String> {
+  let path = active_config_path()
+    .or_else(preferred_config_path)
+    .ok_or_else(|| ERR_NO_HOME.to_string())?;
+  let contents = match std::fs::read_to_string(&path) {
+    Ok(contents) => contents,
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+    Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+  };
+  let updated = set_top_level_key(&contents, "theme", &format!("{:?}", name));
+  // The edit is line-based; make sure it changed that key and nothing else
+  let parse = |s: &str| s.parse::<toml::Table>();
+  let mut expected = parse(&contents).map_err(|e| {
+    format!(
+      "{} is not valid TOML, so it was left alone: {}",
+      path.display(),
+      e
+    )
+  })?;
+  expected.insert("theme".to_string(), toml::Value::String(name.to_string()));
+  if parse(&updated).ok() != Some(expected) {
+    return Err(format!(
+      "Couldn't set the theme in {} without touching other settings; add theme = {:?} by hand",
+      path.display(),
+      name
+    ));
+  }
+  if let Some(dir) = path.parent() {
+    std::fs::create_dir_all(dir)
+      .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+  }
+  crate::filelock::write_atomic(&path, &updated)?;
+  Ok(path)
+}
+
+/// `contents` with the top-level `key` set to `value` (already TOML)
+fn set_top_level_key(contents: &str, key: &str, value: &str) -> String {
+  let assigns = |line: &str| {
+    line
+      .trim_start()
+      .strip_prefix(key)
+      .is_some_and(|rest| rest.trim_start().starts_with('='))
+  };
+  let mut lines: Vec<String> = contents.lines().map(str::to_string).collect();
+  // Everything before the first `[table]` is top level
+  let top = lines
+    .iter()
+    .position(|l| l.trim_start().starts_with('['))
+    .unwrap_or(lines.len());
+  let new_line = format!("{} = {}", key, value);
+  let existing = lines[..top].iter().position(|l| assigns(l)).or_else(|| {
+    lines[..top]
+      .iter()
+      .position(|l| l.trim_start().strip_prefix('#').is_some_and(assigns))
+  });
+  match existing {
+    Some(i) => lines[i] = new_line,
+    None if top < lines.len() => {
+      lines.insert(top, String::new());
+      lines.insert(top, new_line);
+    }
+    None => lines.push(new_line),
+  }
+  let mut out = lines.join("\n");
+  out.push('\n');
+  out
+}
+
+/// Write the example config to the preferred path (`config init`)
+pub fn init_example_config(force: bool) -> Result<String, String> {
+  let path = write_config(EXAMPLE_CONFIG, force)?;
+  Ok(format!("Wrote {}", path.display()))
+}
+
+fn write_config(contents: &str, force: bool) -> Result<PathBuf, String> {
+  let path = preferred_config_path().ok_or_else(|| ERR_NO_HOME.to_string())?;
+  if path.exists() && !force {
+    return Err(format!(
+      "Config file already exists: {} (pass --force to overwrite)",
+      path.display()
+    ));
+  }
+  if let Some(dir) = path.parent() {
+    std::fs::create_dir_all(dir)
+      .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+  }
+  std::fs::write(&path, contents)
+    .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+  Ok(path)
+}
+
+/// The searched config locations in order, marking the one that is loaded
+/// and any existing ones it shadows (`config paths`)
+pub fn describe_config_paths() -> String {
+  let active = active_config_path();
+  get_config_paths()
+    .iter()
+    .map(|path| {
+      let note = if Some(path) == active.as_ref() {
+        "  (loaded)"
+      } else if path.exists() {
+        "  (ignored; an earlier file is loaded)"
+      } else {
+        ""
+      };
+      format!("{}{}", path.display(), note)
+    })
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+/// The loaded config file, its settings and where each tool resolves to
+/// (`config show`)
+pub fn describe_config() -> String {
+  let mut lines = Vec::new();
+  let config = match active_config_path() {
+    None => {
+      lines.push("Config file: none (using defaults)".to_string());
+      None
+    }
+    Some(path) => {
+      lines.push(format!("Config file: {}", path.display()));
+      let parsed = std::fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| toml::from_str::<Config>(&contents).map_err(|e| e.to_string()));
+      match parsed {
+        Ok(config) => {
+          let settings = toml::to_string(&config).unwrap_or_default();
+          lines.push(String::new());
+          if settings.trim().is_empty() {
+            lines.push("(no settings)".to_string());
+          } else {
+            lines.push(settings.trim_end().to_string());
+          }
+          if let Some(ov) = &config.theme_overrides {
+            for warning in crate::theme::validate_overrides(ov) {
+              lines.push(format!("Warning: {}", warning));
+            }
+          }
+          Some(config)
+        }
+        Err(e) => {
+          // load_config() ignores a file it can't parse; say so here
+          lines.push(format!(
+            "Error: file can't be parsed and is ignored: {}",
+            e.trim()
+          ));
+          None
+        }
+      }
+    }
+  };
+  let config = config.unwrap_or_default();
+
+  lines.push(String::new());
+  lines.push("Android SDK:".to_string());
+  lines.extend(
+    describe_sdk_root_env()
+      .into_iter()
+      .map(|line| format!("  {}", line)),
+  );
+  lines.push(match android_sdk_root() {
+    Some(root) => format!("  using {}", root.display()),
+    None => "  not found".to_string(),
+  });
+
+  lines.push(String::new());
+  lines.push("Resolved commands:".to_string());
+  #[cfg_attr(not(target_os = "macos"), allow(unused_mut))]
+  let mut tools = vec![
+    (
+      "emulator",
+      config.android_emulator_cmd.as_deref(),
+      "ANDROID_EMULATOR_CMD",
+      get_android_emulator_cmd(),
+    ),
+    ("adb", config.adb_cmd.as_deref(), "ADB_CMD", get_adb_cmd()),
+    (
+      "avdmanager",
+      config.avdmanager_cmd.as_deref(),
+      "AVDMANAGER_CMD",
+      get_avdmanager_cmd(),
+    ),
+    (
+      "gmtool",
+      config.gmtool_cmd.as_deref(),
+      "GMTOOL_CMD",
+      get_gmtool_cmd(),
+    ),
+  ];
+  #[cfg(target_os = "macos")]
+  tools.push((
+    "xcrun",
+    config.xcrun_cmd.as_deref(),
+    "XCRUN_CMD",
+    get_xcrun_cmd(),
+  ));
+  for (name, configured, env_var, resolved) in tools {
+    lines.extend(describe_tool(name, configured, env_var, resolved));
+  }
+  lines.join("\n")
+}
+
+/// One tool's resolved command and where it came from, plus a note when a
+/// configured value was passed over
+fn describe_tool(
+  name: &str,
+  configured: Option<&str>,
+  env_var: &str,
+  resolved: Result<String, CommandNotFoundError>,
+) -> Vec<String> {
+  let mut lines = vec![match &resolved {
+    Ok(cmd) if Some(cmd.as_str()) == configured => format!("  {:<11} {} (config)", name, cmd),
+    Ok(cmd) if std::env::var(env_var).is_ok_and(|v| &v == cmd) => {
+      format!("  {:<11} {} ({})", name, cmd, env_var)
+    }
+    Ok(cmd) => match env_sdk_roots()
+      .into_iter()
+      .find(|(root, _)| Path::new(cmd).starts_with(root))
+    {
+      Some((_, var)) => format!("  {:<11} {} ({})", name, cmd, var),
+      None => format!("  {:<11} {} (auto-detected)", name, cmd),
+    },
+    Err(_) => format!("  {:<11} not found", name),
+  }];
+  if let Some(configured) = configured {
+    if resolved.as_deref().ok() != Some(configured) {
+      lines.push(format!(
+        "  {:<11} configured \"{}\" was skipped: not on PATH and no such file",
+        "", configured
+      ));
+    }
+  }
+  lines
+}
+
+pub fn command_exists(cmd: &str) -> bool {
   #[cfg(target_os = "windows")]
   {
-    std::process::Command::new("where")
-      .arg(cmd)
-      .output()
+    crate::runner::output(std::process::Command::new("where").arg(cmd))
       .map(|o| o.status.success())
       .unwrap_or(false)
   }
 
   #[cfg(not(target_os = "windows"))]
   {
-    std::process::Command::new("which")
-      .arg(cmd)
-      .output()
+    crate::runner::output(std::process::Command::new("which").arg(cmd))
       .map(|o| o.status.success())
       .unwrap_or(false)
   }
@@ -226,10 +1111,18 @@ where
     }
   }
 
-  // Fall back to simple command name
+  // Fall back to simple command name, unless it was looked for recently
+  if crate::probes::recently_missing(default_cmd) {
+    return Err(error_variant(
+      "Command not found in PATH or common locations (remembered from an earlier lookup; \
+       --reprobe looks again)"
+        .to_string(),
+    ));
+  }
   if command_exists(default_cmd) {
     return Ok(default_cmd.to_string());
   }
+  crate::probes::remember_missing(default_cmd);
 
   // Return error - caller provides specific suggestion
   Err(error_variant(
@@ -244,7 +1137,12 @@ pub fn get_android_emulator_cmd() -> Result<String, CommandNotFoundError> {
     "emulator",
     get_android_emulator_paths(),
     |msg| CommandNotFoundError::AndroidEmulator {
-      suggestion: format!("{}\n\n{}", msg, SUGGESTION_ANDROID_SDK),
+      suggestion: format!(
+        "{}\n\n{}\n\n{}",
+        msg,
+        SUGGESTION_ANDROID_SDK,
+        sdk_root_note()
+      ),
     },
   )
 }
@@ -256,7 +1154,36 @@ pub fn get_adb_cmd() -> Result<String, CommandNotFoundError> {
     "adb",
     get_adb_paths(),
     |msg| CommandNotFoundError::Adb {
-      suggestion: format!("{}\n\n{}", msg, SUGGESTION_ADB),
+      suggestion: format!("{}\n\n{}\n\n{}", msg, SUGGESTION_ADB, sdk_root_note()),
+    },
+  )
+}
+
+pub fn get_avdmanager_cmd() -> Result<String, CommandNotFoundError> {
+  resolve_command(
+    |c| c.avdmanager_cmd.as_ref(),
+    "AVDMANAGER_CMD",
+    "avdmanager",
+    get_avdmanager_paths(),
+    |msg| CommandNotFoundError::Avdmanager {
+      suggestion: format!(
+        "{}\n\n{}\n\n{}",
+        msg,
+        SUGGESTION_AVDMANAGER,
+        sdk_root_note()
+      ),
+    },
+  )
+}
+
+pub fn get_gmtool_cmd() -> Result<String, CommandNotFoundError> {
+  resolve_command(
+    |c| c.gmtool_cmd.as_ref(),
+    "GMTOOL_CMD",
+    "gmtool",
+    get_gmtool_paths(),
+    |msg| CommandNotFoundError::Gmtool {
+      suggestion: format!("{}\n\n{}", msg, SUGGESTION_GMTOOL),
     },
   )
 }
@@ -267,7 +1194,8 @@ pub fn get_xcrun_cmd() -> Result<String, CommandNotFoundError> {
     |c| c.xcrun_cmd.as_ref(),
     "XCRUN_CMD",
     "xcrun",
-    Vec::new(), // xcrun is typically in PATH, not a fixed path
+    // Where the Command Line Tools put it, so finding it needs no `which`
+    vec![PathBuf::from("/usr/bin/xcrun")],
     |msg| CommandNotFoundError::Xcrun {
       suggestion: format!("{}\n\n{}", msg, SUGGESTION_XCRUN),
     },
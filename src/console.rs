@@ -0,0 +1,163 @@
+use crate::config;
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::time::Duration;
+
+// Serial prefix used by adb for local emulators; the suffix is the console port
+const EMULATOR_SERIAL_PREFIX: &str = "emulator-";
+
+// File the emulator writes its console auth token to
+const AUTH_TOKEN_FILE: &str = ".emulator_console_auth_token";
+
+const CONSOLE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Console port embedded in an emulator serial (`emulator-5554` -> 5554)
+pub fn console_port(serial: &str) -> Option<u16> {
+  serial.strip_prefix(EMULATOR_SERIAL_PREFIX)?.parse().ok()
+}
+
+/// Read one console reply: output lines up to a terminating `OK` or `KO: <reason>`.
+///
+/// Returns the output lines joined by newlines, or the `KO` reason as an error.
+pub fn read_reply<R: BufRead>(reader: &mut R) -> Result<String, String> {
+  let mut lines = Vec::new();
+  loop {
+    let mut line = String::new();
+    let read = reader
+      .read_line(&mut line)
+      .map_err(|e| format!("Failed to read from emulator console: {}", e))?;
+    if read == 0 {
+      return Err("Emulator console closed the connection".to_string());
+    }
+
+    let line = line.trim_end_matches(['\r', '\n']);
+    if line == "OK" {
+      return Ok(lines.join("\n"));
+    }
+    if let Some(reason) = line.strip_prefix("KO") {
+      let reason = reason.trim_start_matches(':').trim();
+      return Err(if reason.is_empty() {
+        "Emulator console rejected the command".to_string()
+      } else {
+        reason.to_string()
+      });
+    }
+    lines.push(line.to_string());
+  }
+}
+
+/// Read the console auth token, if the emulator requires one
+fn read_auth_token() -> Option<String> {
+  let path: PathBuf = config::home_dir()?.join(AUTH_TOKEN_FILE);
+  let token = std::fs::read_to_string(path).ok()?;
+  let token = token.trim();
+  if token.is_empty() {
+    None
+  } else {
+    Some(token.to_string())
+  }
+}
+
+/// An authenticated connection to an emulator console
+pub struct ConsoleSession {
+  stream: TcpStream,
+  reader: BufReader<TcpStream>,
+}
+
+impl ConsoleSession {
+  /// Connect to the console on localhost, consume the banner and authenticate
+  pub fn connect(port: u16) -> Result<Self, String> {
+    let stream = TcpStream::connect(("127.0.0.1", port)).map_err(|e| {
+      format!(
+        "Failed to connect to emulator console on port {}: {}",
+        port, e
+      )
+    })?;
+    stream
+      .set_read_timeout(Some(CONSOLE_TIMEOUT))
+      .map_err(|e| format!("Failed to configure emulator console connection: {}", e))?;
+    let reader = BufReader::new(
+      stream
+        .try_clone()
+        .map_err(|e| format!("Failed to configure emulator console connection: {}", e))?,
+    );
+
+    let mut session = ConsoleSession { stream, reader };
+    let banner = read_reply(&mut session.reader)?;
+    if banner.contains("Authentication required") {
+      let token = read_auth_token().ok_or_else(|| {
+        format!(
+          "Emulator console requires authentication but ~/{} could not be read",
+          AUTH_TOKEN_FILE
+        )
+      })?;
+      session
+        .send(&format!("auth {}", token))
+        .map_err(|e| format!("Emulator console authentication failed: {}", e))?;
+    }
+
+    Ok(session)
+  }
+
+  /// Send one command and return its output
+  pub fn send(&mut self, command: &str) -> Result<String, String> {
+    self
+      .stream
+      .write_all(format!("{}\r\n", command).as_bytes())
+      .map_err(|e| format!("Failed to write to emulator console: {}", e))?;
+    read_reply(&mut self.reader)
+  }
+}
+
+impl Drop for ConsoleSession {
+  fn drop(&mut self) {
+    let _ = self.stream.write_all(b"quit\r\n");
+  }
+}
+
+/// Run a single console command against the emulator with the given serial
+pub fn run_command(serial: &str, command: &str) -> Result<String, String> {
+  let port =
+    console_port(serial).ok_or_else(|| format!("'{}' is not a local emulator serial", serial))?;
+  ConsoleSession::connect(port)?.send(command)
+}
+
+/// Join command-line words into one console command, quoting each word
+/// that isn't plain so it reaches the console as a single argument
+pub fn join_command(words: &[String]) -> String {
+  words
+    .iter()
+    .map(|w| quote_word(w))
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+/// `word` as is when it has only safe characters, otherwise in double
+/// quotes with `"` and `\` escaped. Line breaks become `\n` and `\r` so a
+/// word can't end the command early
+fn quote_word(word: &str) -> String {
+  let plain = !word.is_empty()
+    && word
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || "-_.,:=+@%/".contains(c));
+  if plain {
+    return word.to_string();
+  }
+  let mut quoted = String::with_capacity(word.len() + 2);
+  quoted.push('"');
+  for c in word.chars() {
+    match c {
+      '"' | '\\' => {
+        quoted.push('\\');
+        quoted.push(c);
+      }
+      '\n' => quoted.push_str("\\n"),
+      '\r' => quoted.push_str("\\r"),
+      _ => quoted.push(c),
+    }
+  }
+  quoted.push('"');
+  quoted
+}
@@ -1,13 +1,29 @@
+use crate::adb;
 use crate::config;
-
+use crate::console;
+use crate::filelock;
+use crate::genymotion;
+use crate::ini::{self, AvdIni};
+use crate::present;
+use crate::runner;
+use crate::snapshots;
+use crate::text;
+use crate::theme;
+
+use std::collections::{HashMap, HashSet};
 use std::fmt;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
 
 // State constants
 pub const STATE_BOOTED: &str = "Booted";
 pub const STATE_SHUTDOWN: &str = "Shutdown";
-#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
-pub const STATE_AVAILABLE: &str = "Available";
+/// State that was not determined (fast mode, or missing from simctl output)
+pub const STATE_UNKNOWN: &str = "Unknown";
+/// AVD whose system image is no longer installed; it can't be booted
+pub const STATE_BROKEN: &str = "Broken";
 
 // Section headers
 pub const SECTION_ANDROID_EMULATORS: &str = "Android Emulators";
@@ -16,34 +32,175 @@ pub const SECTION_IOS_SIMULATORS: &str = "iOS Simulators";
 // Device types
 pub const DEVICE_TYPE_AVD: &str = "AVD";
 pub const DEVICE_TYPE_RUNNING: &str = "Running Device";
+pub const DEVICE_TYPE_GENYMOTION: &str = "Genymotion";
+
+// Genymotion devices are listed as Android entries whose id is this prefix
+// plus the device's UUID, so launching can tell them from AVDs
+const GENYMOTION_ID_PREFIX: &str = "genymotion:";
+
+// How long to wait before re-querying adb after it started its server
+const ADB_DAEMON_RETRY_DELAY_MS: u64 = 500;
+
+// Bound on `adb emu avd name` for one serial; cloud and third-party
+// emulators can hang on it
+const AVD_NAME_TIMEOUT: Duration = Duration::from_secs(3);
+
+// How often live state is polled while waiting for a transition
+const STATE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// How long to wait for a device to shut down before giving up
+const STOP_TIMEOUT: Duration = Duration::from_secs(60);
+
+// How often `wait_until_booted` reports that it is still waiting
+const BOOT_PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
 
 // Error messages
+pub const ERR_DEVICE_BOOTED: &str = "device is currently booted; stop it first or pass --force";
 #[allow(dead_code)]
 pub const ERR_IOS_ONLY_MACOS: &str = "iOS simulators are only available on macOS";
-
-#[derive(Debug, Clone)]
+pub const ERR_PAIRED_IOS_ONLY: &str = "Paired launch is only available for iOS simulators";
+pub const ERR_COLD_BOOT_ANDROID_ONLY: &str = "Cold boot is not supported for iOS simulators";
+pub const ERR_WINDOW_ANDROID_ONLY: &str =
+  "--fullscreen, --hide-window and --headless only apply to Android emulators; \
+use the Window menu in Simulator instead";
+pub const ERR_NO_GUI_IOS_ONLY: &str =
+  "--no-gui only applies to iOS simulators; use --headless for Android emulators";
+pub const ERR_ERASE_IOS_ONLY: &str =
+  "erase only applies to iOS simulators; reset an Android emulator with `emulaunch open --wipe-data <name>`";
+pub const ERR_GENYMOTION_AVD_ONLY: &str =
+  "this only applies to Android SDK emulators (AVDs); manage Genymotion devices in Genymotion";
+pub const ERR_GENYMOTION_OPTIONS: &str =
+  "launch options don't apply to Genymotion devices; configure them in Genymotion instead";
+pub const ERR_WIPE_BOOTED: &str =
+  "device is running and can't be wiped; stop it first with `emulaunch stop <name>`";
+
+#[derive(Debug, Clone, serde::Serialize)]
 pub struct AndroidEmulator {
   pub name: String,
   pub id: String,
   pub device_type: String,
   pub state: String,
+  /// adb serial (e.g. `emulator-5554`) while the device is running
+  pub serial: Option<String>,
+  /// Why the device can't be launched, from cheap checks made while listing
+  #[serde(rename = "reason", serialize_with = "serialize_launchable")]
+  pub launchable: Result<(), String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize)]
 pub struct IOSSimulator {
   pub name: String,
   pub udid: String,
   pub state: String,
   pub runtime: String,
+  /// Devices paired with this simulator (watch for a phone, phone for a watch)
+  pub paired: Vec<PairMember>,
+  /// Why the simulator can't be booted (e.g. its runtime is missing)
+  #[serde(rename = "reason", serialize_with = "serialize_launchable")]
+  pub launchable: Result<(), String>,
 }
 
-#[allow(clippy::upper_case_acronyms)]
-pub enum EmulatorType {
+/// Serialize `launchable` as the reason it failed, or null
+fn serialize_launchable<S: serde::Serializer>(
+  launchable: &Result<(), String>,
+  serializer: S,
+) -> Result<S::Ok, S::Error> {
+  serde::Serialize::serialize(&launchable.as_ref().err(), serializer)
+}
+
+/// One half of a watch/phone simulator pair
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PairMember {
+  pub name: String,
+  pub udid: String,
+  pub state: String,
+}
+
+/// A watch/phone pairing reported by `simctl list pairs`
+#[derive(Debug, Clone)]
+pub struct SimulatorPair {
+  pub watch: PairMember,
+  pub phone: PairMember,
+}
+
+/// A pair that was left out, with the udids it involves and why
+#[derive(Debug, Clone)]
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub struct SkippedPair {
+  pub udids: Vec<String>,
+  pub note: String,
+}
+
+/// Parsed pairs plus notes for pairs that were skipped
+#[derive(Debug, Clone, Default)]
+pub struct SimulatorPairs {
+  pub pairs: Vec<SimulatorPair>,
+  pub skipped: Vec<SkippedPair>,
+}
+
+/// Stable identity of a device entry, independent of its display name
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "platform", content = "id", rename_all = "lowercase")]
+pub enum EntryKey {
+  /// AVD id (or adb serial for devices only known to adb)
   Android(String),
-  IOS(String),
+  /// Simulator udid
+  Ios(String),
+}
+
+impl fmt::Display for EntryKey {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      EntryKey::Android(id) => write!(f, "android:{}", id),
+      EntryKey::Ios(udid) => write!(f, "ios:{}", udid),
+    }
+  }
+}
+
+/// A platform whose devices form one section of the list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Platform {
+  Android,
+  Ios,
+}
+
+impl Platform {
+  /// Platforms that can be listed on this host, in display order
+  pub fn supported() -> &'static [Platform] {
+    #[cfg(target_os = "macos")]
+    return &[Platform::Android, Platform::Ios];
+
+    #[cfg(not(target_os = "macos"))]
+    return &[Platform::Android];
+  }
+
+  pub fn section_title(self) -> &'static str {
+    match self {
+      Platform::Android => SECTION_ANDROID_EMULATORS,
+      Platform::Ios => SECTION_IOS_SIMULATORS,
+    }
+  }
+
+  /// The platform whose section uses the given header title
+  pub fn from_section_title(title: &str) -> Option<Platform> {
+    match title {
+      SECTION_ANDROID_EMULATORS => Some(Platform::Android),
+      SECTION_IOS_SIMULATORS => Some(Platform::Ios),
+      _ => None,
+    }
+  }
+
+  /// Short name used in messages
+  pub fn label(self) -> &'static str {
+    match self {
+      Platform::Android => "Android",
+      Platform::Ios => "iOS",
+    }
+  }
 }
 
 /// A unified entry for display in the TUI list
+#[derive(Clone)]
 #[allow(clippy::upper_case_acronyms)]
 pub enum EmulatorEntry {
   SectionHeader(String),
@@ -63,14 +220,55 @@ impl EmulatorEntry {
   pub fn is_header(&self) -> bool {
     matches!(self, EmulatorEntry::SectionHeader(_))
   }
+
+  /// The stable key of a device entry (`None` for headers)
+  pub fn key(&self) -> Option<EntryKey> {
+    match self {
+      EmulatorEntry::SectionHeader(_) => None,
+      EmulatorEntry::Android(e) => Some(EntryKey::Android(e.id.clone())),
+      EmulatorEntry::IOS(s) => Some(EntryKey::Ios(s.udid.clone())),
+    }
+  }
+
+  /// Whether the entry can be opened, with the reason when it can't
+  pub fn launchable(&self) -> Result<(), &str> {
+    match self {
+      EmulatorEntry::SectionHeader(_) => Err("Cannot open a section header"),
+      EmulatorEntry::Android(e) => e.launchable.as_ref().map_err(String::as_str).copied(),
+      EmulatorEntry::IOS(s) => s.launchable.as_ref().map_err(String::as_str).copied(),
+    }
+  }
+
+  /// The device state (`None` for headers)
+  pub fn state(&self) -> Option<&str> {
+    match self {
+      EmulatorEntry::SectionHeader(_) => None,
+      EmulatorEntry::Android(e) => Some(&e.state),
+      EmulatorEntry::IOS(s) => Some(&s.state),
+    }
+  }
+
+  /// Show the device as stopped without listing again
+  pub fn mark_shutdown(&mut self) {
+    match self {
+      EmulatorEntry::SectionHeader(_) => {}
+      EmulatorEntry::Android(e) => {
+        e.state = idle_avd_state(&e.launchable).to_string();
+        e.serial = None;
+      }
+      EmulatorEntry::IOS(s) => s.state = STATE_SHUTDOWN.to_string(),
+    }
+  }
 }
 
 impl fmt::Display for EmulatorEntry {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     match self {
       EmulatorEntry::SectionHeader(s) => write!(f, "{}", s),
-      EmulatorEntry::Android(e) => write!(f, "{} [{}] ({})", e.name, e.state, e.device_type),
-      EmulatorEntry::IOS(s) => write!(f, "{} [{}] ({})", s.name, s.state, s.runtime),
+      _ => match present::DeviceView::of(self) {
+        Some(view) => write!(f, "{}", view.line()),
+        None => Ok(()),
+      },
     }
   }
 }
@@ -79,6 +277,10 @@ fn get_android_emulator_cmd() -> Result<String, String> {
   config::get_android_emulator_cmd().map_err(|e: config::CommandNotFoundError| e.to_string())
 }
 
+fn get_avdmanager_cmd() -> Result<String, String> {
+  config::get_avdmanager_cmd().map_err(|e: config::CommandNotFoundError| e.to_string())
+}
+
 fn get_adb_cmd() -> Result<String, String> {
   config::get_adb_cmd().map_err(|e: config::CommandNotFoundError| e.to_string())
 }
@@ -88,28 +290,357 @@ fn get_xcrun_cmd() -> Result<String, String> {
   config::get_xcrun_cmd().map_err(|e: config::CommandNotFoundError| e.to_string())
 }
 
-/// Read the display name from an AVD's config.ini
-fn get_avd_display_name(avd_id: &str) -> Option<String> {
-  let home = std::env::var("HOME").ok()?;
-  let config_path = std::path::PathBuf::from(&home)
-    .join(".android/avd")
-    .join(format!("{}.avd", avd_id))
-    .join("config.ini");
-  let contents = std::fs::read_to_string(config_path).ok()?;
-  contents
-    .lines()
-    .find(|line| line.starts_with("avd.ini.displayname="))
-    .and_then(|line| line.strip_prefix("avd.ini.displayname="))
-    .map(|s| s.trim().to_string())
+/// Run `xcrun simctl <args>` under the simctl timeout and retry policy.
+///
+/// Spawn failures, timeouts and non-zero exits are retried unless `accept`
+/// says the failed output is fine (e.g. "already booted"). Once retries are
+/// exhausted a spawn failure or timeout is an error, while a failed exit is
+/// returned as-is so the caller can report its stderr.
+#[cfg(target_os = "macos")]
+fn run_simctl(
+  args: &[&str],
+  accept: impl Fn(&std::process::Output) -> bool,
+) -> Result<std::process::Output, String> {
+  enum Failure {
+    Error(String),
+    Exit(std::process::Output),
+  }
+
+  let xcrun = get_xcrun_cmd()?;
+  let policy = runner::RetryPolicy::simctl();
+  let result = runner::with_retry(&policy, || {
+    let mut command = std::process::Command::new(&xcrun);
+    command.arg("simctl").args(args).stdin(Stdio::null());
+    match runner::output_with_timeout(&mut command, policy.timeout) {
+      Ok(output) if output.status.success() || accept(&output) => Ok(output),
+      Ok(output) => Err(Failure::Exit(output)),
+      Err(e) => Err(Failure::Error(format!(
+        "Failed to run xcrun simctl {}: {}",
+        args.first().unwrap_or(&""),
+        e
+      ))),
+    }
+  });
+  match result {
+    Ok(output) | Err(Failure::Exit(output)) => Ok(output),
+    Err(Failure::Error(e)) => Err(e),
+  }
+}
+
+/// Whether an AVD id is safe to use as a path component and as the value
+/// of `emulator -avd`: no separators, not `.`/`..`, and no leading `-` that
+/// the emulator would parse as a flag. A `:` is refused too: Windows paths
+/// can't hold one, and Genymotion ids use it.
+pub fn is_valid_avd_id(id: &str) -> bool {
+  !id.is_empty()
+    && id != "."
+    && id != ".."
+    && !id.starts_with('-')
+    && !id
+      .chars()
+      .any(|c| c == '/' || c == '\\' || c == ':' || c.is_control())
+}
+
+/// Where AVDs live: `~/.android/avd`, under `%USERPROFILE%` on Windows
+pub fn avd_root() -> Result<PathBuf, String> {
+  let home = config::home_dir().ok_or_else(|| config::ERR_NO_HOME.to_string())?;
+  Ok(config::join_components(&home, &[".android", "avd"]))
+}
+
+/// The `<id>.avd` directory of an AVD, refusing ids that would escape
+/// `~/.android/avd`
+pub fn avd_dir(avd_id: &str) -> Result<PathBuf, String> {
+  if genymotion_uuid(avd_id).is_some() {
+    return Err(ERR_GENYMOTION_AVD_ONLY.to_string());
+  }
+  if !is_valid_avd_id(avd_id) {
+    return Err(format!("Invalid AVD id '{}'", avd_id));
+  }
+  Ok(avd_root()?.join(format!("{}.avd", avd_id)))
+}
+
+/// An AVD's config.ini, parsed
+fn read_avd_config(avd_id: &str) -> Option<AvdIni> {
+  ini::read(&avd_dir(avd_id).ok()?.join("config.ini"))
+}
+
+/// config.ini keys `tune` edits, in the order they are shown
+pub const TUNE_KEYS: [&str; 3] = ["hw.ramSize", "vm.heapSize", "sdcard.size"];
+
+// Bounds `tune` accepts, in MB; the emulator itself refuses less or more
+const RAM_RANGE_MB: std::ops::RangeInclusive<u32> = 512..=65536;
+const HEAP_RANGE_MB: std::ops::RangeInclusive<u32> = 16..=4096;
+// mksdcard can't make a smaller image
+const MIN_SDCARD_MB: u64 = 9;
+
+/// Changes `tune` makes to an AVD's hardware settings
+#[derive(Debug, Clone, Default)]
+pub struct AvdTuning {
+  pub ram_mb: Option<u32>,
+  pub heap_mb: Option<u32>,
+  /// A size with a K, M or G suffix, as `sdcard.size` takes it
+  pub sdcard: Option<String>,
+}
+
+/// One config.ini key `tune` set
+#[derive(Debug, Clone)]
+pub struct TunedValue {
+  pub key: &'static str,
+  /// `None` when the key was unset
+  pub before: Option<String>,
+  pub after: String,
+}
+
+impl AvdTuning {
+  pub fn is_empty(&self) -> bool {
+    self.ram_mb.is_none() && self.heap_mb.is_none() && self.sdcard.is_none()
+  }
+}
+
+/// A size in MB from a config.ini value: `2048`, `2048M` or `2G`
+fn parse_size_mb(value: &str) -> Option<u64> {
+  let value = value.trim();
+  let (digits, unit) = match value.char_indices().last()? {
+    (i, c) if c.is_ascii_alphabetic() => (&value[..i], c.to_ascii_uppercase()),
+    _ => (value, 'M'),
+  };
+  let n: u64 = digits.trim().parse().ok()?;
+  match unit {
+    'K' => Some(n / 1024),
+    'M' => Some(n),
+    'G' => n.checked_mul(1024),
+    _ => None,
+  }
+}
+
+/// Normalize an SD card size like `2g` or `512M`, refusing ones mksdcard
+/// can't make
+pub fn parse_sdcard_size(size: &str) -> Result<String, String> {
+  let size = size.trim().to_ascii_uppercase();
+  let valid_suffix = size.ends_with(['K', 'M', 'G']);
+  match parse_size_mb(&size) {
+    Some(mb) if valid_suffix && mb >= MIN_SDCARD_MB => Ok(size),
+    Some(_) if valid_suffix => Err(format!(
+      "SD card size '{}' is below the {}M minimum",
+      size, MIN_SDCARD_MB
+    )),
+    _ => Err(format!(
+      "Invalid SD card size '{}'; use a number with K, M or G, like 2G",
+      size
+    )),
+  }
+}
+
+/// Check the requested values against each other and the AVD's current
+/// RAM and heap, so the heap never ends up larger than the RAM
+fn validate_tuning(tuning: &AvdTuning, current: &AvdIni) -> Result<(), String> {
+  if let Some(ram) = tuning.ram_mb {
+    if !RAM_RANGE_MB.contains(&ram) {
+      return Err(format!(
+        "RAM must be between {} and {} MB",
+        RAM_RANGE_MB.start(),
+        RAM_RANGE_MB.end()
+      ));
+    }
+  }
+  if let Some(heap) = tuning.heap_mb {
+    if !HEAP_RANGE_MB.contains(&heap) {
+      return Err(format!(
+        "VM heap must be between {} and {} MB",
+        HEAP_RANGE_MB.start(),
+        HEAP_RANGE_MB.end()
+      ));
+    }
+  }
+  if tuning.ram_mb.is_some() || tuning.heap_mb.is_some() {
+    let ram = tuning
+      .ram_mb
+      .map(u64::from)
+      .or_else(|| current.get("hw.ramSize").and_then(|v| parse_size_mb(v)));
+    let heap = tuning
+      .heap_mb
+      .map(u64::from)
+      .or_else(|| current.get("vm.heapSize").and_then(|v| parse_size_mb(v)));
+    if let (Some(ram), Some(heap)) = (ram, heap) {
+      if heap > ram {
+        let hint = if tuning.heap_mb.is_none() {
+          "; lower it with --heap"
+        } else {
+          ""
+        };
+        return Err(format!(
+          "VM heap ({} MB) can't be larger than the RAM ({} MB){}",
+          heap, ram, hint
+        ));
+      }
+    }
+  }
+  if let Some(size) = &tuning.sdcard {
+    parse_sdcard_size(size)?;
+  }
+  Ok(())
+}
+
+fn avd_config_path(avd_id: &str) -> Result<PathBuf, String> {
+  let path = avd_dir(avd_id)?.join("config.ini");
+  if !path.is_file() {
+    return Err(format!(
+      "No config.ini for AVD '{}' at {}",
+      avd_id,
+      path.display()
+    ));
+  }
+  Ok(path)
+}
+
+/// The current values of `TUNE_KEYS` (`None` when a key is unset)
+pub fn avd_hardware(key: &EntryKey) -> Result<Vec<(&'static str, Option<String>)>, String> {
+  let EntryKey::Android(id) = key else {
+    return Err("Only AVDs have hardware settings to tune".to_string());
+  };
+  let config = ini::read(&avd_config_path(id)?).unwrap_or_default();
+  Ok(
+    TUNE_KEYS
+      .iter()
+      .map(|&k| (k, config.get(k).cloned()))
+      .collect(),
+  )
+}
+
+/// Write the requested hardware settings into an AVD's config.ini, leaving
+/// every other line alone. Returns each key set, with its value before and
+/// after. A running emulator would overwrite the file, so it is refused.
+pub fn tune_avd(key: &EntryKey, tuning: &AvdTuning) -> Result<Vec<TunedValue>, String> {
+  let EntryKey::Android(id) = key else {
+    return Err("Only AVDs have hardware settings to tune".to_string());
+  };
+  let path = avd_config_path(id)?;
+  let contents = std::fs::read_to_string(&path)
+    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+  let current = ini::parse_avd_ini(&contents);
+  validate_tuning(tuning, &current)?;
+  if device_state(key).is_ok_and(|s| s == STATE_BOOTED) {
+    return Err(format!(
+      "AVD '{}' is running; stop it first (`emulaunch stop {}`)",
+      id, id
+    ));
+  }
+
+  let values: Vec<(&'static str, String)> = [
+    (TUNE_KEYS[0], tuning.ram_mb.map(|mb| mb.to_string())),
+    (TUNE_KEYS[1], tuning.heap_mb.map(|mb| mb.to_string())),
+    (
+      TUNE_KEYS[2],
+      tuning
+        .sdcard
+        .as_deref()
+        .map(parse_sdcard_size)
+        .transpose()?,
+    ),
+  ]
+  .into_iter()
+  .filter_map(|(k, v)| Some((k, v?)))
+  .collect();
+  let updates: Vec<(&str, &str)> = values.iter().map(|(k, v)| (*k, v.as_str())).collect();
+  filelock::write_atomic(&path, &ini::set_values(&contents, &updates))?;
+  Ok(
+    values
+      .into_iter()
+      .map(|(key, after)| TunedValue {
+        key,
+        before: current.get(key).cloned(),
+        after,
+      })
+      .collect(),
+  )
+}
+
+/// The display name in config.ini, with its whitespace normalized
+fn avd_display_name(config: &AvdIni) -> Option<String> {
+  let name = text::normalize_whitespace(config.get("avd.ini.displayname")?);
+  (!name.is_empty()).then_some(name)
+}
+
+/// What a listing needs from an AVD's config.ini
+struct AvdDetails {
+  display_name: Option<String>,
+  launchable: Result<(), String>,
+}
+
+/// Read an AVD's config.ini once for its display name and, when `sdk_root`
+/// is given, its system image check
+fn read_avd_details(avd_id: &str, sdk_root: Option<&Path>) -> AvdDetails {
+  let config = read_avd_config(avd_id);
+  let config = config.as_ref();
+  AvdDetails {
+    display_name: config.and_then(avd_display_name),
+    launchable: match (config, sdk_root) {
+      (Some(config), Some(sdk_root)) => system_image_check(config, sdk_root),
+      _ => Ok(()),
+    },
+  }
+}
+
+/// Cheap launch check for an AVD (one stat): its system image
+/// (`image.sysdir.1`, relative to the SDK) must still be installed. Passes
+/// when the SDK location or the config can't be determined.
+fn avd_launchable(avd_id: &str, sdk_root: Option<&Path>) -> Result<(), String> {
+  match (sdk_root, read_avd_config(avd_id)) {
+    (Some(sdk_root), Some(config)) => system_image_check(&config, sdk_root),
+    _ => Ok(()),
+  }
+}
+
+/// `avd_launchable` on a config.ini already read
+fn system_image_check(config: &AvdIni, sdk_root: &Path) -> Result<(), String> {
+  // Windows-created AVDs write the sysdir with backslashes
+  let sysdir = match config.get("image.sysdir.1") {
+    Some(sysdir) if !sysdir.is_empty() => sysdir.replace('\\', "/"),
+    _ => return Ok(()),
+  };
+  if sdk_root.join(&sysdir).is_dir() {
+    return Ok(());
+  }
+  Err(format!(
+    "system image missing: {} \u{2014} reinstall it via sdkmanager or delete the AVD",
+    sdk_root.join(sysdir.trim_end_matches('/')).display()
+  ))
+}
+
+/// State of an AVD that isn't running: `Broken` if it can't be launched
+fn idle_avd_state(launchable: &Result<(), String>) -> &'static str {
+  if launchable.is_err() {
+    STATE_BROKEN
+  } else {
+    STATE_SHUTDOWN
+  }
+}
+
+/// Run an adb query, retrying once if the first call had to start the adb server.
+///
+/// The first invocation after the server starts often reports no devices, even
+/// though emulators are running, so the result is discarded and re-queried.
+fn run_adb_query(adb_cmd: &str, args: &[&str]) -> std::io::Result<std::process::Output> {
+  let output = runner::output(std::process::Command::new(adb_cmd).args(args))?;
+
+  let started_daemon = [&output.stdout, &output.stderr].iter().any(|stream| {
+    String::from_utf8_lossy(stream)
+      .lines()
+      .any(adb::is_daemon_banner)
+  });
+  if !started_daemon {
+    return Ok(output);
+  }
+
+  std::thread::sleep(std::time::Duration::from_millis(ADB_DAEMON_RETRY_DELAY_MS));
+  runner::output(std::process::Command::new(adb_cmd).args(args))
 }
 
-/// Get the set of AVD names that are currently running via adb
-fn get_running_avd_names() -> Result<Vec<String>, String> {
+/// Get the `(serial, AVD name)` pairs of emulators currently running via adb
+fn get_running_avds() -> Result<Vec<(String, String)>, String> {
   let adb_cmd = get_adb_cmd()?;
 
-  let output = std::process::Command::new(&adb_cmd)
-    .args(["devices"])
-    .output()
+  let output = run_adb_query(&adb_cmd, &["devices"])
     .map_err(|e| format!("Failed to run adb devices: {}", e))?;
 
   if !output.status.success() {
@@ -117,127 +648,362 @@ fn get_running_avd_names() -> Result<Vec<String>, String> {
   }
 
   let stdout = String::from_utf8_lossy(&output.stdout);
-  let serials: Vec<String> = stdout
-    .lines()
-    .skip(1)
-    .filter_map(|line| {
-      let parts: Vec<&str> = line.split_whitespace().collect();
-      if parts.len() >= 2 && parts[1] == "device" && parts[0].starts_with("emulator-") {
-        Some(parts[0].to_string())
-      } else {
-        None
-      }
-    })
+  let serials: Vec<String> = adb::parse_devices(&stdout)
+    .into_iter()
+    .filter(|d| d.is_emulator() && d.is_online())
+    .map(|d| d.serial)
     .collect();
 
-  let names = serials
+  let unknown: Vec<&String> = {
+    let mut cache = serial_cache();
+    // A serial that went away may come back as a different AVD
+    cache.retain(|serial, _| serials.contains(serial));
+    serials.iter().filter(|s| !cache.contains_key(*s)).collect()
+  };
+  // Unlocked while querying, so the details pane never waits on a hung query
+  let found: Vec<(String, Option<SerialLookup>)> = std::thread::scope(|scope| {
+    let handles: Vec<_> = unknown
+      .into_iter()
+      .map(|serial| scope.spawn(|| (serial.clone(), query_avd_name(&adb_cmd, serial))))
+      .collect();
+    handles.into_iter().filter_map(|h| h.join().ok()).collect()
+  });
+  let mut cache = serial_cache();
+  for (serial, lookup) in found {
+    // Plain failures are retried on the next listing; timeouts aren't
+    if let Some(lookup) = lookup {
+      cache.insert(serial, lookup);
+    }
+  }
+
+  Ok(
+    serials
+      .into_iter()
+      .filter_map(|serial| match cache.get(&serial) {
+        Some(SerialLookup::Avd(name)) => Some((serial, name.clone())),
+        _ => None,
+      })
+      .collect(),
+  )
+}
+
+/// What the `avd name` query found for a running emulator's serial
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SerialLookup {
+  Avd(String),
+  /// The query hung; the serial is skipped for the rest of the session
+  TimedOut,
+}
+
+/// `avd name` results for this session, keyed by serial. The mapping can't
+/// change while the serial stays in `adb devices`, so each emulator is
+/// only asked once.
+fn serial_cache() -> std::sync::MutexGuard<'static, HashMap<String, SerialLookup>> {
+  static CACHE: OnceLock<Mutex<HashMap<String, SerialLookup>>> = OnceLock::new();
+  CACHE
+    .get_or_init(Default::default)
+    .lock()
+    .unwrap_or_else(|e| e.into_inner())
+}
+
+/// Serials skipped because `avd name` timed out, for the details pane
+pub fn unresponsive_serials() -> Vec<String> {
+  let mut serials: Vec<String> = serial_cache()
     .iter()
-    .filter_map(|serial| {
-      let result = std::process::Command::new(&adb_cmd)
-        .args(["-s", serial, "emu", "avd", "name"])
-        .output()
-        .ok()?;
-      if result.status.success() {
-        let stdout = String::from_utf8_lossy(&result.stdout);
-        stdout.lines().next().map(|s| s.trim().to_string())
-      } else {
-        None
+    .filter(|(_, lookup)| **lookup == SerialLookup::TimedOut)
+    .map(|(serial, _)| serial.clone())
+    .collect();
+  serials.sort();
+  serials
+}
+
+/// Ask one emulator for its AVD name; `None` when it failed in a way worth
+/// retrying later
+fn query_avd_name(adb_cmd: &str, serial: &str) -> Option<SerialLookup> {
+  // The console is cheaper than spawning adb; adb handles emulators whose
+  // console can't be reached (e.g. a missing auth token)
+  if let Ok(name) = console::run_command(serial, "avd name") {
+    return Some(SerialLookup::Avd(name.trim().to_string()));
+  }
+  let mut command = std::process::Command::new(adb_cmd);
+  command
+    .args(["-s", serial, "emu", "avd", "name"])
+    .stdin(Stdio::null());
+  match runner::output_with_timeout(&mut command, AVD_NAME_TIMEOUT) {
+    Ok(result) if result.status.success() => {
+      adb::parse_emu_avd_name(&String::from_utf8_lossy(&result.stdout)).map(SerialLookup::Avd)
+    }
+    Err(e) if e.kind() == std::io::ErrorKind::TimedOut => Some(SerialLookup::TimedOut),
+    _ => None,
+  }
+}
+
+// Upper bound on threads reading AVD configs during a listing
+const MAX_LIST_THREADS: usize = 8;
+
+// Below this many items a listing reads configs on the calling thread
+const PARALLEL_LIST_THRESHOLD: usize = 32;
+
+/// `f` over `items`, split across up to MAX_LIST_THREADS scoped threads.
+/// Results keep the order of `items`.
+fn parallel_map<T: Sync, R: Send>(items: &[T], f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+  let threads = std::thread::available_parallelism()
+    .map_or(1, |n| n.get())
+    .min(MAX_LIST_THREADS);
+  if threads < 2 || items.len() < PARALLEL_LIST_THRESHOLD {
+    return items.iter().map(f).collect();
+  }
+  let f = &f;
+  std::thread::scope(|scope| {
+    let workers: Vec<_> = items
+      .chunks(items.len().div_ceil(threads))
+      .map(|chunk| scope.spawn(move || chunk.iter().map(f).collect::<Vec<R>>()))
+      .collect();
+    workers
+      .into_iter()
+      .flat_map(|w| w.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+      .collect()
+  })
+}
+
+/// List AVDs by scanning ~/.android/avd/ directory. Without `state`, each is
+/// `Shutdown` or `Broken` after checking its system image; with it, every AVD
+/// gets that state and the check is skipped.
+fn list_avds_from_directory(state: Option<&str>) -> Result<Vec<AndroidEmulator>, String> {
+  let avd_root = avd_root()?;
+
+  let entries = std::fs::read_dir(&avd_root)
+    .map_err(|e| format!("Cannot read AVD directory {}: {}", avd_root.display(), e))?;
+
+  // Candidate ids from `<id>.ini` names; the per-AVD stat and config read
+  // happen below, in parallel
+  let ids: Vec<String> = entries
+    .flatten()
+    .filter(|e| e.file_type().is_ok_and(|t| !t.is_dir()))
+    .filter_map(|e| {
+      let path = e.path();
+      if path.extension().and_then(|e| e.to_str()) != Some("ini") {
+        return None;
       }
+      let stem = path.file_stem()?.to_str()?;
+      is_valid_avd_id(stem).then(|| stem.to_string())
     })
     .collect();
 
-  Ok(names)
-}
-
-/// List AVDs by scanning ~/.android/avd/ directory
-fn list_avds_from_directory() -> Result<Vec<AndroidEmulator>, String> {
-  let home = std::env::var("HOME").map_err(|_| "HOME not set".to_string())?;
-  let avd_dir = std::path::PathBuf::from(&home).join(".android/avd");
-
-  let entries =
-    std::fs::read_dir(&avd_dir).map_err(|e| format!("Cannot read AVD directory: {}", e))?;
-
-  let mut emulators = Vec::new();
-  for entry in entries.flatten() {
-    let path = entry.path();
-    if path.extension().and_then(|e| e.to_str()) == Some("ini") && !path.is_dir() {
-      if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
-        // Verify the .avd directory exists
-        if avd_dir.join(format!("{}.avd", stem)).is_dir() {
-          let display_name = get_avd_display_name(stem).unwrap_or_else(|| stem.to_string());
-          emulators.push(AndroidEmulator {
-            name: display_name,
-            id: stem.to_string(),
-            device_type: DEVICE_TYPE_AVD.to_string(),
-            state: STATE_SHUTDOWN.to_string(),
-          });
-        }
-      }
+  let sdk_root = state.is_none().then(config::android_sdk_root).flatten();
+  let emulators: Vec<AndroidEmulator> = parallel_map(&ids, |id| {
+    // Verify the .avd directory exists
+    if !avd_root.join(format!("{}.avd", id)).is_dir() {
+      return None;
     }
-  }
+    let details = read_avd_details(id, sdk_root.as_deref());
+    let state = state.unwrap_or_else(|| idle_avd_state(&details.launchable));
+    Some(AndroidEmulator {
+      name: details.display_name.unwrap_or_else(|| id.clone()),
+      id: id.clone(),
+      device_type: DEVICE_TYPE_AVD.to_string(),
+      state: state.to_string(),
+      serial: None,
+      launchable: details.launchable,
+    })
+  })
+  .into_iter()
+  .flatten()
+  .collect();
 
   if emulators.is_empty() {
-    Err("No AVDs found in ~/.android/avd/".to_string())
+    Err(format!("No AVDs found in {}", avd_root.display()))
   } else {
     Ok(emulators)
   }
 }
 
 pub fn list_android_emulators() -> Result<Vec<AndroidEmulator>, String> {
+  // Genymotion is optional, so a missing gmtool is no error. gmtool can
+  // take seconds, so it runs alongside the SDK listing
+  let (genymotion, sdk) = std::thread::scope(|s| {
+    let genymotion = s.spawn(|| list_genymotion_devices().unwrap_or_default());
+    let sdk = list_sdk_emulators();
+    (genymotion.join().unwrap_or_default(), sdk)
+  });
+  let mut emulators = match sdk {
+    Ok(mut emulators) => {
+      // adb's fallback listing includes running Genymotion devices too
+      emulators.retain(|e| {
+        e.device_type != DEVICE_TYPE_RUNNING || !genymotion.iter().any(|g| g.serial == e.serial)
+      });
+      emulators.extend(genymotion);
+      emulators
+    }
+    Err(_) if !genymotion.is_empty() => genymotion,
+    Err(e) => return Err(e),
+  };
+
+  // Sort: booted first, then by name
+  emulators.sort_by(|a, b| {
+    let a_booted = a.state == STATE_BOOTED;
+    let b_booted = b.state == STATE_BOOTED;
+    b_booted
+      .cmp(&a_booted)
+      .then_with(|| text::collate(&a.name, &b.name))
+  });
+
+  Ok(emulators)
+}
+
+/// AVDs from the SDK's emulator, else the AVD directory, else adb
+fn list_sdk_emulators() -> Result<Vec<AndroidEmulator>, String> {
   let emulator_cmd = get_android_emulator_cmd()?;
-  let running_names = get_running_avd_names().unwrap_or_default();
+  let running = get_running_avds().unwrap_or_default();
 
-  let output = std::process::Command::new(&emulator_cmd)
-    .arg("-list-avds")
-    .output();
+  let output = runner::output(std::process::Command::new(&emulator_cmd).arg("-list-avds"));
 
-  let mut emulators = match output {
-    Ok(result) if result.status.success() => {
+  // A misconfigured SDK can make the emulator list nothing (even exiting
+  // zero); its stderr then says why better than the fallbacks' errors
+  let sdk_problem = output
+    .as_ref()
+    .ok()
+    .and_then(|o| diagnose_sdk_problem(&String::from_utf8_lossy(&o.stderr)));
+
+  match output {
+    Ok(result) if result.status.success() && !result.stdout.trim_ascii().is_empty() => {
+      let sdk_root = config::android_sdk_root();
       let stdout = String::from_utf8_lossy(&result.stdout);
-      Ok(
-        stdout
-          .lines()
-          .filter(|line| !line.is_empty())
-          .map(|line| {
-            let id = line.trim().to_string();
-            let name = get_avd_display_name(&id).unwrap_or_else(|| id.clone());
-            let state = if running_names.contains(&id) {
-              STATE_BOOTED.to_string()
-            } else {
-              STATE_SHUTDOWN.to_string()
-            };
-            AndroidEmulator {
-              name,
-              id,
-              device_type: DEVICE_TYPE_AVD.to_string(),
-              state,
-            }
-          })
-          .collect(),
-      )
+      let ids: Vec<&str> = stdout
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+      Ok(parallel_map(&ids, |&id| {
+        let details = read_avd_details(id, sdk_root.as_deref());
+        let serial = running
+          .iter()
+          .find(|(_, avd)| avd == id)
+          .map(|(serial, _)| serial.clone());
+        let state = if serial.is_some() {
+          STATE_BOOTED
+        } else {
+          idle_avd_state(&details.launchable)
+        };
+        AndroidEmulator {
+          name: details.display_name.unwrap_or_else(|| id.to_string()),
+          id: id.to_string(),
+          device_type: DEVICE_TYPE_AVD.to_string(),
+          state: state.to_string(),
+          serial,
+          launchable: details.launchable,
+        }
+      }))
     }
+    Ok(result) if result.status.success() && sdk_problem.is_none() => Ok(Vec::new()),
     Ok(_) | Err(_) => {
       // emulator command failed or not found — try scanning AVD directory, then adb
-      list_avds_from_directory().or_else(|_| list_android_devices_via_adb())
+      match (
+        list_avds_from_directory(None).or_else(|_| list_android_devices_via_adb()),
+        sdk_problem,
+      ) {
+        (Ok(found), _) if !found.is_empty() => Ok(found),
+        (_, Some(problem)) => Err(problem),
+        (result, None) => result,
+      }
     }
-  }?;
+  }
+}
 
-  // Sort: booted first
-  emulators.sort_by(|a, b| {
-    let a_booted = a.state == STATE_BOOTED;
-    let b_booted = b.state == STATE_BOOTED;
-    b_booted.cmp(&a_booted)
-  });
+/// Genymotion Desktop devices as Android entries
+fn list_genymotion_devices() -> Result<Vec<AndroidEmulator>, String> {
+  Ok(
+    genymotion::list()?
+      .into_iter()
+      .map(|d| AndroidEmulator {
+        state: if d.is_on() {
+          STATE_BOOTED.to_string()
+        } else if d.is_off() {
+          STATE_SHUTDOWN.to_string()
+        } else {
+          d.state.clone()
+        },
+        id: format!("{}{}", GENYMOTION_ID_PREFIX, d.uuid),
+        name: d.name,
+        device_type: DEVICE_TYPE_GENYMOTION.to_string(),
+        serial: d.serial,
+        launchable: Ok(()),
+      })
+      .collect(),
+  )
+}
 
-  Ok(emulators)
+/// The Genymotion UUID behind an Android entry id, if it is one
+fn genymotion_uuid(id: &str) -> Option<&str> {
+  id.strip_prefix(GENYMOTION_ID_PREFIX)
+}
+
+/// Whether a device is a Genymotion one rather than an AVD or simulator
+pub fn is_genymotion(key: &EntryKey) -> bool {
+  matches!(key, EntryKey::Android(id) if genymotion_uuid(id).is_some())
+}
+
+/// Start a Genymotion device through gmtool
+fn open_genymotion_device(uuid: &str) -> Result<String, String> {
+  genymotion::start(uuid)?;
+  Ok(format!("Launching Genymotion device: {}", uuid))
+}
+
+/// The SDK misconfiguration `emulator -list-avds` reported on stderr, if
+/// any, naming the offending path. Samples it recognizes:
+///
+/// ```text
+/// PANIC: Cannot find AVD system path. Please define ANDROID_SDK_ROOT
+/// PANIC: Broken AVD system path. Check your ANDROID_SDK_ROOT value [/opt/sdk]!
+/// ERROR   | ANDROID_SDK_ROOT is defined (/opt/sdk) but is not a valid SDK directory
+/// ```
+fn diagnose_sdk_problem(stderr: &str) -> Option<String> {
+  const SDK_HINTS: [&str; 4] = ["SDK_ROOT", "ANDROID_HOME", "SDK ROOT", "SYSTEM PATH"];
+  let message = stderr.lines().find_map(|line| {
+    let at = line.find("PANIC").or_else(|| line.find("ERROR"))?;
+    let upper = line.to_uppercase();
+    SDK_HINTS
+      .iter()
+      .any(|hint| upper.contains(hint))
+      .then(|| line[at + 5..].trim_start_matches([' ', ':', '|']).trim())
+  })?;
+
+  // The path the emulator quoted, else the variable it was reading
+  let quoted = message
+    .split_once(['[', '('])
+    .and_then(|(_, rest)| rest.split_once([']', ')']))
+    .map(|(path, _)| path.to_string())
+    .filter(|path| path.contains(['/', '\\']));
+  let path = match quoted {
+    Some(path) => path,
+    None => ["ANDROID_SDK_ROOT", "ANDROID_HOME"]
+      .iter()
+      .find_map(|var| Some(format!("{}={}", var, std::env::var(var).ok()?)))
+      .unwrap_or_else(|| "ANDROID_HOME is not set".to_string()),
+  };
+  Some(format!(
+    "emulator can't use the Android SDK ({}): {} (set ANDROID_HOME to your SDK directory)",
+    path, message
+  ))
+}
+
+/// List AVDs without running adb or the emulator binary; every state is
+/// `Unknown` and system images aren't checked. Falls back to the full
+/// listing when the AVD directory is empty.
+pub fn list_android_emulators_fast() -> Result<Vec<AndroidEmulator>, String> {
+  match list_avds_from_directory(Some(STATE_UNKNOWN)) {
+    Ok(mut emulators) => {
+      emulators.sort_by(|a, b| text::collate(&a.name, &b.name));
+      Ok(emulators)
+    }
+    Err(_) => list_android_emulators(),
+  }
 }
 
 fn list_android_devices_via_adb() -> Result<Vec<AndroidEmulator>, String> {
   let adb_cmd = get_adb_cmd()?;
 
-  let output = std::process::Command::new(&adb_cmd)
-    .args(["devices", "-l"])
-    .output()
+  let output = run_adb_query(&adb_cmd, &["devices", "-l"])
     .map_err(|e| format!("Failed to run adb command: {}", e))?;
 
   if !output.status.success() {
@@ -248,40 +1014,36 @@ fn list_android_devices_via_adb() -> Result<Vec<AndroidEmulator>, String> {
   }
 
   let stdout = String::from_utf8_lossy(&output.stdout);
-  Ok(
-    stdout
-      .lines()
-      .skip(1)
-      .filter(|line| !line.is_empty() && line.contains("device"))
-      .filter_map(|line| {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        let id = parts.first().map(|s| s.to_string())?;
-        let name = parts
-          .iter()
-          .find(|p| p.starts_with("model:"))
-          .and_then(|p| p.strip_prefix("model:"))
-          .map(|s| s.to_string())
-          .unwrap_or_else(|| id.clone());
+  Ok(parse_adb_devices_long(&stdout))
+}
 
-        Some(AndroidEmulator {
-          name,
-          id,
-          device_type: DEVICE_TYPE_RUNNING.to_string(),
-          state: STATE_BOOTED.to_string(),
-        })
-      })
-      .collect(),
-  )
+/// Running devices from `adb devices -l` output
+fn parse_adb_devices_long(stdout: &str) -> Vec<AndroidEmulator> {
+  adb::parse_devices(stdout)
+    .into_iter()
+    .filter(|d| d.is_online())
+    .map(|d| AndroidEmulator {
+      name: d.property("model").unwrap_or(&d.serial).to_string(),
+      id: d.serial.clone(),
+      device_type: DEVICE_TYPE_RUNNING.to_string(),
+      state: STATE_BOOTED.to_string(),
+      serial: Some(d.serial),
+      launchable: Err("Connected through adb only; there is no AVD to launch".to_string()),
+    })
+    .collect()
 }
 
 #[cfg(target_os = "macos")]
 pub fn list_ios_simulators() -> Result<Vec<IOSSimulator>, String> {
-  let xcrun = get_xcrun_cmd()?;
+  list_ios_simulators_with_pairs().map(|(simulators, _)| simulators)
+}
 
-  let output = std::process::Command::new(&xcrun)
-    .args(["simctl", "list", "devices", "available", "--json"])
-    .output()
-    .map_err(|e| format!("Failed to run xcrun simctl: {}", e))?;
+/// List simulators with pair links attached, plus the parsed pairs
+#[cfg(target_os = "macos")]
+fn list_ios_simulators_with_pairs() -> Result<(Vec<IOSSimulator>, SimulatorPairs), String> {
+  // One call for both: the full listing carries `devices` and `pairs`.
+  // Unavailable devices are listed too, marked as not launchable
+  let output = run_simctl(&["list", "--json"], |_| false)?;
 
   if !output.status.success() {
     return Err(format!(
@@ -291,7 +1053,18 @@ pub fn list_ios_simulators() -> Result<Vec<IOSSimulator>, String> {
   }
 
   let json = String::from_utf8_lossy(&output.stdout);
-  parse_ios_simulators(&json)
+  let parsed = parse_ios_simulators(&json)?;
+  if runner::verbose() {
+    for note in &parsed.skipped {
+      eprintln!("Skipped simulator entry: {}", note);
+    }
+  }
+  let mut simulators = parsed.simulators;
+  // simctl's map order changes from run to run
+  sort_ios_simulators(&mut simulators);
+  let mut pairs = parse_simulator_pairs(&json).unwrap_or_default();
+  attach_pairs(&mut simulators, &mut pairs);
+  Ok((simulators, pairs))
 }
 
 #[cfg(not(target_os = "macos"))]
@@ -299,186 +1072,2053 @@ pub fn list_ios_simulators() -> Result<Vec<IOSSimulator>, String> {
   Err(ERR_IOS_ONLY_MACOS.to_string())
 }
 
+/// Simulators parsed from `simctl list --json`, plus a note for each
+/// device entry that had to be skipped
 #[cfg(target_os = "macos")]
-fn parse_ios_simulators(json: &str) -> Result<Vec<IOSSimulator>, String> {
-  #[derive(serde::Deserialize)]
-  struct DevicesResponse {
-    devices: serde_json::Value,
+struct ParsedSimulators {
+  simulators: Vec<IOSSimulator>,
+  skipped: Vec<String>,
+}
+
+/// Readable runtime name from a `devices` key.
+///
+/// Xcode 10+ keys are identifiers (`com.apple.CoreSimulator.SimRuntime.iOS-17-0`
+/// -> `iOS 17.0`); older Xcode versions already used display names (`iOS 12.1`),
+/// which are returned unchanged.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn runtime_display_name(key: &str) -> String {
+  let Some(id) = key.strip_prefix("com.apple.CoreSimulator.SimRuntime.") else {
+    return key.to_string();
+  };
+  match id.split_once('-') {
+    Some((os, version)) => format!("{} {}", os, version.replace('-', ".")),
+    None => id.to_string(),
   }
+}
 
-  let response: DevicesResponse =
-    serde_json::from_str(json).map_err(|e| format!("Failed to parse simctl JSON: {}", e))?;
+/// Sort: booted first, then newest runtime and name, as for AVDs
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn sort_ios_simulators(simulators: &mut [IOSSimulator]) {
+  simulators.sort_by(|a, b| {
+    let a_booted = a.state == STATE_BOOTED;
+    let b_booted = b.state == STATE_BOOTED;
+    b_booted
+      .cmp(&a_booted)
+      .then_with(|| compare_runtimes(&a.runtime, &b.runtime))
+      .then_with(|| text::collate(&a.name, &b.name))
+  });
+}
 
-  let mut simulators = Vec::new();
-
-  if let Some(devices_map) = response.devices.as_object() {
-    for (runtime, devices) in devices_map {
-      if let Some(device_list) = devices.as_array() {
-        for device in device_list {
-          let Some(name) = device.get("name").and_then(|v| v.as_str()) else {
-            continue;
-          };
-          let Some(udid) = device.get("udid").and_then(|v| v.as_str()) else {
-            continue;
-          };
-          let Some(state) = device.get("state").and_then(|v| v.as_str()) else {
-            continue;
-          };
-
-          if matches!(state, STATE_BOOTED | STATE_SHUTDOWN | STATE_AVAILABLE) {
-            simulators.push(IOSSimulator {
-              name: name.to_string(),
-              udid: udid.to_string(),
-              state: state.to_string(),
-              runtime: runtime.clone(),
-            });
-          }
-        }
+/// Runtime display names grouped by OS, newest version first within each
+/// (`iOS 17.2` before `iOS 17.0` before `iOS 9.3`)
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn compare_runtimes(a: &str, b: &str) -> std::cmp::Ordering {
+  // `iOS 17.0` -> ("iOS", [17, 0]); names without a version sort after
+  fn split(runtime: &str) -> (&str, Option<Vec<u64>>) {
+    match runtime.rsplit_once(' ') {
+      Some((os, version)) => (os, version.split('.').map(|p| p.parse().ok()).collect()),
+      None => (runtime, None),
+    }
+  }
+  let (a_os, a_version) = split(a);
+  let (b_os, b_version) = split(b);
+  text::collate(a_os, b_os)
+    .then_with(|| b_version.cmp(&a_version))
+    .then_with(|| a.cmp(b))
+}
+
+/// Why a device entry is unavailable, or `None` if it is available.
+///
+/// Accepts both the `isAvailable` bool (Xcode 10.1+, with the reason in
+/// `availabilityError`) and the older `availability: "(unavailable, reason)"`
+/// string. Entries with neither are assumed available.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn device_unavailable_reason(device: &serde_json::Value) -> Option<String> {
+  let reason = if let Some(available) = device.get("isAvailable") {
+    // Some Xcode 10 betas wrote the flag as a "YES"/"NO" string
+    let available = available
+      .as_bool()
+      .or_else(|| available.as_str().map(|s| s.eq_ignore_ascii_case("yes")))
+      .unwrap_or(true);
+    if available {
+      return None;
+    }
+    device.get("availabilityError").and_then(|v| v.as_str())
+  } else {
+    let availability = device.get("availability").and_then(|v| v.as_str())?;
+    let inner = availability.trim_start_matches('(').trim_end_matches(')');
+    if inner.starts_with("available") {
+      return None;
+    }
+    inner.split_once(',').map(|(_, reason)| reason.trim())
+  };
+  Some(match reason.filter(|r| !r.is_empty()) {
+    Some(reason) => format!("Simulator is unavailable: {}", reason),
+    None => "Simulator is unavailable".to_string(),
+  })
+}
+
+#[cfg(target_os = "macos")]
+fn parse_ios_simulators(json: &str) -> Result<ParsedSimulators, String> {
+  #[derive(serde::Deserialize)]
+  struct DevicesResponse {
+    devices: serde_json::Value,
+  }
+
+  let response: DevicesResponse =
+    serde_json::from_str(json).map_err(|e| format!("Failed to parse simctl JSON: {}", e))?;
+
+  let mut parsed = ParsedSimulators {
+    simulators: Vec::new(),
+    skipped: Vec::new(),
+  };
+
+  let Some(devices_map) = response.devices.as_object() else {
+    return Err("Failed to parse simctl JSON: `devices` is not an object".to_string());
+  };
+  for (runtime, devices) in devices_map {
+    let Some(device_list) = devices.as_array() else {
+      parsed
+        .skipped
+        .push(format!("{}: device list is not an array", runtime));
+      continue;
+    };
+    let runtime = runtime_display_name(runtime);
+    for device in device_list {
+      // The udid is the only field nothing else can stand in for
+      let Some(udid) = device.get("udid").and_then(|v| v.as_str()) else {
+        parsed
+          .skipped
+          .push(format!("{}: device without a udid: {}", runtime, device));
+        continue;
+      };
+      let name = device.get("name").and_then(|v| v.as_str()).unwrap_or(udid);
+      let state = device
+        .get("state")
+        .and_then(|v| v.as_str())
+        .unwrap_or(STATE_UNKNOWN);
+
+      parsed.simulators.push(IOSSimulator {
+        name: name.to_string(),
+        udid: udid.to_string(),
+        state: state.to_string(),
+        runtime: runtime.clone(),
+        paired: Vec::new(),
+        launchable: match device_unavailable_reason(device) {
+          Some(reason) => Err(reason),
+          None => Ok(()),
+        },
+      });
+    }
+  }
+
+  Ok(parsed)
+}
+
+/// Parse the `pairs` of `simctl list --json`.
+///
+/// Unlike the devices listing, pairs are keyed by pair UUID and each value holds
+/// a `watch` and a `phone` object. Pairs marked unavailable, or missing a member,
+/// are recorded in `skipped` instead of being returned.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn parse_simulator_pairs(json: &str) -> Result<SimulatorPairs, String> {
+  #[derive(serde::Deserialize)]
+  struct PairsResponse {
+    pairs: serde_json::Value,
+  }
+
+  let response: PairsResponse =
+    serde_json::from_str(json).map_err(|e| format!("Failed to parse simctl pairs JSON: {}", e))?;
+
+  fn member(pair: &serde_json::Value, key: &str) -> Option<PairMember> {
+    let device = pair.get(key)?;
+    Some(PairMember {
+      name: device.get("name")?.as_str()?.to_string(),
+      udid: device.get("udid")?.as_str()?.to_string(),
+      state: device.get("state")?.as_str()?.to_string(),
+    })
+  }
+
+  let mut result = SimulatorPairs::default();
+
+  if let Some(pairs_map) = response.pairs.as_object() {
+    for (pair_id, pair) in pairs_map {
+      let (Some(watch), Some(phone)) = (member(pair, "watch"), member(pair, "phone")) else {
+        result.skipped.push(SkippedPair {
+          udids: Vec::new(),
+          note: format!("Skipped pair {}: missing watch or phone", pair_id),
+        });
+        continue;
+      };
+
+      let pair_state = pair.get("state").and_then(|v| v.as_str()).unwrap_or("");
+      if pair_state.contains("unavailable") {
+        result.skipped.push(SkippedPair {
+          udids: vec![phone.udid, watch.udid],
+          note: format!(
+            "Skipped pair {} <-> {}: pair is unavailable",
+            phone.name, watch.name
+          ),
+        });
+        continue;
+      }
+
+      result.pairs.push(SimulatorPair { watch, phone });
+    }
+  }
+
+  Ok(result)
+}
+
+/// Link each simulator to its pair counterparts.
+///
+/// Pairs with a member that is not among the available simulators are moved
+/// to `pairs.skipped` with a note.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn attach_pairs(simulators: &mut [IOSSimulator], pairs: &mut SimulatorPairs) {
+  let mut kept = Vec::new();
+  for pair in pairs.pairs.drain(..) {
+    let missing = [("phone", &pair.phone), ("watch", &pair.watch)]
+      .into_iter()
+      .find(|(_, member)| !simulators.iter().any(|s| s.udid == member.udid));
+    if let Some((role, member)) = missing {
+      pairs.skipped.push(SkippedPair {
+        udids: vec![pair.phone.udid.clone(), pair.watch.udid.clone()],
+        note: format!(
+          "Skipped pair {} <-> {}: {} '{}' is unavailable",
+          pair.phone.name, pair.watch.name, role, member.name
+        ),
+      });
+      continue;
+    }
+
+    for sim in simulators.iter_mut() {
+      if sim.udid == pair.phone.udid {
+        sim.paired.push(pair.watch.clone());
+      } else if sim.udid == pair.watch.udid {
+        sim.paired.push(pair.phone.clone());
       }
     }
+    kept.push(pair);
+  }
+  pairs.pairs = kept;
+}
+
+/// How to launch a device; each option says which platforms support it
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LaunchOptions {
+  /// Also boot the paired watch/phone simulator (iOS only)
+  pub with_paired: bool,
+  /// Skip loading the quick-boot snapshot (Android only)
+  pub cold_boot: bool,
+  /// Show the emulator window fullscreen (Android only)
+  pub fullscreen: bool,
+  /// Start with the emulator window hidden (Android only)
+  pub hide_window: bool,
+  /// Run without a window at all (Android only)
+  pub headless: bool,
+  /// Reset the device to a clean state before booting it
+  pub wipe_data: bool,
+  /// Boot without bringing up Simulator.app (iOS only)
+  pub no_gui: bool,
+}
+
+impl LaunchOptions {
+  fn any_window_option(&self) -> bool {
+    self.fullscreen || self.hide_window || self.headless
+  }
+}
+
+/// How the emulator window is shown; the modes are mutually exclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WindowMode {
+  Fullscreen,
+  Hidden,
+  Headless,
+}
+
+impl WindowMode {
+  fn arg(self) -> &'static str {
+    match self {
+      WindowMode::Fullscreen => "-fullscreen",
+      WindowMode::Hidden => "-qt-hide-window",
+      WindowMode::Headless => "-no-window",
+    }
+  }
+
+  fn note(self) -> &'static str {
+    match self {
+      WindowMode::Fullscreen => "fullscreen",
+      WindowMode::Hidden => "window hidden",
+      WindowMode::Headless => "headless",
+    }
+  }
+}
+
+/// The one window mode among `requested` (flag set, its name, mode), or an
+/// error naming the conflicting flags
+fn window_mode(requested: &[(bool, &str, WindowMode)]) -> Result<Option<WindowMode>, String> {
+  let set: Vec<_> = requested.iter().filter(|(on, _, _)| *on).collect();
+  match set.as_slice() {
+    [] => Ok(None),
+    [(_, _, mode)] => Ok(Some(*mode)),
+    _ => Err(format!(
+      "{} can't be used together",
+      set
+        .iter()
+        .map(|(_, name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(" and ")
+    )),
   }
+}
 
-  Ok(simulators)
+/// Emulator flags for one launch, and a note on each for the launch message
+#[derive(Debug, Default)]
+struct AndroidLaunchArgs {
+  args: Vec<&'static str>,
+  notes: Vec<&'static str>,
 }
 
-pub fn open_android_emulator(name: &str) -> Result<String, String> {
+/// Build the emulator flags for `options`. Window options given for this
+/// launch replace the AVD's `[devices.<id>]` ones rather than combining.
+fn android_launch_args(avd_id: &str, options: &LaunchOptions) -> Result<AndroidLaunchArgs, String> {
+  if options.with_paired {
+    return Err(ERR_PAIRED_IOS_ONLY.to_string());
+  }
+  if options.no_gui {
+    return Err(ERR_NO_GUI_IOS_ONLY.to_string());
+  }
+  let mut launch = AndroidLaunchArgs::default();
+  if options.cold_boot {
+    launch.args.push("-no-snapshot-load");
+    launch.notes.push("cold boot");
+  }
+  if options.wipe_data {
+    launch.args.push("-wipe-data");
+    launch.notes.push("data wiped");
+  }
+
+  let mode = match window_mode(&[
+    (options.fullscreen, "--fullscreen", WindowMode::Fullscreen),
+    (options.hide_window, "--hide-window", WindowMode::Hidden),
+    (options.headless, "--headless", WindowMode::Headless),
+  ])? {
+    Some(mode) => Some(mode),
+    None => {
+      let device = config::device_config(avd_id);
+      window_mode(&[
+        (
+          device.fullscreen == Some(true),
+          "fullscreen",
+          WindowMode::Fullscreen,
+        ),
+        (
+          device.hide_window == Some(true),
+          "hide_window",
+          WindowMode::Hidden,
+        ),
+      ])
+      .map_err(|e| format!("[devices.{}]: {}", avd_id, e))?
+    }
+  };
+  if let Some(mode) = mode {
+    launch.args.push(mode.arg());
+    launch.notes.push(mode.note());
+  }
+  Ok(launch)
+}
+
+pub fn open_android_emulator(name: &str, options: &LaunchOptions) -> Result<String, String> {
+  let launch = android_launch_args(name, options)?;
+  launch_android_emulator(name, &launch.args)?;
+  if launch.notes.is_empty() {
+    Ok(format!("Launching Android emulator: {}", name))
+  } else {
+    Ok(format!(
+      "Launching Android emulator: {} ({})",
+      name,
+      launch.notes.join(", ")
+    ))
+  }
+}
+
+/// Boot an AVD from one of its named snapshots
+pub fn open_android_emulator_from_snapshot(name: &str, snapshot: &str) -> Result<String, String> {
+  if !snapshots::is_valid_name(snapshot) {
+    return Err(format!("Invalid snapshot name '{}'", snapshot));
+  }
+  launch_android_emulator(name, &["-snapshot", snapshot])?;
+  Ok(format!(
+    "Launching Android emulator: {} (snapshot {})",
+    name, snapshot
+  ))
+}
+
+// Windows process creation flag for the same purpose as a Unix process group
+#[cfg(windows)]
+const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+
+/// Spawn `emulator -avd <name>` with `extra_args`, then the configured
+/// `android_launch_args` and the AVD's own `launch_args`
+fn launch_android_emulator(name: &str, extra_args: &[&str]) -> Result<(), String> {
+  // The emulator has no `--`, so a dash-leading id would be read as a flag
+  if !is_valid_avd_id(name) {
+    return Err(format!("Invalid AVD id '{}'", name));
+  }
+  // Fail now rather than with the emulator's own error after it starts
+  avd_launchable(name, config::android_sdk_root().as_deref())?;
   let emulator_cmd = get_android_emulator_cmd()?;
 
-  std::process::Command::new(&emulator_cmd)
+  let mut command = std::process::Command::new(&emulator_cmd);
+  command
     .args(["-avd", name])
+    .args(extra_args)
+    .args(config::android_launch_args(name))
     .stdin(Stdio::null())
     .stdout(Stdio::null())
-    .stderr(Stdio::null())
-    .spawn()
+    .stderr(Stdio::null());
+  // A process group of its own, so Ctrl+C in the terminal (say, to stop an
+  // `open` queue) doesn't take the emulator down with emulaunch
+  #[cfg(unix)]
+  std::os::unix::process::CommandExt::process_group(&mut command, 0);
+  #[cfg(windows)]
+  std::os::windows::process::CommandExt::creation_flags(&mut command, CREATE_NEW_PROCESS_GROUP);
+  runner::spawn(&mut command)
     .map_err(|e| format!("Failed to launch emulator '{}': {}", name, e))?;
+  Ok(())
+}
 
-  Ok(format!("Launching Android emulator: {}", name))
+/// Boot a simulator, then bring up Simulator.app unless `gui` is false
+#[cfg(target_os = "macos")]
+pub fn open_ios_simulator(udid: &str, gui: bool) -> Result<String, String> {
+  let already_booted = |output: &std::process::Output| {
+    String::from_utf8_lossy(&output.stderr)
+      .contains("Unable to boot device in current state: Booted")
+  };
+  let result = run_simctl(&["boot", udid], already_booted)?;
+  if !result.status.success() && !already_booted(&result) {
+    return Err(format!(
+      "Failed to boot simulator: {}",
+      String::from_utf8_lossy(&result.stderr)
+    ));
+  }
+
+  let was_booted = already_booted(&result);
+  if !gui {
+    return Ok(if was_booted {
+      format!("iOS simulator already booted: {}", udid)
+    } else {
+      format!("Booted iOS simulator headlessly: {}", udid)
+    });
+  }
+  bring_simulator_app_forward();
+
+  Ok(if was_booted {
+    format!(
+      "iOS simulator already booted, bringing Simulator to front: {}",
+      udid
+    )
+  } else {
+    format!("Opening iOS simulator: {}", udid)
+  })
 }
 
 #[cfg(target_os = "macos")]
-pub fn open_ios_simulator(udid: &str) -> Result<String, String> {
-  let xcrun = get_xcrun_cmd()?;
+fn bring_simulator_app_forward() {
+  let _ = runner::spawn(
+    std::process::Command::new("open")
+      .args(["-a", "Simulator"])
+      .stdin(Stdio::null())
+      .stdout(Stdio::null())
+      .stderr(Stdio::null()),
+  );
+}
 
-  let boot_output = std::process::Command::new(&xcrun)
-    .args(["simctl", "boot", udid])
-    .stdin(Stdio::null())
-    .stdout(Stdio::null())
-    .stderr(Stdio::piped())
-    .output();
+/// Bring a running device's window to the front: Simulator.app for a
+/// simulator, the emulator window for an AVD (System Events on macOS,
+/// wmctrl elsewhere)
+pub fn focus_device(key: &EntryKey) -> Result<String, String> {
+  match key {
+    EntryKey::Android(id) if genymotion_uuid(id).is_some() => {
+      Err(ERR_GENYMOTION_AVD_ONLY.to_string())
+    }
+    EntryKey::Android(id) => {
+      if !is_valid_avd_id(id) {
+        return Err(format!("Invalid AVD id '{}'", id));
+      }
+      focus_android_window(id)?;
+      Ok(format!("Brought {} to the front", id))
+    }
+    EntryKey::Ios(udid) => focus_simulator(udid),
+  }
+}
+
+#[cfg(target_os = "macos")]
+fn focus_simulator(udid: &str) -> Result<String, String> {
+  bring_simulator_app_forward();
+  Ok(format!("Bringing Simulator to front: {}", udid))
+}
+
+#[cfg(not(target_os = "macos"))]
+fn focus_simulator(_udid: &str) -> Result<String, String> {
+  Err(ERR_IOS_ONLY_MACOS.to_string())
+}
+
+// Emulator windows are titled "Android Emulator - <avd>:<port>"
+fn emulator_window_marker(id: &str) -> String {
+  format!(" - {}:", id)
+}
+
+#[cfg(target_os = "macos")]
+fn focus_android_window(id: &str) -> Result<(), String> {
+  // `id` passed is_valid_avd_id, so it can't break out of the string
+  let script = format!(
+    r#"tell application "System Events"
+  repeat with p in (processes whose name starts with "qemu-system")
+    repeat with w in windows of p
+      if name of w contains "{}" then
+        set frontmost of p to true
+        perform action "AXRaise" of w
+        return
+      end if
+    end repeat
+  end repeat
+end tell
+error "no window""#,
+    emulator_window_marker(id)
+  );
+  let output = runner::output(std::process::Command::new("osascript").args(["-e", &script]))
+    .map_err(|e| format!("Failed to run osascript: {}", e))?;
+  if !output.status.success() {
+    return Err(format!("No emulator window found for {}", id));
+  }
+  Ok(())
+}
 
-  match boot_output {
-    Ok(result) => {
-      let stderr = String::from_utf8_lossy(&result.stderr);
-      if !result.status.success()
-        && !stderr.contains("Unable to boot device in current state: Booted")
+#[cfg(not(target_os = "macos"))]
+fn focus_android_window(id: &str) -> Result<(), String> {
+  if !config::command_exists("wmctrl") {
+    return Err("Bringing an emulator window forward needs wmctrl".to_string());
+  }
+  let output =
+    runner::output(std::process::Command::new("wmctrl").args(["-a", &emulator_window_marker(id)]))
+      .map_err(|e| format!("Failed to run wmctrl: {}", e))?;
+  if !output.status.success() {
+    return Err(format!("No emulator window found for {}", id));
+  }
+  Ok(())
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn open_ios_simulator(_udid: &str, _gui: bool) -> Result<String, String> {
+  Err(ERR_IOS_ONLY_MACOS.to_string())
+}
+
+/// Boot a simulator together with its paired watch or phone.
+///
+/// Each boot is reported on its own line; the result is an error if any of
+/// them failed or if the simulator has no available pair.
+#[cfg(target_os = "macos")]
+pub fn open_ios_simulator_with_paired(udid: &str, gui: bool) -> Result<String, String> {
+  let (simulators, pairs) = list_ios_simulators_with_pairs()?;
+  let sim = simulators
+    .iter()
+    .find(|s| s.udid == udid)
+    .ok_or_else(|| format!("Simulator '{}' not found", udid))?;
+
+  let notes: Vec<&str> = pairs
+    .skipped
+    .iter()
+    .filter(|p| p.udids.iter().any(|u| u == udid))
+    .map(|p| p.note.as_str())
+    .collect();
+
+  if sim.paired.is_empty() {
+    let mut msg = format!("Simulator '{}' has no available paired device", sim.name);
+    for note in notes {
+      msg.push_str(&format!("\n  {}", note));
+    }
+    return Err(msg);
+  }
+
+  let mut lines = Vec::new();
+  let mut failed = false;
+  for (name, target) in
+    std::iter::once((&sim.name, &sim.udid)).chain(sim.paired.iter().map(|p| (&p.name, &p.udid)))
+  {
+    match open_ios_simulator(target, gui) {
+      Ok(_) if gui => lines.push(format!("Booted {}", name)),
+      Ok(_) => lines.push(format!("Booted {} headlessly", name)),
+      Err(e) => {
+        failed = true;
+        lines.push(format!("Failed to boot {}: {}", name, e));
+      }
+    }
+  }
+  lines.extend(notes.into_iter().map(|n| n.to_string()));
+
+  if failed {
+    Err(lines.join("\n"))
+  } else {
+    Ok(lines.join("\n"))
+  }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn open_ios_simulator_with_paired(_udid: &str, _gui: bool) -> Result<String, String> {
+  Err(ERR_IOS_ONLY_MACOS.to_string())
+}
+
+/// A listed device, as seen by name lookups
+struct NamedDevice {
+  key: EntryKey,
+  name: String,
+  /// AVD id or simulator udid
+  id: String,
+  state: String,
+}
+
+/// Every listed device across platforms; platforms that fail to list are
+/// left out
+fn named_devices() -> Vec<NamedDevice> {
+  let android = list_android_emulators()
+    .unwrap_or_default()
+    .into_iter()
+    .map(|e| NamedDevice {
+      key: EntryKey::Android(e.id.clone()),
+      name: text::normalize_whitespace(&e.name),
+      id: e.id,
+      state: e.state,
+    });
+  let ios = list_ios_simulators()
+    .unwrap_or_default()
+    .into_iter()
+    .map(|s| NamedDevice {
+      key: EntryKey::Ios(s.udid.clone()),
+      name: text::normalize_whitespace(&s.name),
+      id: s.udid,
+      state: s.state,
+    });
+  android.chain(ios).collect()
+}
+
+/// Device names for shell completion, without duplicates. AVDs come from
+/// the directory scan, so no adb or emulator process is started.
+pub fn completion_names() -> Vec<String> {
+  let android = list_android_emulators_fast()
+    .unwrap_or_default()
+    .into_iter()
+    .map(|e| e.name);
+  let ios = list_ios_simulators()
+    .unwrap_or_default()
+    .into_iter()
+    .map(|s| s.name);
+  let mut seen = HashSet::new();
+  android
+    .chain(ios)
+    .map(|name| text::normalize_whitespace(&name))
+    .filter(|name| seen.insert(name.clone()))
+    .collect()
+}
+
+/// Find a device whose name or id is exactly `name`, without guessing.
+/// Used where a wrong guess would be destructive.
+pub fn find_emulator_exact(name: &str) -> Result<EntryKey, String> {
+  let name = text::normalize_whitespace(name);
+  named_devices()
+    .into_iter()
+    .find(|d| d.name == name || d.id == name)
+    .map(|d| d.key)
+    .ok_or_else(|| format!("Emulator '{}' not found", name))
+}
+
+/// Find a device by name, id or udid.
+///
+/// An exact name or id wins (ignoring stray whitespace, but not case, so
+/// names differing only by case can still be told apart). Otherwise names
+/// are matched with the same matcher as the TUI filter (case-insensitive
+/// equality, substring, then fuzzy), and AVD ids by substring. Only the
+/// best tier of matches counts: one match is returned, several are an error
+/// listing them.
+pub fn find_emulator(name: &str) -> Result<EntryKey, String> {
+  find_in(&named_devices(), name)
+}
+
+/// A name given on the command line and the device it resolved to
+pub type NameLookup = (String, Result<EntryKey, String>);
+
+/// The devices named by `open`'s arguments. The joined arguments are tried
+/// first, so `open iPhone 15 Pro` still means one device; otherwise each
+/// argument is looked up on its own, with a result per name. When none of
+/// them match alone, the joined lookup's error is returned.
+pub fn find_emulators(args: &[String]) -> Result<Vec<NameLookup>, String> {
+  let devices = named_devices();
+  let joined = args.join(" ");
+  // `open 1 3` is two devices, each an exact name or else a list index
+  if args.len() > 1 && args.iter().all(|arg| is_list_index(arg)) {
+    return Ok(
+      args
+        .iter()
+        .map(|arg| (arg.clone(), find_in(&devices, arg)))
+        .collect(),
+    );
+  }
+  let joined_err = match find_in(&devices, &joined) {
+    Ok(key) => return Ok(vec![(joined, Ok(key))]),
+    Err(e) => e,
+  };
+  if args.len() < 2 {
+    return Err(joined_err);
+  }
+  let found: Vec<_> = args
+    .iter()
+    .map(|arg| (arg.clone(), find_in(&devices, arg)))
+    .collect();
+  if found.iter().all(|(_, key)| key.is_err()) {
+    return Err(joined_err);
+  }
+  Ok(found)
+}
+
+/// `find_emulators` for destructive launches: the joined arguments, else
+/// each one, must be a device's exact name or id. No fuzzy matches or
+/// list indices, as with `delete` and `erase`.
+pub fn find_emulators_exact(args: &[String]) -> Result<Vec<NameLookup>, String> {
+  let joined = args.join(" ");
+  let joined_err = match find_emulator_exact(&joined) {
+    Ok(key) => return Ok(vec![(joined, Ok(key))]),
+    Err(e) => e,
+  };
+  if args.len() < 2 {
+    return Err(joined_err);
+  }
+  let found: Vec<_> = args
+    .iter()
+    .map(|arg| (arg.clone(), find_emulator_exact(arg)))
+    .collect();
+  if found.iter().all(|(_, key)| key.is_err()) {
+    return Err(joined_err);
+  }
+  Ok(found)
+}
+
+/// Whether a lookup is a number from `list`'s output rather than a name
+fn is_list_index(name: &str) -> bool {
+  !name.is_empty() && name.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// The device numbered `index` in `list`'s output. `named_devices` lists
+/// in the same order, so the numbers hold while the device set is unchanged.
+/// Callers try an exact name first, so an AVD named `2` is still found.
+fn find_by_index(devices: &[NamedDevice], index: &str) -> Result<EntryKey, String> {
+  match index.parse::<usize>() {
+    Ok(n) if (1..=devices.len()).contains(&n) => Ok(devices[n - 1].key.clone()),
+    _ if devices.is_empty() => Err(format!("No device {}; no devices are listed", index)),
+    _ => Err(format!(
+      "No device {}; valid indices are 1-{} (see `emulaunch list`)",
+      index,
+      devices.len()
+    )),
+  }
+}
+
+fn find_in(devices: &[NamedDevice], name: &str) -> Result<EntryKey, String> {
+  let name = text::normalize_whitespace(name);
+  let name = name.as_str();
+  if let Some(device) = devices.iter().find(|d| d.name == name || d.id == name) {
+    return Ok(device.key.clone());
+  }
+  if is_list_index(name) {
+    return find_by_index(devices, name);
+  }
+
+  let query = text::fold(name);
+  let mut candidates: Vec<(text::MatchQuality, &NamedDevice)> = devices
+    .iter()
+    .filter_map(|d| {
+      let by_id = match d.key {
+        EntryKey::Android(_) => text::match_quality(&d.id, &query)
+          .filter(|q| !matches!(q, text::MatchQuality::Fuzzy { .. })),
+        EntryKey::Ios(_) => None,
+      };
+      let quality = text::match_quality(&d.name, &query)
+        .into_iter()
+        .chain(by_id)
+        .min()?;
+      Some((quality, d))
+    })
+    .collect();
+  let Some(best) = candidates.iter().map(|(q, _)| *q).min() else {
+    return Err(format!("Emulator '{}' not found", name));
+  };
+  candidates.retain(|(q, _)| q.same_tier(best));
+  candidates.sort_by_key(|(q, _)| *q);
+
+  match candidates.as_slice() {
+    [(_, device)] => Ok(device.key.clone()),
+    _ => Err(format!(
+      "'{}' matches several devices:\n{}",
+      name,
+      candidates
+        .iter()
+        .map(|(_, d)| format!("  {} [{}] ({})", d.name, d.state, d.key))
+        .collect::<Vec<_>>()
+        .join("\n")
+    )),
+  }
+}
+
+/// Check that `options` apply to this device, before anything is launched
+/// or wiped
+pub fn check_launch(key: &EntryKey, options: &LaunchOptions) -> Result<(), String> {
+  match key {
+    EntryKey::Android(id) if genymotion_uuid(id).is_some() => {
+      if options.with_paired
+        || options.cold_boot
+        || options.any_window_option()
+        || options.wipe_data
+        || options.no_gui
       {
-        return Err(format!("Failed to boot simulator: {}", stderr));
+        return Err(ERR_GENYMOTION_OPTIONS.to_string());
       }
     }
-    Err(e) => return Err(format!("Failed to run simctl boot: {}", e)),
+    EntryKey::Android(id) => {
+      android_launch_args(id, options)?;
+    }
+    EntryKey::Ios(_) if options.cold_boot => return Err(ERR_COLD_BOOT_ANDROID_ONLY.to_string()),
+    EntryKey::Ios(_) if options.any_window_option() => {
+      return Err(ERR_WINDOW_ANDROID_ONLY.to_string())
+    }
+    EntryKey::Ios(_) => {}
+  }
+  // A failed state query blocks the wipe too, as the device may be running
+  if options.wipe_data {
+    ensure_not_booted(key, false).map_err(|e| {
+      if e == ERR_DEVICE_BOOTED {
+        ERR_WIPE_BOOTED.to_string()
+      } else {
+        e
+      }
+    })?;
   }
+  Ok(())
+}
 
-  let _ = std::process::Command::new("open")
-    .args(["-a", "Simulator"])
-    .stdin(Stdio::null())
-    .stdout(Stdio::null())
-    .stderr(Stdio::null())
-    .spawn();
+/// Open a device, optionally with its paired simulator
+pub fn open_key(key: &EntryKey, options: &LaunchOptions) -> Result<String, String> {
+  check_launch(key, options)?;
+  match key {
+    EntryKey::Android(id) => match genymotion_uuid(id) {
+      Some(uuid) => open_genymotion_device(uuid),
+      None => open_android_emulator(id, options),
+    },
+    EntryKey::Ios(udid) => {
+      let mut message = String::new();
+      if options.wipe_data {
+        erase_ios_simulator(udid)?;
+        message = format!("Erased simulator: {}\n", udid);
+      }
+      let gui = !options.no_gui && config::ios_open_gui();
+      if options.with_paired {
+        message.push_str(&open_ios_simulator_with_paired(udid, gui)?);
+      } else {
+        message.push_str(&open_ios_simulator(udid, gui)?);
+      }
+      Ok(message)
+    }
+  }
+}
+
+/// Whether `target` has the shape of a local emulator serial (`emulator-5554`)
+fn looks_like_emulator_serial(target: &str) -> bool {
+  target
+    .strip_prefix("emulator-")
+    .is_some_and(|port| !port.is_empty() && port.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Whether `target` has the shape of a simulator UDID (a hyphenated UUID)
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn looks_like_udid(target: &str) -> bool {
+  let groups: Vec<&str> = target.split('-').collect();
+  groups.len() == 5
+    && groups
+      .iter()
+      .zip([8, 4, 4, 4, 12])
+      .all(|(g, len)| g.len() == len && g.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Whether adb reports a device with this serial as online, from a single
+/// `adb devices` call
+fn adb_serial_online(serial: &str) -> bool {
+  let Ok(adb_cmd) = get_adb_cmd() else {
+    return false;
+  };
+  match run_adb_query(&adb_cmd, &["devices"]) {
+    Ok(output) if output.status.success() => {
+      adb::parse_devices(&String::from_utf8_lossy(&output.stdout))
+        .iter()
+        .any(|d| d.serial == serial && d.is_online())
+    }
+    _ => false,
+  }
+}
 
-  Ok(format!("Opening iOS simulator: {}", udid))
+/// Whether simctl knows a simulator with this UDID, without listing pairs
+#[cfg(target_os = "macos")]
+fn simulator_exists(udid: &str) -> bool {
+  let Ok(output) = run_simctl(&["list", "devices", "--json"], |_| false) else {
+    return false;
+  };
+  output.status.success()
+    && parse_ios_simulators(&String::from_utf8_lossy(&output.stdout))
+      .is_ok_and(|parsed| parsed.simulators.iter().any(|s| s.udid == udid))
 }
 
 #[cfg(not(target_os = "macos"))]
-pub fn open_ios_simulator(_udid: &str) -> Result<String, String> {
+fn simulator_exists(_udid: &str) -> bool {
+  false
+}
+
+/// Resolve a device given by adb serial, UDID, name or id.
+///
+/// Serials and UDIDs are verified with one cheap query and used directly,
+/// skipping the full listing. Anything else, including a serial- or
+/// UDID-shaped string that matches no device, falls back to `find_emulator`
+/// so a device literally named that way still resolves.
+pub fn resolve_device(target: &str) -> Result<EntryKey, String> {
+  if looks_like_emulator_serial(target) && adb_serial_online(target) {
+    return Ok(EntryKey::Android(target.to_string()));
+  }
+  if looks_like_udid(target) && simulator_exists(target) {
+    return Ok(EntryKey::Ios(target.to_string()));
+  }
+  find_emulator(target)
+}
+
+/// Serial of the running emulator for an AVD id (or serial) from a fresh adb query
+fn running_serial(id: &str) -> Result<Option<String>, String> {
+  if genymotion_uuid(id).is_some() {
+    return Ok(
+      list_genymotion_devices()?
+        .into_iter()
+        .find(|d| d.id == id)
+        .and_then(|d| d.serial),
+    );
+  }
+  Ok(
+    get_running_avds()?
+      .into_iter()
+      .find(|(serial, name)| name == id || serial == id)
+      .map(|(serial, _)| serial),
+  )
+}
+
+/// Serial of the running emulator for an AVD id (or serial), or an error
+/// saying it isn't running
+fn serial_for_avd(id: &str) -> Result<String, String> {
+  running_serial(id)?.ok_or_else(|| format!("Emulator '{}' is not running", id))
+}
+
+/// Query the live state of a device, bypassing any cached listing
+pub fn device_state(key: &EntryKey) -> Result<String, String> {
+  match key {
+    EntryKey::Android(id) if genymotion_uuid(id).is_some() => list_genymotion_devices()?
+      .into_iter()
+      .find(|d| &d.id == id)
+      .map(|d| d.state)
+      .ok_or_else(|| format!("Genymotion device '{}' not found", id)),
+    EntryKey::Android(id) => Ok(if running_serial(id)?.is_some() {
+      STATE_BOOTED.to_string()
+    } else {
+      STATE_SHUTDOWN.to_string()
+    }),
+    EntryKey::Ios(udid) => list_ios_simulators()?
+      .into_iter()
+      .find(|s| &s.udid == udid)
+      .map(|s| s.state)
+      .ok_or_else(|| format!("Simulator '{}' not found", udid)),
+  }
+}
+
+/// The address an Android emulator reaches the host's loopback on
+pub const ANDROID_HOST_ALIAS: &str = "10.0.2.2";
+
+/// How a device is reached over the network, for `net-info`. Fields that
+/// need a booted device are `None` while it isn't.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct NetInfo {
+  pub platform: &'static str,
+  pub state: String,
+  pub serial: Option<String>,
+  pub console_port: Option<u16>,
+  pub adb_port: Option<u16>,
+  /// Android: the host's loopback as seen from the emulator
+  pub host_alias: Option<&'static str>,
+  /// Android: the device's own wlan0 address
+  pub wlan_ip: Option<String>,
+  /// iOS: the simulator's data directory on the host
+  pub data_path: Option<String>,
+  pub note: &'static str,
+}
+
+/// Network details of a device; the wlan address is queried from a booted
+/// emulator, and missing when it has no wlan0
+pub fn net_info(key: &EntryKey) -> Result<NetInfo, String> {
+  match key {
+    EntryKey::Android(id) if genymotion_uuid(id).is_some() => {
+      Err(ERR_GENYMOTION_AVD_ONLY.to_string())
+    }
+    EntryKey::Android(id) => {
+      let serial = running_serial(id)?;
+      let console_port = serial.as_deref().and_then(console::console_port);
+      let wlan_ip = serial.as_deref().and_then(android_wlan_ip);
+      Ok(NetInfo {
+        platform: "android",
+        state: if serial.is_some() {
+          STATE_BOOTED
+        } else {
+          STATE_SHUTDOWN
+        }
+        .to_string(),
+        serial,
+        console_port,
+        // The adb port is always the one after the console port
+        adb_port: console_port.and_then(|p| p.checked_add(1)),
+        host_alias: Some(ANDROID_HOST_ALIAS),
+        wlan_ip,
+        data_path: None,
+        note: "10.0.2.2 inside the emulator is the host's 127.0.0.1",
+      })
+    }
+    EntryKey::Ios(udid) => Ok(NetInfo {
+      platform: "ios",
+      state: device_state(key)?,
+      serial: None,
+      console_port: None,
+      adb_port: None,
+      host_alias: None,
+      wlan_ip: None,
+      data_path: simulator_data_path(udid).map(|p| p.display().to_string()),
+      note: "The simulator shares the host's network; localhost is this Mac",
+    }),
+  }
+}
+
+/// `adb shell ip -f inet addr show wlan0`, parsed
+fn android_wlan_ip(serial: &str) -> Option<String> {
+  let adb_cmd = get_adb_cmd().ok()?;
+  let output = runner::output(
+    std::process::Command::new(&adb_cmd)
+      .args([
+        "-s", serial, "shell", "ip", "-f", "inet", "addr", "show", "wlan0",
+      ])
+      .stdin(Stdio::null()),
+  )
+  .ok()?;
+  adb::parse_inet_addr(&String::from_utf8_lossy(&output.stdout)).map(|ip| ip.to_string())
+}
+
+/// Where CoreSimulator keeps a simulator's files
+fn simulator_data_path(udid: &str) -> Option<PathBuf> {
+  Some(
+    config::home_dir()?
+      .join("Library")
+      .join("Developer")
+      .join("CoreSimulator")
+      .join("Devices")
+      .join(udid)
+      .join("data"),
+  )
+}
+
+/// Shut down a running device
+pub fn stop_device(key: &EntryKey) -> Result<String, String> {
+  match key {
+    EntryKey::Android(id) if genymotion_uuid(id).is_some() => {
+      let uuid = genymotion_uuid(id).unwrap_or(id);
+      genymotion::stop(uuid)?;
+      Ok(format!("Stopped Genymotion device: {}", uuid))
+    }
+    EntryKey::Android(id) => {
+      let serial = serial_for_avd(id)?;
+      let adb_cmd = get_adb_cmd()?;
+      let output =
+        runner::output(std::process::Command::new(&adb_cmd).args(["-s", &serial, "emu", "kill"]))
+          .map_err(|e| format!("Failed to run adb emu kill: {}", e))?;
+      if !output.status.success() {
+        return Err(format!(
+          "Failed to stop emulator '{}': {}",
+          id,
+          String::from_utf8_lossy(&output.stderr)
+        ));
+      }
+      Ok(format!("Stopping Android emulator: {}", id))
+    }
+    EntryKey::Ios(udid) => shutdown_ios_simulator(udid),
+  }
+}
+
+#[cfg(target_os = "macos")]
+fn shutdown_ios_simulator(udid: &str) -> Result<String, String> {
+  let xcrun = get_xcrun_cmd()?;
+  let output = runner::output(
+    std::process::Command::new(&xcrun)
+      .args(["simctl", "shutdown", udid])
+      .stdin(Stdio::null()),
+  )
+  .map_err(|e| format!("Failed to run simctl shutdown: {}", e))?;
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  if !output.status.success() && !stderr.contains("current state: Shutdown") {
+    return Err(format!("Failed to shut down simulator: {}", stderr));
+  }
+  Ok(format!("Shutting down iOS simulator: {}", udid))
+}
+
+#[cfg(not(target_os = "macos"))]
+fn shutdown_ios_simulator(_udid: &str) -> Result<String, String> {
   Err(ERR_IOS_ONLY_MACOS.to_string())
 }
 
-pub fn find_emulator(name: &str) -> Result<EmulatorType, String> {
-  if let Ok(android) = list_android_emulators() {
-    if let Some(emu) = android.iter().find(|e| e.name == name || e.id == name) {
-      return Ok(EmulatorType::Android(emu.id.clone()));
+/// Error for a create whose name is already taken, naming the existing entry
+fn name_taken(name: &str, existing: &str, id: &str, state: &str) -> String {
+  format!(
+    "A device named '{}' already exists: {} ({}) [{}]",
+    name, existing, id, state
+  )
+}
+
+/// Create an AVD with `avdmanager create avd`
+pub fn create_android_emulator(
+  name: &str,
+  system_image: &str,
+  device: Option<&str>,
+) -> Result<String, String> {
+  let dir = avd_dir(name)?;
+  if let Some(emu) = list_android_emulators()
+    .unwrap_or_default()
+    .into_iter()
+    .find(|e| e.id == name || e.name == name)
+  {
+    return Err(name_taken(name, &emu.name, &emu.id, &emu.state));
+  }
+  if dir.exists() {
+    return Err(format!("{} already exists", dir.display()));
+  }
+
+  let avdmanager = get_avdmanager_cmd()?;
+  let mut command = std::process::Command::new(&avdmanager);
+  command.args(["create", "avd", "--name", name, "--package", system_image]);
+  if let Some(device) = device {
+    command.args(["--device", device]);
+  }
+  let mut child = command
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .map_err(|e| format!("Failed to run avdmanager: {}", e))?;
+  // Decline the interactive "custom hardware profile" prompt
+  if let Some(mut stdin) = child.stdin.take() {
+    use std::io::Write;
+    let _ = stdin.write_all(b"no\n");
+  }
+  let output = child
+    .wait_with_output()
+    .map_err(|e| format!("Failed to run avdmanager: {}", e))?;
+  runner::trace(&command, output.status);
+  if !output.status.success() {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let message = if stderr.trim().is_empty() {
+      stdout
+    } else {
+      stderr
+    };
+    return Err(format!("avdmanager failed: {}", message.trim()));
+  }
+  Ok(format!("Created Android emulator: {}", name))
+}
+
+/// Create a simulator with `simctl create`
+#[cfg(target_os = "macos")]
+pub fn create_ios_simulator(
+  name: &str,
+  device_type: &str,
+  runtime: &str,
+) -> Result<String, String> {
+  if name.is_empty() || name.starts_with('-') {
+    return Err(format!("Invalid simulator name '{}'", name));
+  }
+  // simctl allows duplicate names, but then the name can't be used to open it
+  if let Some(sim) = list_ios_simulators()?.into_iter().find(|s| s.name == name) {
+    return Err(name_taken(name, &sim.name, &sim.udid, &sim.state));
+  }
+
+  // Not retried: a create that timed out may still have gone through
+  let xcrun = get_xcrun_cmd()?;
+  let policy = runner::RetryPolicy::simctl();
+  let mut command = std::process::Command::new(&xcrun);
+  command
+    .args(["simctl", "create", name, device_type, runtime])
+    .stdin(Stdio::null());
+  let output = runner::output_with_timeout(&mut command, policy.timeout)
+    .map_err(|e| format!("Failed to run xcrun simctl create: {}", e))?;
+  if !output.status.success() {
+    return Err(format!(
+      "Failed to create simulator: {}",
+      String::from_utf8_lossy(&output.stderr).trim()
+    ));
+  }
+  Ok(format!(
+    "Created iOS simulator: {} ({})",
+    name,
+    String::from_utf8_lossy(&output.stdout).trim()
+  ))
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn create_ios_simulator(
+  _name: &str,
+  _device_type: &str,
+  _runtime: &str,
+) -> Result<String, String> {
+  Err(ERR_IOS_ONLY_MACOS.to_string())
+}
+
+/// Poll `done` until it returns true, returning how long that took, or
+/// `None` once `timeout` has elapsed. `tick` is called with the elapsed
+/// time after every poll that wasn't done yet.
+pub fn poll_until(
+  timeout: Duration,
+  mut tick: impl FnMut(Duration),
+  mut done: impl FnMut() -> Result<bool, String>,
+) -> Result<Option<Duration>, String> {
+  let start = Instant::now();
+  loop {
+    if done()? {
+      return Ok(Some(start.elapsed()));
     }
+    let elapsed = start.elapsed();
+    if elapsed >= timeout {
+      return Ok(None);
+    }
+    tick(elapsed);
+    std::thread::sleep(STATE_POLL_INTERVAL);
+  }
+}
+
+/// Poll the live state until it matches `state` or `timeout` elapses
+pub fn wait_for_state(key: &EntryKey, state: &str, timeout: Duration) -> Result<(), String> {
+  match poll_until(timeout, |_| {}, || Ok(device_state(key)? == state))? {
+    Some(_) => Ok(()),
+    None => Err(format!(
+      "Timed out after {}s waiting for {} to reach state {}",
+      timeout.as_secs(),
+      key,
+      state
+    )),
   }
+}
 
-  if let Ok(ios) = list_ios_simulators() {
-    if let Some(sim) = ios.iter().find(|s| s.name == name || s.udid == name) {
-      return Ok(EmulatorType::IOS(sim.udid.clone()));
+/// Whether Android reports that boot has completed (`sys.boot_completed`)
+fn android_boot_completed(serial: &str) -> bool {
+  let Ok(adb_cmd) = get_adb_cmd() else {
+    return false;
+  };
+  runner::output(
+    std::process::Command::new(&adb_cmd)
+      .args(["-s", serial, "shell", "getprop", "sys.boot_completed"])
+      .stdin(Stdio::null()),
+  )
+  .is_ok_and(|o| o.status.success() && String::from_utf8_lossy(&o.stdout).trim() == "1")
+}
+
+/// Whether a device has finished booting: Android must be in adb with
+/// `sys.boot_completed` set, iOS must report `Booted`. Errors count as "not
+/// yet", since adb and simctl are flaky during boot.
+pub fn boot_completed(key: &EntryKey) -> bool {
+  match key {
+    EntryKey::Android(id) => running_serial(id)
+      .ok()
+      .flatten()
+      .is_some_and(|serial| android_boot_completed(&serial)),
+    EntryKey::Ios(_) => device_state(key).is_ok_and(|state| state == STATE_BOOTED),
+  }
+}
+
+/// The first device of `platform` that has finished booting, if any
+pub fn any_boot_completed(platform: Platform) -> Result<Option<EntryKey>, String> {
+  match platform {
+    Platform::Android => Ok(
+      get_running_avds()?
+        .into_iter()
+        .find(|(serial, _)| android_boot_completed(serial))
+        .map(|(_, id)| EntryKey::Android(id)),
+    ),
+    Platform::Ios => Ok(
+      list_ios_simulators()?
+        .into_iter()
+        .find(|s| s.state == STATE_BOOTED)
+        .map(|s| EntryKey::Ios(s.udid)),
+    ),
+  }
+}
+
+/// Block until a just-launched device has finished booting (see
+/// `boot_completed`), returning how long it took. `progress` is called
+/// with the elapsed time every few seconds.
+pub fn wait_until_booted(
+  key: &EntryKey,
+  timeout: Duration,
+  mut progress: impl FnMut(Duration),
+) -> Result<Duration, String> {
+  let mut next_progress = BOOT_PROGRESS_INTERVAL;
+  let tick = |elapsed: Duration| {
+    if elapsed >= next_progress {
+      progress(elapsed);
+      next_progress += BOOT_PROGRESS_INTERVAL;
+    }
+  };
+  poll_until(timeout, tick, || Ok(boot_completed(key)))?.ok_or_else(|| {
+    format!(
+      "{} did not finish booting within {}s",
+      key,
+      timeout.as_secs()
+    )
+  })
+}
+
+/// Pre-flight check for destructive operations (delete, erase, wipe-data).
+///
+/// Checks the live state rather than a possibly stale listing. A booted device
+/// is refused unless `stop_first` is set, in which case it is shut down and
+/// waited on before returning.
+pub fn ensure_not_booted(key: &EntryKey, stop_first: bool) -> Result<(), String> {
+  if device_state(key)? != STATE_BOOTED {
+    return Ok(());
+  }
+  if !stop_first {
+    return Err(ERR_DEVICE_BOOTED.to_string());
+  }
+  stop_device(key)?;
+  wait_for_state(key, STATE_SHUTDOWN, STOP_TIMEOUT)
+}
+
+/// What deleting a device removes: the AVD's `.avd` directory and `.ini`
+/// file, or the simulator udid
+pub fn deletion_targets(key: &EntryKey) -> Result<Vec<String>, String> {
+  match key {
+    EntryKey::Android(id) => {
+      let dir = avd_dir(id)?;
+      Ok(vec![
+        dir.display().to_string(),
+        dir.with_extension("ini").display().to_string(),
+      ])
+    }
+    EntryKey::Ios(udid) => Ok(vec![udid.clone()]),
+  }
+}
+
+/// Delete a shut-down device and confirm it no longer shows up in the list
+pub fn delete_device(key: &EntryKey) -> Result<String, String> {
+  let targets = deletion_targets(key)?;
+  match key {
+    EntryKey::Android(id) => {
+      let avdmanager = get_avdmanager_cmd()?;
+      let output = runner::output(
+        std::process::Command::new(&avdmanager)
+          .args(["delete", "avd", "--name", id])
+          .stdin(Stdio::null()),
+      )
+      .map_err(|e| format!("Failed to run avdmanager: {}", e))?;
+      if !output.status.success() {
+        return Err(format!(
+          "avdmanager failed: {}",
+          String::from_utf8_lossy(&output.stderr).trim()
+        ));
+      }
+      let still_listed = list_android_emulators()
+        .unwrap_or_default()
+        .iter()
+        .any(|e| &e.id == id);
+      if still_listed || avd_dir(id)?.exists() {
+        return Err(format!(
+          "avdmanager reported success but '{}' still exists",
+          id
+        ));
+      }
+    }
+    EntryKey::Ios(udid) => {
+      delete_ios_simulator(udid)?;
+      if list_ios_simulators()?.iter().any(|s| &s.udid == udid) {
+        return Err(format!(
+          "simctl reported success but simulator {} still exists",
+          udid
+        ));
+      }
     }
   }
+  Ok(format!("Deleted {}:\n  {}", key, targets.join("\n  ")))
+}
+
+#[cfg(target_os = "macos")]
+fn delete_ios_simulator(udid: &str) -> Result<(), String> {
+  let output = run_simctl(&["delete", udid], |_| false)?;
+  if !output.status.success() {
+    return Err(format!(
+      "Failed to delete simulator: {}",
+      String::from_utf8_lossy(&output.stderr).trim()
+    ));
+  }
+  Ok(())
+}
+
+#[cfg(not(target_os = "macos"))]
+fn delete_ios_simulator(_udid: &str) -> Result<(), String> {
+  Err(ERR_IOS_ONLY_MACOS.to_string())
+}
+
+/// Reset a shut-down simulator's content and settings
+#[cfg(target_os = "macos")]
+fn erase_ios_simulator(udid: &str) -> Result<(), String> {
+  let output = run_simctl(&["erase", udid], |_| false)?;
+  if !output.status.success() {
+    return Err(format!(
+      "Failed to erase simulator: {}",
+      String::from_utf8_lossy(&output.stderr).trim()
+    ));
+  }
+  Ok(())
+}
+
+#[cfg(not(target_os = "macos"))]
+fn erase_ios_simulator(_udid: &str) -> Result<(), String> {
+  Err(ERR_IOS_ONLY_MACOS.to_string())
+}
+
+/// Erase a simulator's content and settings, shutting it down first if it
+/// is booted, and confirm it came back shut down
+pub fn erase_device(key: &EntryKey) -> Result<String, String> {
+  let EntryKey::Ios(udid) = key else {
+    return Err(ERR_ERASE_IOS_ONLY.to_string());
+  };
+  ensure_not_booted(key, true)?;
+  erase_ios_simulator(udid)?;
+  let state = device_state(key)?;
+  if state != STATE_SHUTDOWN {
+    return Err(format!(
+      "simctl reported success but simulator {} is {}",
+      udid, state
+    ));
+  }
+  Ok(format!("Erased iOS simulator: {}", udid))
+}
+
+/// Every shut-down simulator that can be erased, for `erase --all`
+pub fn erasable_simulators() -> Result<Vec<IOSSimulator>, String> {
+  Ok(
+    list_ios_simulators()?
+      .into_iter()
+      .filter(|s| s.state == STATE_SHUTDOWN && s.launchable.is_ok())
+      .collect(),
+  )
+}
 
-  Err(format!("Emulator '{}' not found", name))
+/// Send a raw console command to a running Android emulator
+pub fn send_console_command(key: &EntryKey, command: &str) -> Result<String, String> {
+  let EntryKey::Android(id) = key else {
+    return Err("The console is only available for Android emulators".to_string());
+  };
+  if genymotion_uuid(id).is_some() {
+    return Err(ERR_GENYMOTION_AVD_ONLY.to_string());
+  }
+  let serial = serial_for_avd(id)?;
+  console::run_command(&serial, command)
+}
+
+/// Run `adb root`, `remount`, `disable-verity` and the like against a
+/// running emulator and say what happened
+pub fn run_adbd_command(key: &EntryKey, command: adb::AdbdCommand) -> Result<String, String> {
+  let EntryKey::Android(id) = key else {
+    return Err(format!(
+      "`adb {}` only applies to Android emulators; iOS simulators have no adbd",
+      command.name()
+    ));
+  };
+  let serial = serial_for_avd(id)?;
+  let adb_cmd = get_adb_cmd()?;
+  let output = runner::output(
+    std::process::Command::new(&adb_cmd)
+      .args(["-s", &serial, command.name()])
+      .stdin(Stdio::null()),
+  )
+  .map_err(|e| format!("Failed to run adb {}: {}", command.name(), e))?;
+  // adb exits 0 for most refusals, so the text decides
+  let text = format!(
+    "{}\n{}",
+    String::from_utf8_lossy(&output.stdout),
+    String::from_utf8_lossy(&output.stderr)
+  );
+  let message = adb::interpret_adbd_reply(command, id, &text)?;
+  if command.restarts_adbd() {
+    // Later adb calls fail until adbd is back
+    let _ = runner::output(
+      std::process::Command::new(&adb_cmd)
+        .args(["-s", &serial, "wait-for-device"])
+        .stdin(Stdio::null()),
+    );
+  }
+  Ok(message)
 }
 
-/// Collect all emulators into a unified list with section headers
-pub fn collect_all_entries() -> Vec<EmulatorEntry> {
-  let mut entries = Vec::new();
+/// Capture a booted device's display as PNG bytes
+pub fn screenshot(key: &EntryKey) -> Result<Vec<u8>, String> {
+  let state = device_state(key)?;
+  if state != STATE_BOOTED {
+    return Err(format!(
+      "device is {}; only a booted device can be captured",
+      state
+    ));
+  }
+  match key {
+    EntryKey::Android(id) => android_screenshot(&serial_for_avd(id)?),
+    EntryKey::Ios(udid) => ios_screenshot(udid),
+  }
+}
 
-  let android = list_android_emulators().unwrap_or_default();
-  if !android.is_empty() {
-    entries.push(EmulatorEntry::SectionHeader(
-      SECTION_ANDROID_EMULATORS.to_string(),
+fn android_screenshot(serial: &str) -> Result<Vec<u8>, String> {
+  let adb_cmd = get_adb_cmd()?;
+  let output = runner::output(
+    std::process::Command::new(&adb_cmd)
+      .args(["-s", serial, "exec-out", "screencap", "-p"])
+      .stdin(Stdio::null()),
+  )
+  .map_err(|e| format!("Failed to run adb screencap: {}", e))?;
+  if !output.status.success() || !output.stdout.starts_with(PNG_SIGNATURE) {
+    return Err(format!(
+      "Failed to capture screenshot: {}",
+      String::from_utf8_lossy(&output.stderr).trim()
     ));
-    for emu in android {
-      entries.push(EmulatorEntry::Android(emu));
+  }
+  Ok(output.stdout)
+}
+
+// First bytes of every PNG file
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// simctl only writes screenshots to a file, so go through a temporary one
+#[cfg(target_os = "macos")]
+fn ios_screenshot(udid: &str) -> Result<Vec<u8>, String> {
+  let path = filelock::create_temp_file("emulaunch-screenshot", ".png")?;
+  let path_arg = path.to_string_lossy();
+  let output = match run_simctl(&["io", udid, "screenshot", &path_arg], |_| false) {
+    Ok(output) => output,
+    Err(e) => {
+      let _ = std::fs::remove_file(&path);
+      return Err(e);
     }
+  };
+  let png = if output.status.success() {
+    std::fs::read(&path).map_err(|e| format!("Failed to read screenshot: {}", e))
+  } else {
+    Err(format!(
+      "Failed to capture screenshot: {}",
+      String::from_utf8_lossy(&output.stderr).trim()
+    ))
+  };
+  let _ = std::fs::remove_file(&path);
+  png
+}
+
+#[cfg(not(target_os = "macos"))]
+fn ios_screenshot(_udid: &str) -> Result<Vec<u8>, String> {
+  Err(ERR_IOS_ONLY_MACOS.to_string())
+}
+
+/// Whether a string is safe to pass as an Android package or iOS bundle id.
+///
+/// `adb shell` joins its arguments into a remote shell command line, so the
+/// id is restricted to the characters real ids use.
+fn is_valid_app_id(id: &str) -> bool {
+  !id.is_empty()
+    && id
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_' || c == '-')
+}
+
+/// Translate a failed `simctl get_app_container` into a readable error
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn app_container_error(stderr: &str, bundle_id: &str, name: &str) -> String {
+  if stderr.contains("No such file or directory") {
+    format!("app {} is not installed on {}", bundle_id, name)
+  } else {
+    format!("Failed to look up app container: {}", stderr.trim())
   }
+}
 
-  let ios = list_ios_simulators().unwrap_or_default();
-  if !ios.is_empty() {
-    entries.push(EmulatorEntry::SectionHeader(
-      SECTION_IOS_SIMULATORS.to_string(),
+/// Resolve the path of an app's container on a device.
+///
+/// `kind` is `app`, `data` or `groups`. On iOS this wraps `simctl
+/// get_app_container`, which only works on a booted simulator. On Android it
+/// is best-effort: `app` prints the APK path(s) from `pm path`, and `data`
+/// uses `run-as`, which only works for debuggable apps.
+pub fn app_container(
+  key: &EntryKey,
+  name: &str,
+  app_id: &str,
+  kind: &str,
+) -> Result<String, String> {
+  if !is_valid_app_id(app_id) {
+    return Err(format!("Invalid bundle or package id '{}'", app_id));
+  }
+  if device_state(key)? != STATE_BOOTED {
+    return Err(format!("{} must be booted to look up app containers", name));
+  }
+
+  match key {
+    EntryKey::Ios(udid) => ios_app_container(udid, name, app_id, kind),
+    EntryKey::Android(id) => {
+      let serial = serial_for_avd(id)?;
+      android_app_container(&serial, name, app_id, kind)
+    }
+  }
+}
+
+#[cfg(target_os = "macos")]
+fn ios_app_container(
+  udid: &str,
+  name: &str,
+  bundle_id: &str,
+  kind: &str,
+) -> Result<String, String> {
+  let xcrun = get_xcrun_cmd()?;
+  let output = runner::output(
+    std::process::Command::new(&xcrun)
+      .args(["simctl", "get_app_container", udid, bundle_id, kind])
+      .stdin(Stdio::null()),
+  )
+  .map_err(|e| format!("Failed to run simctl get_app_container: {}", e))?;
+  if !output.status.success() {
+    return Err(app_container_error(
+      &String::from_utf8_lossy(&output.stderr),
+      bundle_id,
+      name,
     ));
-    for sim in ios {
-      entries.push(EmulatorEntry::IOS(sim));
+  }
+  Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(not(target_os = "macos"))]
+fn ios_app_container(
+  _udid: &str,
+  _name: &str,
+  _bundle_id: &str,
+  _kind: &str,
+) -> Result<String, String> {
+  Err(ERR_IOS_ONLY_MACOS.to_string())
+}
+
+fn android_app_container(
+  serial: &str,
+  name: &str,
+  package: &str,
+  kind: &str,
+) -> Result<String, String> {
+  let args: &[&str] = match kind {
+    "app" => &["pm", "path", package],
+    "data" => &["run-as", package, "pwd"],
+    _ => {
+      return Err(format!(
+        "Container type '{}' is not available for Android emulators",
+        kind
+      ))
+    }
+  };
+
+  let adb_cmd = get_adb_cmd()?;
+  let output = runner::output(
+    std::process::Command::new(&adb_cmd)
+      .args(["-s", serial, "shell"])
+      .args(args)
+      .stdin(Stdio::null()),
+  )
+  .map_err(|e| format!("Failed to run adb shell: {}", e))?;
+  let stdout = String::from_utf8_lossy(&output.stdout);
+  let stderr = String::from_utf8_lossy(&output.stderr);
+
+  match kind {
+    "app" => {
+      let paths: Vec<&str> = stdout
+        .lines()
+        .filter_map(|l| l.trim().strip_prefix("package:"))
+        .collect();
+      if paths.is_empty() {
+        return Err(format!("app {} is not installed on {}", package, name));
+      }
+      Ok(paths.join("\n"))
+    }
+    _ => {
+      // run-as reports failures on stdout on some Android versions
+      let dir = stdout.trim();
+      if !output.status.success() || !dir.starts_with('/') {
+        let reason = if stderr.trim().is_empty() {
+          dir
+        } else {
+          stderr.trim()
+        };
+        return Err(format!(
+          "run-as failed for {} (only debuggable apps are supported): {}",
+          package, reason
+        ));
+      }
+      Ok(dir.to_string())
     }
   }
+}
 
-  entries
+/// Reveal a path in Finder
+#[cfg(target_os = "macos")]
+pub fn reveal_in_finder(path: &str) -> Result<(), String> {
+  let status = runner::status(
+    std::process::Command::new("open")
+      .args(["-R", path])
+      .stdin(Stdio::null())
+      .stdout(Stdio::null())
+      .stderr(Stdio::null()),
+  )
+  .map_err(|e| format!("Failed to run open: {}", e))?;
+  if !status.success() {
+    return Err(format!("Failed to reveal {} in Finder", path));
+  }
+  Ok(())
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn reveal_in_finder(_path: &str) -> Result<(), String> {
+  Err("Revealing in Finder is only available on macOS".to_string())
+}
+
+/// List the device entries of one platform, without a section header.
+///
+/// `fast` skips Android state detection (see `list_android_emulators_fast`).
+pub fn list_platform_entries(platform: Platform, fast: bool) -> Result<Vec<EmulatorEntry>, String> {
+  match platform {
+    Platform::Android if fast => Ok(
+      list_android_emulators_fast()?
+        .into_iter()
+        .map(EmulatorEntry::Android)
+        .collect(),
+    ),
+    Platform::Android => Ok(
+      list_android_emulators()?
+        .into_iter()
+        .map(EmulatorEntry::Android)
+        .collect(),
+    ),
+    Platform::Ios => Ok(
+      list_ios_simulators()?
+        .into_iter()
+        .map(EmulatorEntry::IOS)
+        .collect(),
+    ),
+  }
+}
+
+/// List every supported platform, treating failures as empty sections
+pub fn collect_sections(fast: bool) -> Vec<(Platform, Vec<EmulatorEntry>)> {
+  Platform::supported()
+    .iter()
+    .map(|&platform| {
+      (
+        platform,
+        list_platform_entries(platform, fast).unwrap_or_default(),
+      )
+    })
+    .collect()
 }
 
 /// Open an emulator entry (non-header)
-pub fn open_entry(entry: &EmulatorEntry) -> Result<String, String> {
-  match entry {
-    EmulatorEntry::Android(e) => open_android_emulator(&e.id),
-    EmulatorEntry::IOS(s) => open_ios_simulator(&s.udid),
-    EmulatorEntry::SectionHeader(_) => Err("Cannot open a section header".to_string()),
+pub fn open_entry(entry: &EmulatorEntry, options: &LaunchOptions) -> Result<String, String> {
+  entry.launchable()?;
+  let key = entry
+    .key()
+    .ok_or_else(|| "Cannot open a section header".to_string())?;
+  open_key(&key, options)
+}
+
+/// The plain-text listing of the given platforms' sections, in order. Only
+/// those platforms are queried. With `numbered`, devices are prefixed with
+/// the index `open` accepts in place of a name.
+pub fn format_emulator_list(
+  platforms: &[Platform],
+  numbered: bool,
+  colors: Option<&theme::ThemeColors>,
+) -> String {
+  let truecolor = theme::truecolor_supported();
+  let paint = |text: &str, color: fn(&theme::ThemeColors) -> ratatui::style::Color| match colors {
+    Some(colors) => theme::paint(text, color(colors), truecolor),
+    None => text.to_string(),
+  };
+  let style = |part: present::Part, text: &str| match (part, colors) {
+    (present::Part::Name, _) => paint(text, |c| c.name_fg),
+    (present::Part::Platform(Platform::Android), _) => paint(text, |c| c.icon_android_fg),
+    (present::Part::Platform(Platform::Ios), _) => paint(text, |c| c.icon_ios_fg),
+    (present::Part::State(kind), Some(colors)) => {
+      theme::paint(text, theme::state_color(kind, colors), truecolor)
+    }
+    _ => paint(text, |c| c.meta_fg),
+  };
+  let listed: Vec<(Platform, Result<Vec<EmulatorEntry>, String>)> = platforms
+    .iter()
+    .map(|&platform| (platform, list_platform_entries(platform, false)))
+    .collect();
+  // Disambiguate across every listed platform, as the TUI does
+  let all: Vec<EmulatorEntry> = listed
+    .iter()
+    .filter_map(|(_, result)| result.as_ref().ok())
+    .flatten()
+    .cloned()
+    .collect();
+  let width = all.len().to_string().len();
+  let views: Vec<present::DeviceView> = present::views(&all).into_iter().flatten().collect();
+  // One set of columns for every section, so they line up with each other.
+  // Icons only go to a terminal, not into text that gets pasted elsewhere
+  let columns = present::Columns::fit(&views, colors.is_some() && config::icons()).capped();
+  let mut views = views.into_iter().enumerate();
+  let sections: Vec<String> = listed
+    .iter()
+    .map(|(platform, result)| {
+      let (noun, section) = match platform {
+        Platform::Android => ("Android emulators", SECTION_ANDROID_EMULATORS),
+        Platform::Ios => ("iOS simulators", SECTION_IOS_SIMULATORS),
+      };
+      let mut output = String::new();
+      match result {
+        Ok(entries) if !entries.is_empty() => {
+          output.push_str(&paint(&format!("{}:", section), |c| c.header_fg));
+          output.push('\n');
+          for (i, view) in views.by_ref().take(entries.len()) {
+            let line = view.aligned_line_with(&columns, style);
+            if numbered {
+              output.push_str(&format!("  {:>width$}. {}\n", i + 1, line));
+            } else {
+              output.push_str(&format!("  {}\n", line));
+            }
+          }
+        }
+        Ok(_) => output.push_str(&format!("No {} found\n", noun)),
+        Err(e) => output.push_str(&format!("{} error: {}\n", noun, e)),
+      }
+      output
+    })
+    .collect();
+  sections.join("\n")
+}
+
+/// A listing failure, kept out of the device arrays
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ListError {
+  pub platform: &'static str,
+  pub message: String,
+}
+
+/// Both device lists plus per-platform failures, served to scripts through
+/// `list_document`: `{"android": [...], "ios": [...], "errors": [...]}`
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct EmulatorList {
+  pub android: Vec<AndroidEmulator>,
+  pub ios: Vec<IOSSimulator>,
+  pub errors: Vec<ListError>,
+}
+
+impl EmulatorList {
+  /// Current state of every device, keyed by identity
+  pub fn states(&self) -> Vec<(EntryKey, &str, &str)> {
+    let android = self.android.iter().map(|a| {
+      (
+        EntryKey::Android(a.id.clone()),
+        a.name.as_str(),
+        a.state.as_str(),
+      )
+    });
+    let ios = self.ios.iter().map(|i| {
+      (
+        EntryKey::Ios(i.udid.clone()),
+        i.name.as_str(),
+        i.state.as_str(),
+      )
+    });
+    android.chain(ios).collect()
   }
 }
 
-/// Format a plain text list for the `list` subcommand
-pub fn format_emulator_list() -> String {
-  let mut output = String::new();
+/// List the given platforms; the others are left empty and not queried.
+/// A platform whose listing fails is left empty and reported in `errors`.
+pub fn collect_emulator_list(platforms: &[Platform]) -> EmulatorList {
+  let mut list = EmulatorList::default();
+  for &platform in platforms {
+    let (name, result) = match platform {
+      Platform::Android => (
+        "android",
+        list_android_emulators().map(|a| list.android = a),
+      ),
+      Platform::Ios => ("ios", list_ios_simulators().map(|i| list.ios = i)),
+    };
+    if let Err(message) = result {
+      list.errors.push(ListError {
+        platform: name,
+        message,
+      });
+    }
+  }
+  list
+}
 
-  match list_android_emulators() {
-    Ok(android) if !android.is_empty() => {
-      output.push_str(SECTION_ANDROID_EMULATORS);
-      output.push_str(":\n");
-      for emu in android {
-        output.push_str(&format!(
-          "  {} [{}] ({})\n",
-          emu.name, emu.state, emu.device_type
-        ));
+/// Placeholders `list --format` fills in
+const TEMPLATE_KEYS: &[&str] = &["name", "id", "state", "platform", "meta"];
+
+enum TemplatePart {
+  Text(String),
+  Key(&'static str),
+}
+
+/// Split a `--format` template into text and placeholders, resolving
+/// `\t`, `\n`, `\r` and `\\` escapes
+fn parse_template(template: &str) -> Result<Vec<TemplatePart>, String> {
+  let mut parts = Vec::new();
+  let mut text = String::new();
+  let mut chars = template.chars();
+  while let Some(c) = chars.next() {
+    match c {
+      '\\' => match chars.next() {
+        Some('t') => text.push('\t'),
+        Some('n') => text.push('\n'),
+        Some('r') => text.push('\r'),
+        Some('\\') => text.push('\\'),
+        Some(other) => {
+          text.push('\\');
+          text.push(other);
+        }
+        None => text.push('\\'),
+      },
+      '{' => {
+        let rest = chars.as_str();
+        let Some(end) = rest.find('}') else {
+          return Err(format!("Unclosed '{{' in --format: {}", template));
+        };
+        let name = &rest[..end];
+        chars = rest[end + 1..].chars();
+        let Some(key) = TEMPLATE_KEYS.iter().find(|&&k| k == name) else {
+          let valid: Vec<String> = TEMPLATE_KEYS.iter().map(|k| format!("{{{}}}", k)).collect();
+          return Err(format!(
+            "Unknown placeholder '{{{}}}' in --format; valid placeholders: {}",
+            name,
+            valid.join(", ")
+          ));
+        };
+        parts.push(TemplatePart::Text(std::mem::take(&mut text)));
+        parts.push(TemplatePart::Key(key));
       }
-      output.push('\n');
+      _ => text.push(c),
     }
-    Ok(_) => output.push_str("No Android emulators found\n\n"),
-    Err(e) => output.push_str(&format!("Android emulators error: {}\n\n", e)),
   }
+  parts.push(TemplatePart::Text(text));
+  Ok(parts)
+}
 
-  match list_ios_simulators() {
-    Ok(ios) if !ios.is_empty() => {
-      output.push_str(SECTION_IOS_SIMULATORS);
-      output.push_str(":\n");
-      for sim in ios {
-        output.push_str(&format!(
-          "  {} [{}] ({})\n",
-          sim.name, sim.state, sim.runtime
-        ));
+/// Every device in `list` with its view, Android first, as `list` shows them
+fn list_views(list: &EmulatorList) -> Vec<(EntryKey, present::DeviceView)> {
+  let entries: Vec<EmulatorEntry> = list
+    .android
+    .iter()
+    .cloned()
+    .map(EmulatorEntry::Android)
+    .chain(list.ios.iter().cloned().map(EmulatorEntry::IOS))
+    .collect();
+  entries
+    .iter()
+    .map(EmulatorEntry::key)
+    .zip(present::views(&entries))
+    .filter_map(|(key, view)| Some((key?, view?)))
+    .collect()
+}
+
+/// `list --format`: one line per device from a template of `{name}`,
+/// `{id}`, `{state}`, `{platform}` and `{meta}` (device type or runtime, or
+/// why the device can't be opened, as `list` shows it), without section
+/// headers. Listing failures are returned separately.
+pub fn format_emulator_list_template(
+  platforms: &[Platform],
+  template: &str,
+) -> Result<(String, Vec<ListError>), String> {
+  let parts = parse_template(template)?;
+  let list = collect_emulator_list(platforms);
+  let mut output = String::new();
+  for (key, view) in list_views(&list) {
+    let (platform, id) = match &key {
+      EntryKey::Android(id) => ("android", id),
+      EntryKey::Ios(udid) => ("ios", udid),
+    };
+    let fields = [
+      ("name", view.name.as_str()),
+      ("id", id),
+      ("state", &view.state),
+      ("platform", platform),
+      ("meta", &view.meta),
+    ];
+    for part in &parts {
+      match part {
+        TemplatePart::Text(text) => output.push_str(text),
+        TemplatePart::Key(key) => {
+          let value = fields.iter().find(|(k, _)| k == key).map_or("", |(_, v)| v);
+          output.push_str(value);
+        }
       }
     }
-    Ok(_) => output.push_str("No iOS simulators found\n"),
-    Err(e) => output.push_str(&format!("iOS simulators error: {}\n", e)),
+    output.push('\n');
   }
+  Ok((output, list.errors))
+}
+
+/// A device in `list --json`, with the `meta` that `list` and `{meta}` show
+#[derive(serde::Serialize)]
+struct WithMeta<'a, T> {
+  #[serde(flatten)]
+  device: &'a T,
+  meta: String,
+}
+
+#[derive(serde::Serialize)]
+pub struct ListDocument<'a> {
+  android: Vec<WithMeta<'a, AndroidEmulator>>,
+  ios: Vec<WithMeta<'a, IOSSimulator>>,
+  errors: &'a [ListError],
+}
+
+/// `list` as `list --json` and the serve `list` method return it
+pub fn list_document(list: &EmulatorList) -> ListDocument<'_> {
+  // list_views keeps the devices in order, Android first
+  let mut metas = list_views(list).into_iter().map(|(_, view)| view.meta);
+  ListDocument {
+    android: list
+      .android
+      .iter()
+      .zip(metas.by_ref())
+      .map(|(device, meta)| WithMeta { device, meta })
+      .collect(),
+    ios: list
+      .ios
+      .iter()
+      .zip(metas)
+      .map(|(device, meta)| WithMeta { device, meta })
+      .collect(),
+    errors: &list.errors,
+  }
+}
 
-  output
+/// The emulator list as a pretty-printed JSON document for `list --json`
+pub fn format_emulator_list_json(platforms: &[Platform]) -> Result<String, String> {
+  let list = collect_emulator_list(platforms);
+  serde_json::to_string_pretty(&list_document(&list)).map_err(|e| e.to_string())
 }
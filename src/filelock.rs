@@ -0,0 +1,188 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+// How long a writer waits for another process to release the lock
+const LOCK_TIMEOUT: Duration = Duration::from_secs(3);
+
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(20);
+
+// A lock older than this is assumed to belong to a crashed process
+const STALE_LOCK_AGE: Duration = Duration::from_secs(10);
+
+/// Advisory lock held as `<path>.lock` until dropped
+pub struct FileLock {
+  lock_path: PathBuf,
+}
+
+impl FileLock {
+  /// Acquire the lock guarding `path`, waiting for other holders and breaking
+  /// locks left behind by crashed processes
+  pub fn acquire(path: &Path) -> Result<Self, String> {
+    let lock_path = sibling_path(path, "lock");
+    let deadline = Instant::now() + LOCK_TIMEOUT;
+
+    loop {
+      match OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&lock_path)
+      {
+        Ok(mut file) => {
+          let _ = writeln!(file, "{}", std::process::id());
+          return Ok(FileLock { lock_path });
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+          if is_stale(&lock_path) {
+            let _ = std::fs::remove_file(&lock_path);
+            continue;
+          }
+          if Instant::now() >= deadline {
+            return Err(format!(
+              "Timed out waiting for lock {} (another emulaunch may be running)",
+              lock_path.display()
+            ));
+          }
+          std::thread::sleep(LOCK_RETRY_INTERVAL);
+        }
+        Err(e) => {
+          return Err(format!(
+            "Failed to create lock {}: {}",
+            lock_path.display(),
+            e
+          ))
+        }
+      }
+    }
+  }
+}
+
+impl Drop for FileLock {
+  fn drop(&mut self) {
+    let _ = std::fs::remove_file(&self.lock_path);
+  }
+}
+
+fn is_stale(lock_path: &Path) -> bool {
+  std::fs::metadata(lock_path)
+    .and_then(|m| m.modified())
+    .ok()
+    .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+    .is_some_and(|age| age > STALE_LOCK_AGE)
+}
+
+// Names tried before giving up on a fresh temp path
+const TEMP_ATTEMPTS: u32 = 16;
+
+/// A name in the temp dir that other processes can't predict: the pid and
+/// a random suffix
+fn temp_path(prefix: &str, extension: &str) -> PathBuf {
+  use std::hash::BuildHasher;
+  let random = std::collections::hash_map::RandomState::new().hash_one(Instant::now());
+  std::env::temp_dir().join(format!(
+    "{}-{}-{:016x}{}",
+    prefix,
+    std::process::id(),
+    random,
+    extension
+  ))
+}
+
+/// Create an empty file in the temp dir under a fresh name. It is created
+/// with `create_new`, so a file or symlink planted at the name is never
+/// written through.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub fn create_temp_file(prefix: &str, extension: &str) -> Result<PathBuf, String> {
+  let mut last_error = None;
+  for _ in 0..TEMP_ATTEMPTS {
+    let path = temp_path(prefix, extension);
+    match OpenOptions::new().write(true).create_new(true).open(&path) {
+      Ok(_) => return Ok(path),
+      Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => last_error = Some(e),
+      Err(e) => return Err(format!("Failed to create {}: {}", path.display(), e)),
+    }
+  }
+  Err(format!(
+    "Failed to create a temp file: {}",
+    last_error.map_or_else(String::new, |e| e.to_string())
+  ))
+}
+
+/// Create an empty directory in the temp dir under a fresh name. Like
+/// `create_temp_file`, a name that already exists is never reused.
+pub fn create_temp_dir(prefix: &str) -> Result<PathBuf, String> {
+  let mut last_error = None;
+  for _ in 0..TEMP_ATTEMPTS {
+    let path = temp_path(prefix, "");
+    match std::fs::create_dir(&path) {
+      Ok(()) => return Ok(path),
+      Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => last_error = Some(e),
+      Err(e) => return Err(format!("Failed to create {}: {}", path.display(), e)),
+    }
+  }
+  Err(format!(
+    "Failed to create a temp directory: {}",
+    last_error.map_or_else(String::new, |e| e.to_string())
+  ))
+}
+
+/// `<path>.<suffix>` next to `path`
+pub fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+  let mut name = path.file_name().unwrap_or_default().to_os_string();
+  name.push(".");
+  name.push(suffix);
+  path.with_file_name(name)
+}
+
+/// Replace `path` with `contents` by writing a temp file and renaming it over,
+/// so readers never observe a partially written file
+pub fn write_atomic(path: &Path, contents: &str) -> Result<(), String> {
+  let tmp_path = sibling_path(path, &format!("tmp.{}", std::process::id()));
+  std::fs::write(&tmp_path, contents)
+    .map_err(|e| format!("Failed to write {}: {}", tmp_path.display(), e))?;
+  std::fs::rename(&tmp_path, path).map_err(|e| {
+    let _ = std::fs::remove_file(&tmp_path);
+    format!("Failed to replace {}: {}", path.display(), e)
+  })
+}
+
+/// Parse JSON lines, skipping blank or malformed ones
+pub fn parse_jsonl<T: DeserializeOwned>(contents: &str) -> Vec<T> {
+  contents
+    .lines()
+    .filter(|line| !line.trim().is_empty())
+    .filter_map(|line| serde_json::from_str(line).ok())
+    .collect()
+}
+
+/// Append a record to a JSON-lines file, keeping only the newest `max`.
+/// Another emulaunch process may be writing at the same time, so the lock
+/// is held across read-modify-write and the file is replaced atomically.
+pub fn append_jsonl<T: Serialize + DeserializeOwned>(
+  path: &Path,
+  record: T,
+  max: usize,
+) -> Result<(), String> {
+  if let Some(dir) = path.parent() {
+    std::fs::create_dir_all(dir)
+      .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+  }
+
+  let _lock = FileLock::acquire(path)?;
+  let mut records: Vec<T> = std::fs::read_to_string(path)
+    .map(|c| parse_jsonl(&c))
+    .unwrap_or_default();
+  records.push(record);
+  let skip = records.len().saturating_sub(max);
+
+  let mut contents = String::new();
+  for record in &records[skip..] {
+    let line = serde_json::to_string(record).map_err(|e| e.to_string())?;
+    contents.push_str(&line);
+    contents.push('\n');
+  }
+  write_atomic(path, &contents)
+}
@@ -0,0 +1,125 @@
+//! Genymotion Desktop devices through its command-line tool, gmtool.
+//!
+//! `gmtool admin list` is parsed into devices, which `emulators` lists with
+//! the AVDs. Starting and stopping go through `gmtool admin start/stop`;
+//! everything else emulaunch does to an AVD is refused for them.
+
+use crate::{config, runner};
+use std::process::Stdio;
+use std::time::Duration;
+
+// Bound on `gmtool admin list`; it asks the hypervisor about every device
+const LIST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// States `gmtool admin list` prints in its first column
+const STATE_ON: &str = "On";
+const STATE_OFF: &str = "Off";
+
+// ADB Serial column of a device that isn't running
+const NO_SERIAL: &str = "0.0.0.0";
+
+/// One row of `gmtool admin list`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenymotionDevice {
+  pub uuid: String,
+  pub name: String,
+  /// `On`, `Off`, or whatever else gmtool reports while it changes
+  pub state: String,
+  /// adb serial (`192.168.56.101:5555`) while the device is running
+  pub serial: Option<String>,
+}
+
+impl GenymotionDevice {
+  pub fn is_on(&self) -> bool {
+    self.state == STATE_ON
+  }
+
+  pub fn is_off(&self) -> bool {
+    self.state == STATE_OFF
+  }
+}
+
+/// Parse `gmtool admin list`: a `|`-separated table of state, ADB serial,
+/// UUID and name under a header and a dashed rule.
+///
+/// ```text
+/// State  |   ADB Serial    |                 UUID                 |  Name
+/// -------+-----------------+--------------------------------------+---------------
+///   On   | 192.168.56.101  | 0c6e5ad3-2b9c-4c3e-9f7a-5d1e8b7f6a21 | Google Pixel 3
+///   Off  |     0.0.0.0     | 7f3b2c1d-8e4a-4b5c-a6d7-e8f9a0b1c2d3 | Galaxy S10
+/// ```
+pub fn parse_admin_list(stdout: &str) -> Vec<GenymotionDevice> {
+  stdout
+    .lines()
+    .filter_map(|line| {
+      // Names may contain `|`, so it is the last field, taken whole
+      let fields: Vec<&str> = line.splitn(4, '|').map(str::trim).collect();
+      let [state, serial, uuid, name] = fields[..] else {
+        return None;
+      };
+      if state == "State" || state.starts_with('-') || uuid.is_empty() {
+        return None;
+      }
+      Some(GenymotionDevice {
+        uuid: uuid.to_string(),
+        name: if name.is_empty() { uuid } else { name }.to_string(),
+        state: state.to_string(),
+        serial: (!serial.is_empty() && serial != NO_SERIAL).then(|| serial.to_string()),
+      })
+    })
+    .collect()
+}
+
+/// Every Genymotion device, or an error when gmtool is missing or fails
+pub fn list() -> Result<Vec<GenymotionDevice>, String> {
+  let gmtool = config::get_gmtool_cmd().map_err(|e| e.to_string())?;
+  let output = runner::output_with_timeout(
+    std::process::Command::new(&gmtool).args(["admin", "list"]),
+    LIST_TIMEOUT,
+  )
+  .map_err(|e| format!("Failed to run gmtool: {}", e))?;
+  if !output.status.success() {
+    return Err(format!(
+      "gmtool admin list failed: {}",
+      String::from_utf8_lossy(&output.stderr).trim()
+    ));
+  }
+  Ok(parse_admin_list(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Run `gmtool admin stop <uuid>`, which returns once the device is off
+pub fn stop(uuid: &str) -> Result<(), String> {
+  let gmtool = config::get_gmtool_cmd().map_err(|e| e.to_string())?;
+  let output = runner::output(
+    std::process::Command::new(&gmtool)
+      .args(["admin", "stop", uuid])
+      .stdin(Stdio::null()),
+  )
+  .map_err(|e| format!("Failed to run gmtool: {}", e))?;
+  if !output.status.success() {
+    return Err(format!(
+      "Failed to stop Genymotion device '{}': {}",
+      uuid,
+      String::from_utf8_lossy(&output.stderr).trim()
+    ));
+  }
+  Ok(())
+}
+
+/// Spawn `gmtool admin start <uuid>`; gmtool waits for the boot itself, so
+/// it is left running in the background
+pub fn start(uuid: &str) -> Result<(), String> {
+  let gmtool = config::get_gmtool_cmd().map_err(|e| e.to_string())?;
+  let mut command = std::process::Command::new(&gmtool);
+  command
+    .args(["admin", "start", uuid])
+    .stdin(Stdio::null())
+    .stdout(Stdio::null())
+    .stderr(Stdio::null());
+  // Out of the terminal's process group, as with the Android emulator
+  #[cfg(unix)]
+  std::os::unix::process::CommandExt::process_group(&mut command, 0);
+  runner::spawn(&mut command)
+    .map_err(|e| format!("Failed to start Genymotion device '{}': {}", uuid, e))?;
+  Ok(())
+}
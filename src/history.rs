@@ -0,0 +1,143 @@
+use crate::config;
+use crate::emulators::{self, EntryKey};
+use crate::filelock;
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const HISTORY_FILE: &str = "history.jsonl";
+
+// Older records are dropped when the file is rewritten
+const MAX_RECORDS: usize = 1000;
+
+// Only the most recent boots count towards the typical boot time
+const BOOT_SAMPLE_WINDOW: usize = 10;
+
+/// How a device was booted, when known
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BootMode {
+  Cold,
+  Quick,
+}
+
+impl BootMode {
+  /// How a launch boots `key`: an AVD cold or from its quick-boot snapshot.
+  /// Simulators and Genymotion devices have no such modes.
+  pub fn of_launch(key: &EntryKey, cold: bool) -> Option<BootMode> {
+    match key {
+      EntryKey::Android(_) if emulators::is_genymotion(key) => None,
+      EntryKey::Android(_) if cold => Some(BootMode::Cold),
+      EntryKey::Android(_) => Some(BootMode::Quick),
+      EntryKey::Ios(_) => None,
+    }
+  }
+}
+
+/// One launch of a device, with the measured boot time when it was waited on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryRecord {
+  pub key: EntryKey,
+  /// Unix timestamp (seconds) of the launch
+  pub timestamp: u64,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub boot_secs: Option<f64>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub boot_mode: Option<BootMode>,
+}
+
+/// Location of the history file in the data dir
+pub fn history_path() -> Option<PathBuf> {
+  Some(config::data_dir()?.join(HISTORY_FILE))
+}
+
+/// Load all history records, skipping lines that don't parse
+pub fn load() -> Vec<HistoryRecord> {
+  let Some(contents) = history_path().and_then(|p| std::fs::read_to_string(p).ok()) else {
+    return Vec::new();
+  };
+  parse_records(&contents)
+}
+
+/// Parse JSON-lines history, skipping blank or malformed lines
+pub fn parse_records(contents: &str) -> Vec<HistoryRecord> {
+  filelock::parse_jsonl(contents)
+}
+
+/// Append a launch record, with the boot duration if it was measured
+pub fn record_launch(
+  key: &EntryKey,
+  boot: Option<(Duration, Option<BootMode>)>,
+) -> Result<HistoryRecord, String> {
+  let path = history_path().ok_or_else(|| config::ERR_NO_HOME.to_string())?;
+  let record = HistoryRecord {
+    key: key.clone(),
+    timestamp: SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map_or(0, |d| d.as_secs()),
+    boot_secs: boot.map(|(d, _)| d.as_secs_f64()),
+    boot_mode: boot.and_then(|(_, mode)| mode),
+  };
+  filelock::append_jsonl(&path, record.clone(), MAX_RECORDS)?;
+  Ok(record)
+}
+
+/// Delete the history file, returning how many launches it held
+pub fn clear() -> Result<usize, String> {
+  let path = history_path().ok_or_else(|| config::ERR_NO_HOME.to_string())?;
+  if !path.exists() {
+    return Ok(0);
+  }
+  let _lock = filelock::FileLock::acquire(&path)?;
+  let count = load().len();
+  std::fs::remove_file(&path).map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+  Ok(count)
+}
+
+/// When each device in `records` was last launched (Unix time)
+pub fn last_launched(records: &[HistoryRecord]) -> HashMap<EntryKey, u64> {
+  let mut last: HashMap<EntryKey, u64> = HashMap::new();
+  for record in records {
+    let at = last.entry(record.key.clone()).or_default();
+    *at = (*at).max(record.timestamp);
+  }
+  last
+}
+
+/// Typical boot time for a device: the median of its most recent measured
+/// boots in the same mode, or `None` without samples. Untagged records
+/// count as quick boots, since cold boots were always tagged.
+pub fn typical_boot(
+  records: &[HistoryRecord],
+  key: &EntryKey,
+  mode: Option<BootMode>,
+) -> Option<Duration> {
+  let mut samples: Vec<f64> = records
+    .iter()
+    .rev()
+    .filter(|r| {
+      &r.key == key
+        && (r.boot_mode == mode || mode == Some(BootMode::Quick) && r.boot_mode.is_none())
+    })
+    .filter_map(|r| r.boot_secs)
+    .filter(|secs| secs.is_finite() && *secs >= 0.0)
+    .take(BOOT_SAMPLE_WINDOW)
+    .collect();
+  median(&mut samples).map(Duration::from_secs_f64)
+}
+
+/// Median of some samples, or `None` when there are none
+pub fn median(samples: &mut [f64]) -> Option<f64> {
+  if samples.is_empty() {
+    return None;
+  }
+  samples.sort_by(f64::total_cmp);
+  let mid = samples.len() / 2;
+  Some(if samples.len().is_multiple_of(2) {
+    (samples[mid - 1] + samples[mid]) / 2.0
+  } else {
+    samples[mid]
+  })
+}
@@ -0,0 +1,90 @@
+//! Key/value reader for the ini files the Android tools write: an AVD's
+//! `config.ini` and `hardware-qemu.ini`, and the `<id>.ini` next to its
+//! `.avd` directory.
+//!
+//! These are flat `key=value` lines, but files edited by hand or created on
+//! Windows carry a BOM, CRLF endings, comments and the odd `[section]`.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+// Byte order mark some Windows editors put at the start of the file
+const BOM: char = '\u{feff}';
+
+/// Parsed ini contents, keyed by the trimmed key
+pub type AvdIni = HashMap<String, String>;
+
+/// Parse ini contents. Keys and values are trimmed; a value runs to the end
+/// of the line, so it may itself contain `=`. Blank lines, `#`/`;`
+/// comments, section headers and lines without `=` are skipped. When a key
+/// repeats, the first value wins, as the emulator reads it.
+pub fn parse_avd_ini(contents: &str) -> AvdIni {
+  let mut values = AvdIni::new();
+  for line in contents.trim_start_matches(BOM).lines() {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with(['#', ';', '[']) {
+      continue;
+    }
+    let Some((key, value)) = line.split_once('=') else {
+      continue;
+    };
+    let key = key.trim();
+    if !key.is_empty() {
+      values
+        .entry(key.to_string())
+        .or_insert_with(|| value.trim().to_string());
+    }
+  }
+  values
+}
+
+/// Read and parse an ini file; invalid UTF-8 is replaced rather than
+/// rejected, since only a few ASCII keys are looked up
+pub fn read(path: &Path) -> Option<AvdIni> {
+  let bytes = std::fs::read(path).ok()?;
+  Some(parse_avd_ini(&String::from_utf8_lossy(&bytes)))
+}
+
+/// Set `updates` in ini contents, keeping every other line as it was. The
+/// first line with a key is rewritten in place (the one `parse_avd_ini`
+/// reads), keeping the spacing around `=`; keys not in the file are
+/// appended. Line endings follow the file's own.
+pub fn set_values(contents: &str, updates: &[(&str, &str)]) -> String {
+  let newline = if contents.contains("\r\n") {
+    "\r\n"
+  } else {
+    "\n"
+  };
+  let mut pending: Vec<(&str, &str)> = updates.to_vec();
+  let mut out = String::with_capacity(contents.len());
+  for line in contents.split_inclusive('\n') {
+    let body = line.trim_end_matches(['\r', '\n']);
+    let ending = &line[body.len()..];
+    let trimmed = body.trim_start_matches(BOM).trim();
+    let key = (!trimmed.starts_with(['#', ';', '[']))
+      .then(|| trimmed.split_once('='))
+      .flatten()
+      .map(|(key, _)| key.trim());
+    let found = key.and_then(|key| pending.iter().position(|(k, _)| *k == key));
+    match found {
+      Some(i) => {
+        let (_, value) = pending.remove(i);
+        let eq = body.find('=').unwrap_or(body.len());
+        let after = &body[eq + 1..];
+        let spacing = &after[..after.len() - after.trim_start().len()];
+        out.push_str(&body[..=eq]);
+        out.push_str(spacing);
+        out.push_str(value);
+        out.push_str(ending);
+      }
+      None => out.push_str(line),
+    }
+  }
+  if !pending.is_empty() && !out.is_empty() && !out.ends_with('\n') {
+    out.push_str(newline);
+  }
+  for (key, value) in pending {
+    out.push_str(&format!("{}={}{}", key, value, newline));
+  }
+  out
+}
@@ -0,0 +1,716 @@
+use crate::config::KeySpec;
+use crate::emulators::Platform;
+use crate::line_edit::Edit;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// How typing reaches the filter, set by `filter_focus` in the config
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterFocusMode {
+  /// Letters type into the filter unless they are j/k/q with an empty filter
+  Implicit,
+  /// `/` or Ctrl+F focuses the filter; letters are bindings while the list is focused
+  Explicit,
+  /// As `Explicit`, but a character that isn't bound also focuses the
+  /// filter and starts the query
+  Auto,
+}
+
+impl FilterFocusMode {
+  pub fn from_config(value: Option<&str>) -> Self {
+    match value.map(|v| v.trim().to_lowercase()).as_deref() {
+      Some("implicit") => Self::Implicit,
+      Some("auto") => Self::Auto,
+      _ => Self::Explicit,
+    }
+  }
+
+  /// Whether the filter takes focus to be typed into (`Explicit`, `Auto`)
+  pub fn is_modal(self) -> bool {
+    self != Self::Implicit
+  }
+}
+
+/// Which part of the TUI receives printable keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Focus {
+  List,
+  Filter,
+  /// The snapshot list in the details pane
+  Snapshots,
+  /// A yes/no prompt is waiting for an answer
+  Confirm,
+  /// The activity log in the details pane
+  Log,
+  /// The theme picker popup
+  Themes,
+}
+
+/// What a key press does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+  Quit,
+  MoveUp,
+  MoveDown,
+  /// Move by the height of the list, or half of it
+  PageUp,
+  PageDown,
+  HalfPageUp,
+  HalfPageDown,
+  /// Select the first or last device
+  SelectFirst,
+  SelectLast,
+  Open,
+  OpenPaired,
+  OpenNoGui,
+  /// Open the selected AVD without loading its quick-boot snapshot
+  OpenCold,
+  Refresh,
+  FocusFilter,
+  FocusList,
+  FilterPush(char),
+  /// Focus the filter and type this character into it
+  FilterStart(char),
+  FilterPop,
+  /// Move the filter's cursor or delete around it
+  FilterEdit(Edit),
+  ToggleSnapshots,
+  DeleteSnapshot,
+  /// Shut the selected device down, after a confirmation
+  Stop,
+  ClearFilter,
+  /// Switch to the next sort order
+  CycleSort,
+  /// Hide or show a platform's section
+  ToggleSection(Platform),
+  /// Fold the selected row's section down to its header, or unfold it
+  Collapse,
+  Expand,
+  ToggleCollapse,
+  /// Open the theme picker, or close it and go back to the last theme
+  ToggleThemes,
+  /// Use the theme picked and save it to the config file
+  SaveTheme,
+  /// Copy the selected device's id (AVD id or UDID) to the clipboard
+  YankId,
+  /// Copy the selected device's name to the clipboard
+  YankName,
+  ToggleLog,
+  /// Open the popup listing every binding
+  ToggleHelp,
+  Confirm,
+  /// Bring the running device forward instead of launching it again
+  FocusWindow,
+  Cancel,
+  None,
+}
+
+impl Action {
+  /// The action as the trace records it; typed characters are left out,
+  /// since they could spell device or app names
+  pub fn trace_name(&self) -> String {
+    match self {
+      Action::FilterPush(_) => "FilterPush".to_string(),
+      Action::FilterStart(_) => "FilterStart".to_string(),
+      other => format!("{:?}", other),
+    }
+  }
+}
+
+/// A key and the modifiers held with it, as written in `[keys]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyBinding {
+  code: KeyCode,
+  modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+  /// Parse a descriptor such as `"j"`, `"J"`, `"esc"`, `"ctrl-c"` or
+  /// `"alt+enter"`. Letters are case-sensitive unless a modifier is given.
+  pub fn parse(desc: &str) -> Result<Self, String> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = desc.trim();
+    loop {
+      let lower = rest.to_ascii_lowercase();
+      let modifier = [
+        ("ctrl", KeyModifiers::CONTROL),
+        ("alt", KeyModifiers::ALT),
+        ("shift", KeyModifiers::SHIFT),
+      ]
+      .into_iter()
+      .find(|(name, _)| {
+        lower.starts_with(name) && matches!(lower[name.len()..].chars().next(), Some('-' | '+'))
+      });
+      // A lone "-" or "+" after the modifier is the key itself
+      match modifier {
+        Some((name, m)) if rest.len() > name.len() + 1 => {
+          modifiers |= m;
+          rest = &rest[name.len() + 1..];
+        }
+        _ => break,
+      }
+    }
+
+    let mut chars = rest.chars();
+    let code = match (chars.next(), chars.next()) {
+      (Some(c), None) if modifiers.contains(KeyModifiers::SHIFT) => {
+        modifiers.remove(KeyModifiers::SHIFT);
+        KeyCode::Char(c.to_ascii_uppercase())
+      }
+      (Some(c), None) if modifiers.is_empty() => KeyCode::Char(c),
+      // crossterm reports Ctrl and Alt letters in lowercase
+      (Some(c), None) => KeyCode::Char(c.to_ascii_lowercase()),
+      _ => match rest.to_ascii_lowercase().as_str() {
+        "esc" | "escape" => KeyCode::Esc,
+        "enter" | "return" => KeyCode::Enter,
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "backspace" => KeyCode::Backspace,
+        "delete" | "del" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "space" => KeyCode::Char(' '),
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" | "pgup" => KeyCode::PageUp,
+        "pagedown" | "pgdn" => KeyCode::PageDown,
+        name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+          Some(n @ 1..=24) => KeyCode::F(n),
+          _ => return Err(format!("'{}' is not a key", desc)),
+        },
+      },
+    };
+    Ok(KeyBinding { code, modifiers })
+  }
+
+  fn matches(&self, key: KeyEvent) -> bool {
+    // Shift is already in the case of a letter
+    let modifiers = match key.code {
+      KeyCode::Char(_) => key.modifiers - KeyModifiers::SHIFT,
+      _ => key.modifiers,
+    };
+    self.code == key.code && self.modifiers == modifiers
+  }
+
+  /// A character typed without Ctrl or Alt, which could also go to the filter
+  fn is_plain_char(&self) -> bool {
+    matches!(self.code, KeyCode::Char(_)) && self.modifiers.is_empty()
+  }
+}
+
+impl fmt::Display for KeyBinding {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for (m, name) in [
+      (KeyModifiers::CONTROL, "Ctrl+"),
+      (KeyModifiers::ALT, "Alt+"),
+      (KeyModifiers::SHIFT, "Shift+"),
+    ] {
+      if self.modifiers.contains(m) {
+        f.write_str(name)?;
+      }
+    }
+    match self.code {
+      KeyCode::Char(' ') => f.write_str("Space"),
+      KeyCode::Char(c) if self.modifiers.is_empty() => write!(f, "{}", c),
+      KeyCode::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+      KeyCode::Up => f.write_str("\u{2191}"),
+      KeyCode::Down => f.write_str("\u{2193}"),
+      KeyCode::PageUp => f.write_str("PgUp"),
+      KeyCode::PageDown => f.write_str("PgDn"),
+      code => write!(f, "{}", code),
+    }
+  }
+}
+
+/// Actions `[keys]` can rebind: config name, action, default keys and
+/// what the help popup says about it
+const BINDABLE: &[(&str, Action, &[&str], &str)] = &[
+  (
+    "quit",
+    Action::Quit,
+    &["q", "esc"],
+    "Quit, or close the snapshots or log",
+  ),
+  ("down", Action::MoveDown, &["j", "down"], "Move down"),
+  ("up", Action::MoveUp, &["k", "up"], "Move up"),
+  ("page_down", Action::PageDown, &["pgdn"], "Move down a page"),
+  ("page_up", Action::PageUp, &["pgup"], "Move up a page"),
+  (
+    "half_page_down",
+    Action::HalfPageDown,
+    &["ctrl-d"],
+    "Move down half a page",
+  ),
+  (
+    "half_page_up",
+    Action::HalfPageUp,
+    &["ctrl-u"],
+    "Move up half a page (when the filter is empty)",
+  ),
+  (
+    "first",
+    Action::SelectFirst,
+    &["home", "g"],
+    "Select the first device",
+  ),
+  (
+    "last",
+    Action::SelectLast,
+    &["end", "G"],
+    "Select the last device",
+  ),
+  ("open", Action::Open, &["enter"], "Open the selected device"),
+  (
+    "open_paired",
+    Action::OpenPaired,
+    &["ctrl-p"],
+    "Open a simulator with its paired watch or phone",
+  ),
+  (
+    "open_no_gui",
+    Action::OpenNoGui,
+    &["ctrl-b"],
+    "Boot a simulator without Simulator.app",
+  ),
+  (
+    "open_cold",
+    Action::OpenCold,
+    &["C", "ctrl-enter"],
+    "Cold boot the selected AVD, skipping its snapshot",
+  ),
+  (
+    "refresh",
+    Action::Refresh,
+    &["r", "ctrl-r"],
+    "Re-list devices",
+  ),
+  (
+    "stop",
+    Action::Stop,
+    &["x"],
+    "Shut down the selected device",
+  ),
+  (
+    "filter",
+    Action::FocusFilter,
+    &["/", "ctrl-f"],
+    "Focus the filter",
+  ),
+  (
+    "clear_filter",
+    Action::ClearFilter,
+    &["ctrl-u"],
+    "Clear the filter",
+  ),
+  ("sort", Action::CycleSort, &["o"], "Change the sort order"),
+  (
+    "yank",
+    Action::YankId,
+    &["y"],
+    "Copy the device's AVD id or UDID",
+  ),
+  (
+    "yank_name",
+    Action::YankName,
+    &["Y"],
+    "Copy the device's name",
+  ),
+  (
+    "theme",
+    Action::ToggleThemes,
+    &["t"],
+    "Pick a theme, previewed as you move",
+  ),
+  (
+    "toggle_android",
+    Action::ToggleSection(Platform::Android),
+    &["a"],
+    "Hide or show the Android section",
+  ),
+  (
+    "toggle_ios",
+    Action::ToggleSection(Platform::Ios),
+    &["s"],
+    "Hide or show the iOS section",
+  ),
+  (
+    "collapse",
+    Action::Collapse,
+    &["h"],
+    "Collapse the section to its header",
+  ),
+  (
+    "expand",
+    Action::Expand,
+    &["l"],
+    "Expand a collapsed section",
+  ),
+  (
+    "toggle_collapse",
+    Action::ToggleCollapse,
+    &["space"],
+    "Collapse or expand the section (Enter on a header too)",
+  ),
+  (
+    "snapshots",
+    Action::ToggleSnapshots,
+    &["tab"],
+    "Show or close the AVD's snapshots",
+  ),
+  (
+    "log",
+    Action::ToggleLog,
+    &["L"],
+    "Show or close this session's activity log",
+  ),
+  (
+    "delete_snapshot",
+    Action::DeleteSnapshot,
+    &["x"],
+    "Delete the selected snapshot",
+  ),
+  ("help", Action::ToggleHelp, &["?"], "Show this list"),
+];
+
+/// Names `[keys]` accepts
+pub fn action_names() -> impl Iterator<Item = &'static str> {
+  BINDABLE.iter().map(|(name, ..)| *name)
+}
+
+// Actions reachable from every pane with a Ctrl, Alt or non-character key
+const GLOBAL_ACTIONS: &[Action] = &[
+  Action::MoveDown,
+  Action::MoveUp,
+  Action::PageDown,
+  Action::PageUp,
+  Action::SelectFirst,
+  Action::SelectLast,
+  Action::OpenPaired,
+  Action::OpenNoGui,
+  Action::Refresh,
+  Action::ClearFilter,
+  Action::HalfPageDown,
+  Action::HalfPageUp,
+];
+
+// Actions in the device list, in the order a shared key resolves
+const LIST_ACTIONS: &[Action] = &[
+  Action::Quit,
+  Action::MoveDown,
+  Action::MoveUp,
+  Action::SelectFirst,
+  Action::SelectLast,
+  Action::Open,
+  Action::OpenCold,
+  Action::ToggleSnapshots,
+  Action::ToggleLog,
+  Action::Refresh,
+  Action::Stop,
+  Action::FocusFilter,
+  Action::ClearFilter,
+  Action::CycleSort,
+  Action::YankId,
+  Action::YankName,
+  Action::ToggleThemes,
+  Action::ToggleSection(Platform::Android),
+  Action::ToggleSection(Platform::Ios),
+  Action::Collapse,
+  Action::Expand,
+  Action::ToggleCollapse,
+  Action::ToggleHelp,
+];
+
+/// The keys bound to each action: the defaults, with any action listed in
+/// `[keys]` taking the keys given there instead
+#[derive(Debug, Clone)]
+pub struct KeyMap {
+  bindings: Vec<(Action, Vec<KeyBinding>)>,
+}
+
+/// A problem in `[keys]` that `KeyMap::build` worked around
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyWarning {
+  /// The config key at fault, such as `keys.quit`
+  pub key: String,
+  pub message: String,
+}
+
+impl KeyWarning {
+  fn new(action: &str, message: impl Into<String>) -> Self {
+    KeyWarning {
+      key: format!("keys.{}", action),
+      message: message.into(),
+    }
+  }
+}
+
+impl fmt::Display for KeyWarning {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}: {}", self.key, self.message)
+  }
+}
+
+impl Default for KeyMap {
+  fn default() -> Self {
+    KeyMap::build(None).0
+  }
+}
+
+impl KeyMap {
+  /// The key map for `[keys]`, and warnings for unknown actions and keys
+  /// that don't parse (those are skipped); `lint` reports the same warnings
+  pub fn build(overrides: Option<&BTreeMap<String, KeySpec>>) -> (Self, Vec<KeyWarning>) {
+    let mut warnings: Vec<KeyWarning> = overrides
+      .into_iter()
+      .flatten()
+      .filter(|(name, _)| !action_names().any(|n| n == name.as_str()))
+      .map(|(name, _)| {
+        KeyWarning::new(
+          name,
+          format!(
+            "unknown action, ignored (actions: {})",
+            action_names().collect::<Vec<_>>().join(", ")
+          ),
+        )
+      })
+      .collect();
+    let parse = |descs: &[&str]| -> Vec<KeyBinding> {
+      descs
+        .iter()
+        .filter_map(|d| KeyBinding::parse(d).ok())
+        .collect()
+    };
+    let bindings = BINDABLE
+      .iter()
+      .map(|&(name, action, defaults, _)| {
+        let Some(spec) = overrides.and_then(|o| o.get(name)) else {
+          return (action, parse(defaults));
+        };
+        let mut keys = Vec::new();
+        for desc in spec.descriptors() {
+          match KeyBinding::parse(desc) {
+            Ok(key) => keys.push(key),
+            Err(e) => warnings.push(KeyWarning::new(name, format!("{}, ignored", e))),
+          }
+        }
+        // Unbinding quit by mistake would leave no way out
+        if keys.is_empty() && action == Action::Quit {
+          warnings.push(KeyWarning::new(
+            name,
+            "needs at least one key; the defaults are kept",
+          ));
+          keys = parse(defaults);
+        }
+        (action, keys)
+      })
+      .collect();
+    (KeyMap { bindings }, warnings)
+  }
+
+  /// Every key bound to `action`
+  pub fn keys(&self, action: Action) -> &[KeyBinding] {
+    self
+      .bindings
+      .iter()
+      .find(|(a, _)| *a == action)
+      .map_or(&[], |(_, keys)| keys.as_slice())
+  }
+
+  /// Up to `max` of the keys bound to `action` joined with `/`, for the help
+  /// bar; `None` when it has none
+  pub fn label(&self, action: Action, max: usize) -> Option<String> {
+    let keys = self.keys(action);
+    (!keys.is_empty()).then(|| {
+      keys
+        .iter()
+        .take(max)
+        .map(KeyBinding::to_string)
+        .collect::<Vec<_>>()
+        .join("/")
+    })
+  }
+
+  /// Every rebindable action's keys and description, in `BINDABLE` order,
+  /// for the help popup; unbound actions are left out
+  pub fn describe(&self) -> Vec<(String, &'static str)> {
+    BINDABLE
+      .iter()
+      .filter(|&&(_, action, ..)| !self.keys(action).is_empty())
+      .map(|&(_, action, _, description)| {
+        // Commas, since "/" is a key too
+        let keys: Vec<String> = self
+          .keys(action)
+          .iter()
+          .map(KeyBinding::to_string)
+          .collect();
+        (keys.join(", "), description)
+      })
+      .collect()
+  }
+
+  /// Whether `key` is one of the quit keys, whatever has focus
+  pub fn is_quit(&self, key: KeyEvent) -> bool {
+    self.lookup(key, &[Action::Quit], true).is_some()
+  }
+
+  /// The first of `actions` bound to `key`; plain characters only count
+  /// when `plain_chars` is set
+  fn lookup(&self, key: KeyEvent, actions: &[Action], plain_chars: bool) -> Option<Action> {
+    actions.iter().copied().find(|&action| {
+      self
+        .keys(action)
+        .iter()
+        .any(|b| b.matches(key) && (plain_chars || !b.is_plain_char()))
+    })
+  }
+}
+
+/// Readline's cursor and deletion keys, for wherever typing goes to the
+/// filter
+fn filter_edit(key: KeyEvent) -> Option<Edit> {
+  let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+  match key.code {
+    KeyCode::Left => Some(Edit::Left),
+    KeyCode::Right => Some(Edit::Right),
+    KeyCode::Delete => Some(Edit::DeleteForward),
+    KeyCode::Char('a') if ctrl => Some(Edit::Start),
+    KeyCode::Char('e') if ctrl => Some(Edit::End),
+    KeyCode::Char('w') if ctrl => Some(Edit::DeleteWord),
+    _ => None,
+  }
+}
+
+/// Map a key press while the first listing is running: nothing can be
+/// selected yet, so typing goes to the filter and Esc quits
+pub fn dispatch_loading(key: KeyEvent) -> Action {
+  let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+  match key.code {
+    KeyCode::Esc => Action::Quit,
+    KeyCode::Backspace => Action::FilterPop,
+    KeyCode::Char(c) if !ctrl => Action::FilterPush(c),
+    _ => Action::None,
+  }
+}
+
+/// Map a key press to an action for the current focus state
+pub fn dispatch(
+  key: KeyEvent,
+  keymap: &KeyMap,
+  mode: FilterFocusMode,
+  focus: Focus,
+  filter_empty: bool,
+) -> Action {
+  let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+  // A prompt takes every key: y confirms, f picks the re-launch prompt's
+  // focus choice, anything else cancels
+  if focus == Focus::Confirm {
+    return match key.code {
+      KeyCode::Char('y') | KeyCode::Char('Y') => Action::Confirm,
+      KeyCode::Char('f') | KeyCode::Char('F') => Action::FocusWindow,
+      _ => Action::Cancel,
+    };
+  }
+
+  // Editing keys in the focused filter aren't rebindable; Backspace on an
+  // empty query leaves the filter like Esc
+  if mode.is_modal() && focus == Focus::Filter {
+    match key.code {
+      KeyCode::Esc | KeyCode::Enter => return Action::FocusList,
+      KeyCode::Backspace if filter_empty => return Action::FocusList,
+      KeyCode::Backspace => return Action::FilterPop,
+      _ => {}
+    }
+    if let Some(edit) = filter_edit(key) {
+      return Action::FilterEdit(edit);
+    }
+  }
+
+  // With nothing to clear, Ctrl+U is left to half_page_up as in vim
+  let global: Vec<Action> = GLOBAL_ACTIONS
+    .iter()
+    .copied()
+    .filter(|&a| !(a == Action::ClearFilter && filter_empty))
+    .collect();
+  if let Some(action) = keymap.lookup(key, &global, false) {
+    return action;
+  }
+
+  match (mode, focus) {
+    (_, Focus::Snapshots) => {
+      let actions = [
+        Action::ToggleSnapshots,
+        Action::Quit,
+        Action::MoveDown,
+        Action::MoveUp,
+        Action::Open,
+        Action::DeleteSnapshot,
+      ];
+      match keymap.lookup(key, &actions, true) {
+        Some(Action::Quit) => Action::ToggleSnapshots,
+        action => action.unwrap_or(Action::None),
+      }
+    }
+    (_, Focus::Log) => {
+      let actions = [
+        Action::ToggleLog,
+        Action::Quit,
+        Action::MoveDown,
+        Action::MoveUp,
+      ];
+      match keymap.lookup(key, &actions, true) {
+        Some(Action::Quit) => Action::ToggleLog,
+        action => action.unwrap_or(Action::None),
+      }
+    }
+    // Enter uses the theme for this session, w saves it as well
+    (_, Focus::Themes) => {
+      if key.code == KeyCode::Char('w') {
+        return Action::SaveTheme;
+      }
+      let actions = [
+        Action::ToggleThemes,
+        Action::Quit,
+        Action::MoveDown,
+        Action::MoveUp,
+        Action::Open,
+      ];
+      match keymap.lookup(key, &actions, true) {
+        Some(Action::Quit) => Action::ToggleThemes,
+        action => action.unwrap_or(Action::None),
+      }
+    }
+    (_, Focus::Confirm) => Action::None,
+    // Letters are bindings only while there is nothing to add them to
+    (FilterFocusMode::Implicit, _) => {
+      let actions: Vec<Action> = LIST_ACTIONS
+        .iter()
+        .copied()
+        .filter(|&a| a != Action::FocusFilter)
+        .collect();
+      match keymap.lookup(key, &actions, filter_empty) {
+        Some(action) => action,
+        None => match key.code {
+          KeyCode::Backspace => Action::FilterPop,
+          KeyCode::Char(c) if !ctrl => Action::FilterPush(c),
+          _ => filter_edit(key).map_or(Action::None, Action::FilterEdit),
+        },
+      }
+    }
+    (_, Focus::Filter) => match key.code {
+      KeyCode::Char(c) if !ctrl => Action::FilterPush(c),
+      _ => Action::None,
+    },
+    (_, Focus::List) => match (keymap.lookup(key, LIST_ACTIONS, true), key.code) {
+      (Some(action), _) => action,
+      (None, KeyCode::Backspace) => Action::FilterPop,
+      (None, KeyCode::Char(c)) if mode == FilterFocusMode::Auto && !ctrl => Action::FilterStart(c),
+      (None, _) => Action::None,
+    },
+  }
+}
@@ -0,0 +1,106 @@
+//! A single line of text being edited with a cursor, for the TUI filter.
+//!
+//! The cursor moves and deletes by grapheme cluster, so an accented or
+//! emoji simulator name is never split mid-character. Words are separated
+//! by whitespace, as with readline's Ctrl-W.
+
+use crate::text;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// An edit other than typing a character or Backspace
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edit {
+  Left,
+  Right,
+  /// To the start of the line (Ctrl-A)
+  Start,
+  /// To the end of the line (Ctrl-E)
+  End,
+  /// Delete the word before the cursor (Ctrl-W)
+  DeleteWord,
+  /// Delete the character under the cursor (Delete)
+  DeleteForward,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LineEdit {
+  text: String,
+  /// Byte offset into `text`, always on a grapheme boundary
+  cursor: usize,
+}
+
+impl LineEdit {
+  /// `text` with the cursor at its end
+  pub fn new(text: String) -> LineEdit {
+    let cursor = text.len();
+    LineEdit { text, cursor }
+  }
+
+  pub fn as_str(&self) -> &str {
+    &self.text
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.text.is_empty()
+  }
+
+  pub fn clear(&mut self) {
+    self.text.clear();
+    self.cursor = 0;
+  }
+
+  /// Columns before the cursor, where the terminal cursor goes
+  pub fn cursor_width(&self) -> usize {
+    text::display_width(&self.text[..self.cursor])
+  }
+
+  pub fn insert(&mut self, c: char) {
+    self.text.insert(self.cursor, c);
+    self.cursor += c.len_utf8();
+  }
+
+  /// Delete the character before the cursor
+  pub fn backspace(&mut self) {
+    let start = self.previous_boundary();
+    self.text.replace_range(start..self.cursor, "");
+    self.cursor = start;
+  }
+
+  pub fn apply(&mut self, edit: Edit) {
+    match edit {
+      Edit::Left => self.cursor = self.previous_boundary(),
+      Edit::Right => self.cursor = self.next_boundary(),
+      Edit::Start => self.cursor = 0,
+      Edit::End => self.cursor = self.text.len(),
+      Edit::DeleteWord => {
+        let before = &self.text[..self.cursor];
+        // Spaces right before the cursor go with the word before them
+        let word_end = before.trim_end().len();
+        let start = before[..word_end]
+          .trim_end_matches(|c: char| !c.is_whitespace())
+          .len();
+        self.text.replace_range(start..self.cursor, "");
+        self.cursor = start;
+      }
+      Edit::DeleteForward => {
+        let end = self.next_boundary();
+        self.text.replace_range(self.cursor..end, "");
+      }
+    }
+  }
+
+  fn previous_boundary(&self) -> usize {
+    self.text[..self.cursor]
+      .grapheme_indices(true)
+      .next_back()
+      .map_or(0, |(i, _)| i)
+  }
+
+  fn next_boundary(&self) -> usize {
+    self.text[self.cursor..]
+      .graphemes(true)
+      .next()
+      .map_or(self.cursor, |g| self.cursor + g.len())
+  }
+}
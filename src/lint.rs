@@ -0,0 +1,414 @@
+//! `config lint`: every problem in the config file, reported at once.
+//!
+//! Each check is a function over the parsed `Config` (the device checks
+//! also get the devices listed right now) returning findings. Errors are
+//! settings that don't do what they say; warnings are ones that may be
+//! stale or are ignored.
+
+use crate::config::{self, Config, DeviceConfig};
+use crate::emulators::{self, Platform};
+use crate::keys;
+use crate::theme;
+use crate::view::{self, SortMode};
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+  Error,
+  Warning,
+}
+
+/// One problem, with the config key it is about
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+  pub severity: Severity,
+  pub key: String,
+  pub message: String,
+}
+
+impl Finding {
+  fn error(key: impl Into<String>, message: impl Into<String>) -> Self {
+    Finding {
+      severity: Severity::Error,
+      key: key.into(),
+      message: message.into(),
+    }
+  }
+
+  fn warning(key: impl Into<String>, message: impl Into<String>) -> Self {
+    Finding {
+      severity: Severity::Warning,
+      key: key.into(),
+      message: message.into(),
+    }
+  }
+}
+
+/// Ids of the devices listed when the lint ran; `None` when no platform
+/// could be listed, so device references can't be checked
+pub struct DeviceSnapshot {
+  ids: Option<HashSet<String>>,
+}
+
+impl DeviceSnapshot {
+  /// AVD ids from the directory scan and simulator UDIDs; no emulator or
+  /// adb process is started
+  pub fn take() -> Self {
+    let mut ids = HashSet::new();
+    let mut listed = false;
+    for &platform in Platform::supported() {
+      if let Ok(entries) = emulators::list_platform_entries(platform, true) {
+        listed = true;
+        ids.extend(entries.iter().filter_map(|e| match e.key()? {
+          emulators::EntryKey::Android(id) | emulators::EntryKey::Ios(id) => Some(id),
+        }));
+      }
+    }
+    DeviceSnapshot {
+      ids: listed.then_some(ids),
+    }
+  }
+}
+
+/// The result of linting the active config file
+#[derive(Debug, Serialize)]
+pub struct Report {
+  pub path: Option<PathBuf>,
+  pub errors: usize,
+  pub warnings: usize,
+  pub findings: Vec<Finding>,
+}
+
+impl Report {
+  fn new(path: Option<PathBuf>, findings: Vec<Finding>) -> Self {
+    let errors = findings
+      .iter()
+      .filter(|f| f.severity == Severity::Error)
+      .count();
+    Report {
+      path,
+      errors,
+      warnings: findings.len() - errors,
+      findings,
+    }
+  }
+
+  /// One line per finding, then a count
+  pub fn to_text(&self) -> String {
+    let mut lines = vec![match &self.path {
+      Some(path) => format!("Config file: {}", path.display()),
+      None => "Config file: none (using defaults)".to_string(),
+    }];
+    for f in &self.findings {
+      let label = match f.severity {
+        Severity::Error => "error:  ",
+        Severity::Warning => "warning:",
+      };
+      lines.push(format!("{} {}: {}", label, f.key, f.message));
+    }
+    lines.push(if self.findings.is_empty() {
+      "No problems found".to_string()
+    } else {
+      format!(
+        "{} {}, {} {}",
+        self.errors,
+        if self.errors == 1 { "error" } else { "errors" },
+        self.warnings,
+        if self.warnings == 1 {
+          "warning"
+        } else {
+          "warnings"
+        }
+      )
+    });
+    lines.join("\n")
+  }
+}
+
+/// Lint the config file that would be loaded. A file that doesn't parse is
+/// one error, since `load_config` would ignore it entirely.
+pub fn run() -> Report {
+  let Some(path) = config::active_config_path() else {
+    return Report::new(None, Vec::new());
+  };
+  let parsed = std::fs::read_to_string(&path)
+    .map_err(|e| e.to_string())
+    .and_then(|contents| toml::from_str::<Config>(&contents).map_err(|e| e.to_string()));
+  let findings = match parsed {
+    Ok(config) => lint(&config, &DeviceSnapshot::take()),
+    Err(e) => vec![Finding::error(
+      "(file)",
+      format!(
+        "can't be parsed, so every setting is ignored: {}",
+        e.trim_end()
+      ),
+    )],
+  };
+  Report::new(Some(path), findings)
+}
+
+/// Every check over one config
+pub fn lint(config: &Config, devices: &DeviceSnapshot) -> Vec<Finding> {
+  let mut findings = Vec::new();
+  findings.extend(check_unknown_keys("", &config.unknown));
+  findings.extend(check_tools(config));
+  findings.extend(check_theme(config));
+  findings.extend(check_theme_overrides(config));
+  findings.extend(check_filter_focus(config));
+  findings.extend(check_sort(config));
+  findings.extend(check_collapsed_sections(config));
+  findings.extend(check_keys(config));
+  findings.extend(check_simctl(config));
+  findings.extend(check_notify_cmd(config));
+  findings.extend(check_launch_args(
+    "android_launch_args",
+    config.android_launch_args.as_deref(),
+  ));
+  findings.extend(check_devices(config, devices));
+  findings
+}
+
+fn check_unknown_keys(prefix: &str, unknown: &BTreeMap<String, toml::Value>) -> Vec<Finding> {
+  unknown
+    .keys()
+    .map(|key| Finding::warning(format!("{}{}", prefix, key), "unknown setting, ignored"))
+    .collect()
+}
+
+/// Why a configured program can't be run, if it can't: a path must be an
+/// executable file, a bare name must be on PATH
+fn program_problem(cmd: &str) -> Option<String> {
+  if cmd.trim().is_empty() {
+    return Some("is empty".to_string());
+  }
+  let path = Path::new(cmd);
+  if path.components().count() == 1 && !path.is_absolute() {
+    return (!config::command_exists(cmd)).then(|| format!("'{}' is not on PATH", cmd));
+  }
+  let Ok(meta) = std::fs::metadata(path) else {
+    return Some(format!("{} does not exist", cmd));
+  };
+  if !meta.is_file() {
+    return Some(format!("{} is not a file", cmd));
+  }
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::PermissionsExt;
+    if meta.permissions().mode() & 0o111 == 0 {
+      return Some(format!("{} is not executable", cmd));
+    }
+  }
+  None
+}
+
+fn check_tools(config: &Config) -> Vec<Finding> {
+  [
+    ("android_emulator_cmd", &config.android_emulator_cmd),
+    ("adb_cmd", &config.adb_cmd),
+    ("xcrun_cmd", &config.xcrun_cmd),
+    ("avdmanager_cmd", &config.avdmanager_cmd),
+    ("gmtool_cmd", &config.gmtool_cmd),
+  ]
+  .into_iter()
+  .filter_map(|(key, cmd)| {
+    let problem = program_problem(cmd.as_deref()?)?;
+    Some(Finding::error(
+      key,
+      format!("{}; the usual locations are searched instead", problem),
+    ))
+  })
+  .collect()
+}
+
+fn check_theme(config: &Config) -> Vec<Finding> {
+  match config.theme.as_deref() {
+    Some(name) if !theme::is_known_theme(name) => vec![Finding::error(
+      "theme",
+      format!(
+        "unknown theme '{}', the default is used (themes: {})",
+        name,
+        theme::THEME_NAMES.join(", ")
+      ),
+    )],
+    _ => Vec::new(),
+  }
+}
+
+fn check_theme_overrides(config: &Config) -> Vec<Finding> {
+  let Some(overrides) = &config.theme_overrides else {
+    return Vec::new();
+  };
+  let mut findings = check_unknown_keys("theme_overrides.", &overrides.unknown);
+  findings.extend(overrides.slots().into_iter().filter_map(|(slot, value)| {
+    let value = value?;
+    theme::parse_hex_color(value).is_none().then(|| {
+      Finding::error(
+        format!("theme_overrides.{}", slot),
+        format!("\"{}\" is not a #rrggbb color", value),
+      )
+    })
+  }));
+  findings
+}
+
+fn check_filter_focus(config: &Config) -> Vec<Finding> {
+  match config.filter_focus.as_deref().map(str::trim) {
+    Some(mode)
+      if !["explicit", "implicit", "auto"]
+        .iter()
+        .any(|m| mode.eq_ignore_ascii_case(m)) =>
+    {
+      vec![Finding::error(
+        "filter_focus",
+        format!(
+          "'{}' is not \"explicit\", \"auto\" or \"implicit\"; explicit is used",
+          mode
+        ),
+      )]
+    }
+    _ => Vec::new(),
+  }
+}
+
+fn check_sort(config: &Config) -> Vec<Finding> {
+  match config.sort.as_deref() {
+    Some(name) if SortMode::from_name(name).is_none() => vec![Finding::error(
+      "sort",
+      format!(
+        "'{}' is not \"state\", \"name\" or \"recent\"; state is used",
+        name.trim()
+      ),
+    )],
+    _ => Vec::new(),
+  }
+}
+
+fn check_collapsed_sections(config: &Config) -> Vec<Finding> {
+  let names: Vec<&str> = view::SECTION_NAMES.iter().map(|(n, _)| *n).collect();
+  config
+    .collapsed_sections
+    .iter()
+    .flatten()
+    .filter(|name| !names.iter().any(|n| name.trim().eq_ignore_ascii_case(n)))
+    .map(|name| {
+      Finding::warning(
+        "collapsed_sections",
+        format!(
+          "'{}' is not a section, ignored (sections: {})",
+          name.trim(),
+          names.join(", ")
+        ),
+      )
+    })
+    .collect()
+}
+
+fn check_keys(config: &Config) -> Vec<Finding> {
+  let Some(keys) = &config.keys else {
+    return Vec::new();
+  };
+  // The TUI skips these and carries on, so they are warnings here too
+  keys::KeyMap::build(Some(keys))
+    .1
+    .into_iter()
+    .map(|w| Finding::warning(w.key, w.message))
+    .collect()
+}
+
+fn check_simctl(config: &Config) -> Vec<Finding> {
+  match config.simctl_timeout_secs {
+    Some(0) => vec![Finding::error(
+      "simctl_timeout_secs",
+      "0 would kill every simctl call at once",
+    )],
+    _ => Vec::new(),
+  }
+}
+
+fn check_notify_cmd(config: &Config) -> Vec<Finding> {
+  match config.notify_cmd.as_deref() {
+    Some(cmd) if cmd.trim().is_empty() => vec![Finding::warning(
+      "notify_cmd",
+      "is empty, so the bell rings instead",
+    )],
+    Some(cmd) => program_problem(cmd)
+      .map(|problem| Finding::error("notify_cmd", problem))
+      .into_iter()
+      .collect(),
+    None => Vec::new(),
+  }
+}
+
+/// Arguments are passed verbatim, so an item holding a space is one
+/// argument to the emulator
+fn check_launch_args(key: &str, args: Option<&[String]>) -> Vec<Finding> {
+  let mut findings = Vec::new();
+  for (i, arg) in args.unwrap_or_default().iter().enumerate() {
+    let key = format!("{}[{}]", key, i);
+    if arg.is_empty() {
+      findings.push(Finding::error(key, "empty argument"));
+    } else if arg == "-avd" {
+      findings.push(Finding::error(key, "-avd is set by emulaunch"));
+    } else if arg.starts_with('-') && arg.contains(char::is_whitespace) {
+      findings.push(Finding::warning(
+        key,
+        format!(
+          "\"{}\" is passed as one argument; split it into separate items",
+          arg
+        ),
+      ));
+    }
+  }
+  findings
+}
+
+fn check_device(id: &str, device: &DeviceConfig) -> Vec<Finding> {
+  let key = format!("devices.{}", id);
+  let mut findings = check_unknown_keys(&format!("{}.", key), &device.unknown);
+  if device.fullscreen == Some(true) && device.hide_window == Some(true) {
+    findings.push(Finding::error(
+      key.as_str(),
+      "fullscreen and hide_window can't both be set; launches fail",
+    ));
+  }
+  findings.extend(check_launch_args(
+    &format!("{}.launch_args", key),
+    device.launch_args.as_deref(),
+  ));
+  findings
+}
+
+/// Per-device settings, and whether each still names a listed device (a
+/// warning, since devices come and go)
+fn check_devices(config: &Config, devices: &DeviceSnapshot) -> Vec<Finding> {
+  let Some(table) = &config.devices else {
+    return Vec::new();
+  };
+  let mut ids: Vec<&String> = table.keys().collect();
+  ids.sort();
+  let mut findings = Vec::new();
+  if devices.ids.is_none() && !ids.is_empty() {
+    findings.push(Finding::warning(
+      "devices",
+      "no devices could be listed, so the ids weren't checked",
+    ));
+  }
+  for id in ids {
+    if devices
+      .ids
+      .as_ref()
+      .is_some_and(|known| !known.contains(id))
+    {
+      findings.push(Finding::warning(
+        format!("devices.{}", id),
+        "no device with this id is listed",
+      ));
+    }
+    findings.extend(check_device(id, &table[id]));
+  }
+  findings
+}
@@ -1,169 +1,2278 @@
+mod adb;
+mod bundle;
+mod clipboard;
+mod clock;
+mod completions;
 mod config;
+mod console;
 mod emulators;
+mod filelock;
+mod genymotion;
+mod history;
+mod ini;
+mod keys;
+mod line_edit;
+mod lint;
+mod metrics;
+mod onboarding;
+mod plain;
+mod present;
+mod probes;
+mod queue;
+mod runner;
+mod serve;
+mod session;
+mod shell;
+mod snapshots;
+mod text;
 mod theme;
+mod trace;
+mod update;
+mod view;
 
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand, ValueHint};
 use crossterm::{
-  event::{self, Event, KeyCode, KeyEventKind},
+  event::{self, Event, KeyEventKind},
   terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
   ExecutableCommand,
 };
-use emulators::{EmulatorEntry, EmulatorType};
+use emulators::{EmulatorEntry, EntryKey, Platform};
+use keys::{Action, FilterFocusMode, Focus};
 use ratatui::{
-  layout::{Constraint, Layout},
+  layout::{Alignment, Constraint, Flex, Layout, Margin},
   style::{Modifier, Style},
   text::{Line, Span},
-  widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
-  Terminal,
+  widgets::{
+    Block, Borders, Clear, List, ListItem, ListState, Paragraph, Scrollbar, ScrollbarOrientation,
+    ScrollbarState, Wrap,
+  },
+  Terminal, TerminalOptions, Viewport,
 };
-use std::io;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How often device states are re-listed while the TUI is open, unless
+/// `refresh_interval_secs` says otherwise
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+// How long a row stays highlighted after its state changes
+const FLASH_DURATION: Duration = Duration::from_secs(2);
+
+// Left margin of a device row, under its section header
+const DEVICE_INDENT: &str = "   ";
+
+// Device names are never cut shorter than this to fit the row
+const MIN_NAME_WIDTH: usize = 12;
+
+// The activity log shows only this many of the newest outcomes; the exit
+// report still prints them all
+const MAX_LOG_ENTRIES: usize = 500;
+
+// How long a toast stays on the details pane
+const TOAST_DURATION: Duration = Duration::from_secs(4);
+
+// How long a device that showed up during the session is tagged "new"
+const NEW_TAG_DURATION: Duration = Duration::from_secs(30);
+
+// How long `open --wait` and `wait` wait unless --wait-timeout is given
+const DEFAULT_BOOT_WAIT_SECS: u64 = 120;
+
+// Exit status of `wait` when the timeout runs out, as with timeout(1)
+const EXIT_TIMED_OUT: i32 = 124;
+
+// Rows the picker takes below the prompt with `--inline`
+const INLINE_HEIGHT: u16 = 15;
+
+// Rows the list keeps, borders included, however tall the details get
+const MIN_LIST_HEIGHT: u16 = 5;
+
+// Below this the layout can't fit, and only a note asking for more room
+// is drawn
+const MIN_TUI_WIDTH: u16 = 20;
+const MIN_TUI_HEIGHT: u16 = 8;
+
+// Most devices the list shows unless `max_devices` says otherwise
+const DEFAULT_MAX_DEVICES: usize = 200;
 
 #[derive(Parser)]
 #[command(name = "emulators", about = "List and open Android/iOS emulators")]
 struct Cli {
   #[command(subcommand)]
   command: Option<Commands>,
+  /// Open the picker without checking Android device states first
+  #[arg(long)]
+  fast: bool,
+  /// Pick a device and print its AVD id or UDID instead of opening it;
+  /// exits non-zero if cancelled
+  #[arg(long)]
+  print: bool,
+  /// Draw the picker in the rows below the prompt instead of taking over
+  /// the screen (overrides `inline`)
+  #[arg(long)]
+  inline: bool,
+  /// Only re-list devices when asked to, not every few seconds (overrides
+  /// refresh_interval_secs)
+  #[arg(long)]
+  no_refresh: bool,
+  /// Print every external command run, and how it exited, on stderr
+  #[arg(long, short, global = true)]
+  verbose: bool,
+  /// Seconds before a hung simctl call is killed and retried (overrides
+  /// simctl_timeout_secs)
+  #[arg(long, global = true, value_name = "SECS")]
+  timeout: Option<u64>,
+  /// Look for tools that weren't found recently instead of assuming they
+  /// are still missing
+  #[arg(long, global = true)]
+  reprobe: bool,
 }
 
 #[derive(Subcommand)]
 enum Commands {
   /// Print a plain text list of all emulators
-  List,
+  List {
+    /// Print a JSON document instead, with errors in a separate field
+    #[arg(long)]
+    json: bool,
+    /// Print one line per device from a template of {name}, {id}, {state},
+    /// {platform} and {meta}; \t and \n are expanded
+    #[arg(long, value_name = "TEMPLATE", conflicts_with = "json")]
+    format: Option<String>,
+    /// Only list Android emulators
+    #[arg(long)]
+    android: bool,
+    /// Only list iOS simulators
+    #[arg(long)]
+    ios: bool,
+    /// Leave out the numbers `open` accepts in place of a name
+    #[arg(long)]
+    no_index: bool,
+    /// Color the list with the configured theme; `auto` colors only a
+    /// terminal, and never when NO_COLOR is set
+    #[arg(long, value_name = "WHEN", default_value = "auto", value_parser = ["auto", "always", "never"], conflicts_with_all = ["json", "format"])]
+    color: String,
+  },
   /// Open an emulator by name
   Open {
-    /// Name of the emulator to open
+    /// Name of the emulator to open, or its number in `list` (in list
+    /// order; a device named exactly that wins); several names (quoted if
+    /// they contain spaces) open several devices
+    #[arg(value_hint = ValueHint::Other)]
     name: Vec<String>,
+    /// Also boot the paired watch/phone simulator (iOS only)
+    #[arg(long)]
+    with_paired: bool,
+    /// Boot without loading the quick-boot snapshot (Android only)
+    #[arg(long)]
+    cold_boot: bool,
+    /// Show the emulator fullscreen (Android only)
+    #[arg(long)]
+    fullscreen: bool,
+    /// Start with the emulator window hidden (Android only)
+    #[arg(long)]
+    hide_window: bool,
+    /// Run the emulator without a window (Android only)
+    #[arg(long)]
+    headless: bool,
+    /// Boot the simulator without opening Simulator.app (iOS only)
+    #[arg(long)]
+    no_gui: bool,
+    /// Erase all data on the device before booting it (asks first on a terminal)
+    #[arg(long)]
+    wipe_data: bool,
+    /// Don't ask for confirmation before wiping data
+    #[arg(long, short)]
+    yes: bool,
+    /// Block until the device has finished booting (bounded by --wait-timeout).
+    /// Emulators queued past max_parallel_boots block anyway, each waiting for an earlier one
+    #[arg(long)]
+    wait: bool,
+    /// Seconds to wait for each boot, with --wait or a queue
+    #[arg(long, value_name = "SECS", default_value_t = DEFAULT_BOOT_WAIT_SECS)]
+    wait_timeout: u64,
+    /// Start another instance of an emulator that is already running
+    #[arg(long)]
+    force: bool,
+  },
+  /// Shut down a running device
+  Stop {
+    /// Name, id, adb serial or UDID of the device
+    #[arg(value_hint = ValueHint::Other)]
+    name: String,
+  },
+  /// Print the live state of a device (Booted or Shutdown)
+  Status {
+    /// Name, id, adb serial or UDID of the device
+    #[arg(value_hint = ValueHint::Other)]
+    name: String,
+  },
+  /// Print how to reach a device over the network: ports, the host alias
+  /// and its IP for Android, the shared network and data path for iOS
+  NetInfo {
+    /// Name, id, adb serial or UDID of the device
+    #[arg(value_hint = ValueHint::Other)]
+    name: String,
+    /// Print a JSON object instead
+    #[arg(long)]
+    json: bool,
+  },
+  /// Wait until a device reaches a state, without starting or stopping it
+  Wait {
+    /// Name, id, adb serial or UDID of the device
+    #[arg(value_hint = ValueHint::Other, required_unless_present = "any")]
+    name: Option<String>,
+    /// State to wait for; `booted` means finished booting
+    #[arg(long, default_value = "booted", value_parser = ["booted", "shutdown"])]
+    state: String,
+    /// Wait for any device of this platform to finish booting instead
+    #[arg(long, value_name = "PLATFORM", value_parser = ["android", "ios"], conflicts_with_all = ["name", "state"])]
+    any: Option<String>,
+    /// Print nothing; only the exit status tells (124 on timeout)
+    #[arg(long, short)]
+    quiet: bool,
+    /// Seconds to wait before giving up
+    #[arg(long, value_name = "SECS", default_value_t = DEFAULT_BOOT_WAIT_SECS)]
+    wait_timeout: u64,
+  },
+  /// Toggle adbd privileges on a running Android emulator: root, unroot,
+  /// remount, disable-verity or enable-verity (engineering images only)
+  Adb {
+    /// Name, AVD id or adb serial of the running emulator
+    #[arg(value_hint = ValueHint::Other)]
+    name: String,
+    #[arg(value_parser = adb::AdbdCommand::NAMES)]
+    command: String,
+  },
+  /// Send a raw command to a running Android emulator's console
+  Console {
+    /// Name, AVD id or adb serial of the running emulator
+    #[arg(value_hint = ValueHint::Other)]
+    name: String,
+    /// Console command, e.g. `sms send 5551234 "hello"`
+    #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+    command: Vec<String>,
+  },
+  /// Print the path of an app's container on a booted device
+  Container {
+    /// Name, id, adb serial or UDID of the device
+    #[arg(value_hint = ValueHint::Other)]
+    name: String,
+    /// Bundle id (iOS) or package name (Android)
+    bundle_id: String,
+    /// Container to resolve; `groups` is iOS only, Android support is best-effort
+    #[arg(long = "type", default_value = "app", value_parser = ["app", "data", "groups"])]
+    kind: String,
+    /// Reveal the container in Finder (macOS only)
+    #[arg(long)]
+    open: bool,
+  },
+  /// Show or change an AVD's RAM, VM heap and SD card size in its
+  /// config.ini (the AVD must not be running)
+  Tune {
+    /// Exact name or id of the AVD
+    #[arg(value_hint = ValueHint::Other)]
+    name: String,
+    /// RAM in MB (hw.ramSize)
+    #[arg(long, value_name = "MB")]
+    ram: Option<u32>,
+    /// VM heap in MB (vm.heapSize)
+    #[arg(long, value_name = "MB")]
+    heap: Option<u32>,
+    /// SD card size with a K, M or G suffix, e.g. 2G (sdcard.size)
+    #[arg(long, value_name = "SIZE")]
+    sdcard: Option<String>,
+    /// Print the current values without changing anything
+    #[arg(long, conflicts_with_all = ["ram", "heap", "sdcard"])]
+    show: bool,
+  },
+  /// Delete an AVD or simulator
+  Delete {
+    /// Name of the emulator to delete
+    #[arg(value_hint = ValueHint::Other)]
+    name: Vec<String>,
+    /// Shut the device down first if it is booted
+    #[arg(long)]
+    force: bool,
+    /// Don't ask for confirmation
+    #[arg(long, short)]
+    yes: bool,
+  },
+  /// Erase an iOS simulator's content and settings (simctl erase)
+  Erase {
+    /// Name of the simulator to erase; several names (quoted if they
+    /// contain spaces) erase several
+    #[arg(value_hint = ValueHint::Other, required_unless_present = "all")]
+    name: Vec<String>,
+    /// Erase every shut-down simulator instead
+    #[arg(long, conflicts_with = "name")]
+    all: bool,
+    /// Don't ask for confirmation
+    #[arg(long, short)]
+    yes: bool,
+  },
+  /// Save a PNG of a booted device's screen
+  Screenshot {
+    /// Name, id, adb serial or UDID of the device
+    #[arg(value_hint = ValueHint::Other)]
+    name: String,
+    /// Output file; `-` writes the PNG to stdout (default: a timestamped
+    /// file in the current directory)
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+  },
+  /// Create a new emulator or simulator
+  Create {
+    #[command(subcommand)]
+    target: CreateCommand,
+  },
+  /// Manage the emulaunch configuration
+  Config {
+    #[command(subcommand)]
+    action: ConfigCommand,
+  },
+  /// Summarize recorded usage: launches, boot times and stops per device
+  /// (needs `metrics = true`; nothing is sent anywhere)
+  Stats {
+    /// Print a JSON object instead
+    #[arg(long)]
+    json: bool,
+  },
+  /// Manage the launch history behind the Recent section and boot estimates
+  History {
+    #[command(subcommand)]
+    action: HistoryCommand,
+  },
+  /// Print where the EMULAUNCH_TRACE=1 trace is written and a redacted
+  /// summary of it to paste into an issue
+  TraceInfo,
+  /// Start an interactive prompt that accepts the other subcommands
+  Shell,
+  /// Open the device picker (the same as running with no subcommand)
+  Pick {
+    /// Numbered list and line prompts on plain stdout/stdin, for screen
+    /// readers (automatic with TERM=dumb)
+    #[arg(long)]
+    plain: bool,
+  },
+  /// Serve JSON-RPC requests for editor integrations
+  Serve {
+    /// Read newline-delimited requests on stdin and answer on stdout
+    #[arg(long)]
+    stdio: bool,
+  },
+  /// Print a completion script for bash, zsh, fish or powershell
+  Completions { shell: clap_complete::Shell },
+  /// Print the version, optionally checking GitHub for a newer release
+  Version {
+    /// Look up the latest release now
+    #[arg(long)]
+    check: bool,
+  },
+  /// Print device names for completion scripts, one per line
+  #[command(name = "__complete-names", hide = true)]
+  CompleteNames,
+}
+
+#[derive(Subcommand)]
+enum CreateCommand {
+  /// Create an AVD with avdmanager
+  Android {
+    /// AVD name (letters, digits, `.`, `_` and `-`)
+    name: String,
+    /// System image package, e.g. "system-images;android-34;google_apis;arm64-v8a"
+    #[arg(long)]
+    system_image: String,
+    /// Hardware profile id or name, e.g. pixel_7 (see `avdmanager list device`)
+    #[arg(long)]
+    device: Option<String>,
+  },
+  /// Create a simulator with simctl (macOS only)
+  Ios {
+    /// Simulator name
+    name: String,
+    /// Device type identifier or name, e.g. "iPhone 15"
+    #[arg(long)]
+    device_type: String,
+    /// Runtime identifier or name, e.g. com.apple.CoreSimulator.SimRuntime.iOS-17-0
+    #[arg(long)]
+    runtime: String,
+  },
+}
+
+#[derive(Subcommand)]
+enum HistoryCommand {
+  /// Forget every recorded launch
+  Clear,
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+  /// Print the loaded config file, its settings and the resolved commands
+  Show,
+  /// Write a commented example config to the first searched location
+  Init {
+    /// Overwrite an existing config file
+    #[arg(long)]
+    force: bool,
+  },
+  /// List the config file locations in the order they are searched
+  Paths,
+  /// Check every setting (tool paths, theme, colors, device ids, launch
+  /// arguments); exits non-zero only when there are errors
+  Lint {
+    /// Print a JSON report instead
+    #[arg(long)]
+    json: bool,
+  },
+  /// Bundle the config (and optionally history) into a directory or .tar.gz
+  Export {
+    /// Target directory, or a file ending in .tar.gz/.tgz
+    path: PathBuf,
+    /// Include the launch history
+    #[arg(long)]
+    history: bool,
+  },
+  /// Install a bundle made by `config export` on this machine
+  Import {
+    /// Bundle directory or .tar.gz archive
+    path: PathBuf,
+    /// Overwrite existing files
+    #[arg(long)]
+    force: bool,
   },
 }
 
 fn main() {
   let cli = Cli::parse();
+  // The picker owns the terminal, so only trace it when stderr goes elsewhere
+  if cli.verbose && (cli.command.is_some() || !io::stderr().is_terminal()) {
+    runner::set_verbose();
+  }
+  if let Some(secs) = cli.timeout {
+    runner::set_timeout_override(Duration::from_secs(secs));
+  }
+  if cli.reprobe {
+    probes::clear();
+  }
 
-  match cli.command {
-    Some(Commands::List) => {
-      print!("{}", emulators::format_emulator_list());
+  let result = match cli.command {
+    Some(Commands::Shell) => shell::run().map_err(CommandError::from),
+    Some(Commands::Pick { plain }) => {
+      run_picker(cli.fast, cli.print, cli.inline, cli.no_refresh, plain)
     }
-    Some(Commands::Open { name }) => {
-      let name = name.join(" ");
-      match emulators::find_emulator(&name) {
-        Ok(EmulatorType::Android(emu_name)) => match emulators::open_android_emulator(&emu_name) {
-          Ok(msg) => println!("{}", msg),
-          Err(e) => {
-            eprintln!("Error: {}", e);
-            std::process::exit(1);
+    Some(command) => {
+      // Machine-facing commands never print the update notice
+      let notice = (!matches!(
+        command,
+        Commands::Serve { .. }
+          | Commands::Completions { .. }
+          | Commands::CompleteNames
+          | Commands::Version { .. }
+      ))
+      .then(update::spawn_notice);
+      // `config lint` reports these itself
+      if !matches!(
+        command,
+        Commands::Serve { .. }
+          | Commands::Completions { .. }
+          | Commands::CompleteNames
+          | Commands::Config {
+            action: ConfigCommand::Lint { .. }
           }
-        },
-        Ok(EmulatorType::IOS(udid)) => match emulators::open_ios_simulator(&udid) {
+      ) {
+        for warning in config::warnings() {
+          eprintln!("Warning: {}", warning);
+        }
+      }
+      let result = run_command(command);
+      if let Some(Ok(msg)) = notice.map(|rx| rx.recv_timeout(update::FETCH_TIMEOUT)) {
+        eprintln!("{}", msg);
+      }
+      result
+    }
+    None => run_picker(cli.fast, cli.print, cli.inline, cli.no_refresh, false),
+  };
+
+  match result {
+    Ok(()) => {}
+    Err(CommandError::Message(e)) => {
+      eprintln!("Error: {}", e);
+      std::process::exit(1);
+    }
+    Err(CommandError::Exit(code)) => std::process::exit(code),
+  }
+}
+
+/// Why a command failed: a message for `main` to print, or an exit status
+/// for a failure that was already reported
+#[derive(Debug)]
+pub enum CommandError {
+  Message(String),
+  Exit(i32),
+}
+
+impl From<String> for CommandError {
+  fn from(message: String) -> Self {
+    CommandError::Message(message)
+  }
+}
+
+impl From<&str> for CommandError {
+  fn from(message: &str) -> Self {
+    CommandError::Message(message.to_string())
+  }
+}
+
+/// Run the TUI, or the plain line-based picker when asked for or when the
+/// terminal is dumb
+fn run_picker(
+  fast: bool,
+  print: bool,
+  inline: bool,
+  no_refresh: bool,
+  plain: bool,
+) -> Result<(), CommandError> {
+  let result = if plain || plain::terminal_is_dumb() {
+    plain::run(fast, print)
+  } else {
+    run_tui(fast, print, inline, no_refresh)
+  };
+  match result {
+    // Failed actions were already reported; only the exit code is left
+    Ok(false) => Err(CommandError::Exit(1)),
+    result => result.map(|_| ()).map_err(|e| e.to_string().into()),
+  }
+}
+
+/// `wait`: poll until the device reaches `state`, or exit with
+/// `EXIT_TIMED_OUT` once the timeout runs out
+fn run_wait(
+  name: Option<String>,
+  state: String,
+  any: Option<String>,
+  quiet: bool,
+  timeout: Duration,
+) -> Result<(), CommandError> {
+  // A dot a second on a terminal, so a long wait visibly isn't stuck
+  let dots = !quiet && io::stderr().is_terminal();
+  let mut printed = 0;
+  let tick = |elapsed: Duration| {
+    if dots && elapsed.as_secs() > printed {
+      printed = elapsed.as_secs();
+      eprint!(".");
+    }
+  };
+  let (label, reached) = match (any.as_deref(), name) {
+    (Some(any), _) => {
+      let platform = if any == "ios" {
+        Platform::Ios
+      } else {
+        Platform::Android
+      };
+      let mut found = None;
+      let reached = emulators::poll_until(timeout, tick, || {
+        found = emulators::any_boot_completed(platform)?;
+        Ok(found.is_some())
+      });
+      let label = found.map_or(format!("an {} device", platform.label()), |k| k.to_string());
+      (label, reached)
+    }
+    (None, Some(name)) => {
+      let key = emulators::resolve_device(&name)?;
+      let reached = if state == "booted" {
+        emulators::poll_until(timeout, tick, || Ok(emulators::boot_completed(&key)))
+      } else {
+        emulators::poll_until(timeout, tick, || {
+          Ok(emulators::device_state(&key)? == emulators::STATE_SHUTDOWN)
+        })
+      };
+      (name, reached)
+    }
+    (None, None) => unreachable!("clap requires a name or --any"),
+  };
+  if dots && printed > 0 {
+    eprintln!();
+  }
+  match reached? {
+    Some(elapsed) if !quiet => println!("{} is {} after {}s", label, state, elapsed.as_secs()),
+    Some(_) => {}
+    None => {
+      if !quiet {
+        eprintln!(
+          "Error: {} did not become {} within {}s",
+          label,
+          state,
+          timeout.as_secs()
+        );
+      }
+      return Err(CommandError::Exit(EXIT_TIMED_OUT));
+    }
+  }
+  Ok(())
+}
+
+/// Launch one device for `open`, asking before wiping it. A running
+/// emulator is left alone without `force`. Returns whether `--wait` has a
+/// boot to wait for (a device that was already booted doesn't).
+fn launch_device(
+  key: &EntryKey,
+  options: &emulators::LaunchOptions,
+  yes: bool,
+  wait: bool,
+  force: bool,
+) -> Result<bool, String> {
+  if options.wipe_data {
+    // Refuse a booted device before asking, not after
+    emulators::check_launch(key, options)?;
+    if !yes
+      && io::stdin().is_terminal()
+      && !confirm(&format!(
+        "Wipe all data on {}? This can't be undone.\n",
+        key
+      ))?
+    {
+      return Err("Aborted".to_string());
+    }
+  }
+  // Simulators take a second boot in stride; a second emulator process
+  // fails or opens a duplicate window
+  let android_id = match key {
+    EntryKey::Android(id) if !force => Some(id),
+    _ => None,
+  };
+  let already_booted = (wait || android_id.is_some())
+    && emulators::device_state(key).is_ok_and(|s| s == emulators::STATE_BOOTED);
+  if let Some(id) = android_id.filter(|_| already_booted) {
+    println!(
+      "{} is already running; pass --force to launch another instance",
+      id
+    );
+    return Ok(false);
+  }
+  println!("{}", emulators::open_key(key, options)?);
+  let _ = metrics::record(metrics::MetricEvent::Launch { key: key.clone() });
+  let waiting = wait && !already_booted;
+  // A boot that is waited on is recorded with its duration by `wait_for_boot`
+  if !waiting {
+    let _ = history::record_launch(key, None);
+  }
+  Ok(waiting)
+}
+
+/// Whether `--color` output should be colored: `auto` only for a terminal
+/// and when NO_COLOR is unset or empty (https://no-color.org)
+fn use_color(when: &str) -> bool {
+  match when {
+    "always" => true,
+    "never" => false,
+    _ => io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()),
+  }
+}
+
+/// `open --wait`: block until a launched device reports it has booted
+fn wait_for_boot(
+  name: &str,
+  key: &EntryKey,
+  options: &emulators::LaunchOptions,
+  timeout: Duration,
+) -> Result<(), String> {
+  let waited = emulators::wait_until_booted(key, timeout, |elapsed| {
+    eprintln!("Waiting for {} to boot... {}s", name, elapsed.as_secs())
+  });
+  let elapsed = match waited {
+    Ok(elapsed) => elapsed,
+    Err(e) => {
+      // It was still launched, just not timed
+      let _ = history::record_launch(key, None);
+      return Err(e);
+    }
+  };
+  // Boot times feed the TUI's estimates; failing to record one isn't fatal
+  // A wiped device has no quick-boot snapshot to load
+  let mode = history::BootMode::of_launch(key, options.cold_boot || options.wipe_data);
+  let _ = history::record_launch(key, Some((elapsed, mode)));
+  let _ = metrics::record(metrics::MetricEvent::Boot {
+    key: key.clone(),
+    secs: elapsed.as_secs_f64(),
+    mode,
+  });
+  println!("{} booted in {}s", name, elapsed.as_secs());
+  Ok(())
+}
+
+/// `screenshot-YYYYMMDD-HHMMSS.png` (UTC) in the current directory
+fn default_screenshot_path() -> PathBuf {
+  PathBuf::from(format!(
+    "screenshot-{}.png",
+    clock::UtcTime::now().compact()
+  ))
+}
+
+/// Ask a yes/no question on stderr; anything but `y`/`yes` is a no
+fn confirm(prompt: &str) -> Result<bool, String> {
+  eprint!("{}Continue? [y/N] ", prompt);
+  let mut answer = String::new();
+  io::stdin()
+    .read_line(&mut answer)
+    .map_err(|e| format!("Failed to read answer: {}", e))?;
+  Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Run a non-interactive subcommand, printing its output
+fn run_command(command: Commands) -> Result<(), CommandError> {
+  match command {
+    Commands::Wait {
+      name,
+      state,
+      any,
+      quiet,
+      wait_timeout,
+    } => run_wait(name, state, any, quiet, Duration::from_secs(wait_timeout)),
+    command => run_subcommand(command).map_err(CommandError::Message),
+  }
+}
+
+/// Every subcommand but `wait`, which can end with an exit status of its own
+fn run_subcommand(command: Commands) -> Result<(), String> {
+  match command {
+    Commands::List {
+      json,
+      format,
+      android,
+      ios,
+      no_index,
+      color,
+    } => {
+      let platforms: &[Platform] = match (android, ios) {
+        (true, false) => &[Platform::Android],
+        (false, true) => &[Platform::Ios],
+        // Unfiltered JSON and templates leave out platforms this host can't list
+        _ if json || format.is_some() => Platform::supported(),
+        _ => &[Platform::Android, Platform::Ios],
+      };
+      if let Some(template) = format {
+        let (output, errors) = emulators::format_emulator_list_template(platforms, &template)?;
+        print!("{}", output);
+        for e in errors {
+          eprintln!("Error: {}", e.message);
+        }
+      } else if json {
+        println!("{}", emulators::format_emulator_list_json(platforms)?);
+      } else {
+        // Indices count from the first Android emulator, so an iOS-only
+        // listing can't show them
+        let numbered = !no_index && platforms.first() == Some(&Platform::Android);
+        let colors = use_color(&color).then(|| {
+          let cfg = config::load_config();
+          theme::resolve_theme(
+            cfg.as_ref().and_then(|c| c.theme.as_deref()),
+            cfg.as_ref().and_then(|c| c.theme_overrides.as_ref()),
+          )
+        });
+        print!(
+          "{}",
+          emulators::format_emulator_list(platforms, numbered, colors.as_ref())
+        );
+      }
+    }
+    Commands::Open {
+      name,
+      with_paired,
+      cold_boot,
+      fullscreen,
+      hide_window,
+      headless,
+      no_gui,
+      wipe_data,
+      yes,
+      wait,
+      wait_timeout,
+      force,
+    } => {
+      let wait_timeout = Duration::from_secs(wait_timeout);
+      let options = emulators::LaunchOptions {
+        with_paired,
+        cold_boot,
+        fullscreen,
+        hide_window,
+        headless,
+        wipe_data,
+        no_gui,
+      };
+      // A wipe can't be undone, so it only goes to an exactly named device
+      let targets = if wipe_data {
+        emulators::find_emulators_exact(&name)?
+      } else {
+        emulators::find_emulators(&name)?
+      };
+      let total = targets.len();
+      let mut failures = Vec::new();
+      let mut booting = Vec::new();
+      // Simulators start together; emulators past max_parallel_boots wait
+      // for earlier ones, since booting together they fight for disk and CPU
+      let mut android = Vec::new();
+      for (name, key) in targets {
+        match key {
+          Ok(key @ EntryKey::Android(_)) => android.push((name, key)),
+          Ok(key) => match launch_device(&key, &options, yes, wait, force) {
+            Ok(true) => booting.push((name, key)),
+            Ok(false) => {}
+            Err(e) => failures.push((name, e)),
+          },
+          Err(e) => failures.push((name, e)),
+        }
+      }
+      let limit = config::max_parallel_boots();
+      let mut queue = queue::BootQueue::new(android.len(), limit);
+      let queued = queue.is_limited();
+      if let Some(limit) = limit.filter(|_| queued) {
+        eprintln!(
+          "Booting {} emulators {} at a time (max_parallel_boots); Ctrl+C stops the queue and leaves started ones running",
+          android.len(),
+          limit
+        );
+      }
+      while let Some(step) = queue.next() {
+        match step {
+          queue::Step::Start(i) => {
+            let (name, key) = &android[i];
+            if queued {
+              println!("Booting {}/{}: {}", i + 1, android.len(), name);
+            }
+            // A queued boot is waited on even without --wait
+            match launch_device(key, &options, yes, wait || queued, force) {
+              Ok(true) => queue.booting(i),
+              Ok(false) => queue.done(i),
+              Err(e) => {
+                failures.push((name.clone(), e));
+                queue.done(i);
+              }
+            }
+          }
+          queue::Step::Wait(i) => {
+            let (name, key) = &android[i];
+            if let Err(e) = wait_for_boot(name, key, &options, wait_timeout) {
+              failures.push((name.clone(), e));
+            }
+            queue.done(i);
+          }
+        }
+      }
+      if wait {
+        booting.extend(
+          queue
+            .still_booting()
+            .into_iter()
+            .map(|i| android[i].clone()),
+        );
+      }
+      for (name, key) in booting {
+        if let Err(e) = wait_for_boot(&name, &key, &options, wait_timeout) {
+          failures.push((name, e));
+        }
+      }
+      if total == 1 {
+        if let Some((_, e)) = failures.pop() {
+          return Err(e);
+        }
+      } else if !failures.is_empty() {
+        for (name, e) in &failures {
+          eprintln!("Error: {}: {}", name, e);
+        }
+        return Err(format!(
+          "{} of {} devices failed to open",
+          failures.len(),
+          total
+        ));
+      }
+    }
+    Commands::Stop { name } => {
+      let key = emulators::resolve_device(&name)?;
+      println!("{}", emulators::stop_device(&key)?);
+      let _ = metrics::record(metrics::MetricEvent::Stop { key });
+    }
+    Commands::Status { name } => {
+      let key = emulators::resolve_device(&name)?;
+      println!("{}", emulators::device_state(&key)?);
+    }
+    Commands::NetInfo { name, json } => {
+      let key = emulators::resolve_device(&name)?;
+      let info = emulators::net_info(&key)?;
+      if json {
+        println!(
+          "{}",
+          serde_json::to_string_pretty(&info).map_err(|e| e.to_string())?
+        );
+      } else {
+        print!("{}", format_net_info(&info));
+      }
+    }
+    Commands::Adb { name, command } => {
+      let key = emulators::resolve_device(&name)?;
+      let command = adb::AdbdCommand::from_name(&command).ok_or("unknown adb command")?;
+      println!("{}", emulators::run_adbd_command(&key, command)?);
+    }
+    Commands::Tune {
+      name,
+      ram,
+      heap,
+      sdcard,
+      show,
+    } => {
+      // Edits config.ini, so no guessing which AVD was meant
+      let key = emulators::find_emulator_exact(&name)?;
+      let tuning = emulators::AvdTuning {
+        ram_mb: ram,
+        heap_mb: heap,
+        sdcard,
+      };
+      if show {
+        for (k, value) in emulators::avd_hardware(&key)? {
+          println!("{:<12} {}", k, value.as_deref().unwrap_or("(unset)"));
+        }
+      } else if tuning.is_empty() {
+        return Err("Nothing to change: pass --ram, --heap or --sdcard, or --show".to_string());
+      } else {
+        for value in emulators::tune_avd(&key, &tuning)? {
+          println!(
+            "{:<12} {} -> {}",
+            value.key,
+            value.before.as_deref().unwrap_or("(unset)"),
+            value.after
+          );
+        }
+        if tuning.sdcard.is_some() {
+          println!(
+            "An existing SD card image keeps its size until the AVD is wiped (open --wipe-data)"
+          );
+        }
+      }
+    }
+    Commands::Console { name, command } => {
+      let key = emulators::resolve_device(&name)?;
+      let output = emulators::send_console_command(&key, &console::join_command(&command))?;
+      if !output.is_empty() {
+        println!("{}", output);
+      }
+    }
+    Commands::Container {
+      name,
+      bundle_id,
+      kind,
+      open,
+    } => {
+      let key = emulators::resolve_device(&name)?;
+      if open && matches!(key, EntryKey::Android(_)) {
+        return Err("--open is only available for iOS simulators".to_string());
+      }
+      let path = emulators::app_container(&key, &name, &bundle_id, &kind)?;
+      println!("{}", path);
+      if open {
+        emulators::reveal_in_finder(&path)?;
+      }
+    }
+    Commands::Screenshot { name, output } => {
+      let key = emulators::resolve_device(&name)?;
+      let png = emulators::screenshot(&key)?;
+      match output {
+        Some(path) if path.as_os_str() == "-" => io::stdout()
+          .write_all(&png)
+          .map_err(|e| format!("Failed to write screenshot: {}", e))?,
+        _ => {
+          let path = output.unwrap_or_else(default_screenshot_path);
+          std::fs::write(&path, &png)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+          println!("Saved screenshot to {}", path.display());
+        }
+      }
+    }
+    Commands::Delete { name, force, yes } => {
+      // No guessing when deleting: the name or id must match exactly
+      let key = emulators::find_emulator_exact(&name.join(" "))?;
+      let booted = emulators::device_state(&key)? == emulators::STATE_BOOTED;
+      if booted && !force {
+        return Err(emulators::ERR_DEVICE_BOOTED.to_string());
+      }
+      if !yes && io::stdin().is_terminal() {
+        let mut prompt = format!(
+          "Delete {}?\n  {}\n",
+          key,
+          emulators::deletion_targets(&key)?.join("\n  ")
+        );
+        if booted {
+          prompt.push_str("It is booted and will be shut down first.\n");
+        }
+        if !confirm(&prompt)? {
+          return Err("Aborted".to_string());
+        }
+      }
+      emulators::ensure_not_booted(&key, force)?;
+      println!("{}", emulators::delete_device(&key)?);
+    }
+    Commands::Erase { name, all, yes } => {
+      let targets: Vec<(String, EntryKey)> = if all {
+        emulators::erasable_simulators()?
+          .into_iter()
+          .map(|s| (s.name, EntryKey::Ios(s.udid)))
+          .collect()
+      } else {
+        // No guessing when erasing: the name or id must match exactly
+        let name = name.join(" ");
+        let key = emulators::find_emulator_exact(&name)?;
+        if matches!(key, EntryKey::Android(_)) {
+          return Err(emulators::ERR_ERASE_IOS_ONLY.to_string());
+        }
+        vec![(name, key)]
+      };
+      if targets.is_empty() {
+        println!("No shut-down iOS simulators to erase");
+        return Ok(());
+      }
+      if !yes && io::stdin().is_terminal() {
+        let listed: Vec<String> = targets
+          .iter()
+          .map(|(name, key)| format!("{} ({})", name, key))
+          .collect();
+        let prompt = format!(
+          "Erase all content and settings of:\n  {}\nBooted simulators are shut down first.\n",
+          listed.join("\n  ")
+        );
+        if !confirm(&prompt)? {
+          return Err("Aborted".to_string());
+        }
+      }
+      let total = targets.len();
+      let mut failed = 0;
+      for (name, key) in &targets {
+        match emulators::erase_device(key) {
           Ok(msg) => println!("{}", msg),
+          Err(e) if total == 1 => return Err(e),
           Err(e) => {
-            eprintln!("Error: {}", e);
-            std::process::exit(1);
+            eprintln!("Error: {}: {}", name, e);
+            failed += 1;
           }
-        },
-        Err(e) => {
-          eprintln!("Error: {}", e);
-          std::process::exit(1);
         }
       }
+      if failed > 0 {
+        return Err(format!(
+          "{} of {} simulators failed to erase",
+          failed, total
+        ));
+      }
     }
-    None => {
-      if let Err(e) = run_tui() {
-        eprintln!("Error: {}", e);
-        std::process::exit(1);
+    Commands::Create { target } => {
+      let msg = match target {
+        CreateCommand::Android {
+          name,
+          system_image,
+          device,
+        } => emulators::create_android_emulator(&name, &system_image, device.as_deref())?,
+        CreateCommand::Ios {
+          name,
+          device_type,
+          runtime,
+        } => emulators::create_ios_simulator(&name, &device_type, &runtime)?,
+      };
+      println!("{}", msg);
+    }
+    Commands::History { action } => match action {
+      HistoryCommand::Clear => match history::clear()? {
+        0 => println!("No launch history to clear"),
+        1 => println!("Cleared 1 launch from the history"),
+        n => println!("Cleared {} launches from the history", n),
+      },
+    },
+    Commands::Stats { json } => {
+      if !config::metrics_enabled() {
+        eprintln!("Note: metrics is off; set `metrics = true` in the config to record usage");
+      }
+      let summary = metrics::summarize(&metrics::load());
+      if json {
+        println!(
+          "{}",
+          serde_json::to_string_pretty(&summary).map_err(|e| e.to_string())?
+        );
+      } else {
+        print!("{}", metrics::format_summary(&summary));
+      }
+    }
+    Commands::TraceInfo => {
+      let path = trace::trace_path().ok_or_else(|| config::ERR_NO_HOME.to_string())?;
+      println!("Trace file: {}", path.display());
+      if !trace::enabled() {
+        println!(
+          "Tracing is off; run with {}=1 to record one",
+          trace::TRACE_ENV
+        );
+      }
+      println!();
+      print!("{}", trace::format_summary(&trace::load()));
+    }
+    Commands::Config { action } => {
+      let msg = match action {
+        ConfigCommand::Show => {
+          // Show what is there now, not what was missing a while ago
+          probes::clear();
+          config::describe_config()
+        }
+        ConfigCommand::Init { force } => config::init_example_config(force)?,
+        ConfigCommand::Paths => config::describe_config_paths(),
+        ConfigCommand::Lint { json } => {
+          let report = lint::run();
+          let output = if json {
+            serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?
+          } else {
+            report.to_text()
+          };
+          println!("{}", output);
+          if report.errors > 0 {
+            return Err(format!(
+              "config lint found {} {}",
+              report.errors,
+              if report.errors == 1 {
+                "error"
+              } else {
+                "errors"
+              }
+            ));
+          }
+          return Ok(());
+        }
+        ConfigCommand::Export { path, history } => bundle::export(&path, history)?,
+        ConfigCommand::Import { path, force } => bundle::import(&path, force)?,
+      };
+      println!("{}", msg);
+    }
+    Commands::Wait { .. } => unreachable!("run_command handles wait"),
+    Commands::Shell => return Err("Already in an emulaunch shell".to_string()),
+    // Only reached from the shell, which is line-based already
+    Commands::Pick { .. } => {
+      plain::run(false, false).map_err(|e| e.to_string())?;
+    }
+    Commands::Serve { stdio: true } => serve::run_stdio()?,
+    Commands::Serve { stdio: false } => {
+      return Err("serve needs a transport; pass --stdio".to_string())
+    }
+    Commands::Completions { shell } => {
+      print!("{}", completions::script(shell, &mut Cli::command()));
+    }
+    Commands::Version { check } => {
+      println!("emulaunch {}", update::CURRENT_VERSION);
+      if check {
+        println!("{}", update::check_now()?);
+      }
+    }
+    Commands::CompleteNames => {
+      for name in emulators::completion_names() {
+        println!("{}", name);
       }
     }
   }
+  Ok(())
+}
+
+/// Fresh listing for one platform, sent from a refresh thread
+struct PlatformUpdate {
+  platform: Platform,
+  result: Result<Vec<EmulatorEntry>, String>,
+  /// From a fast listing: Android states are placeholders and the full
+  /// listing is still coming
+  unchecked: bool,
+}
+
+/// What a recorded TUI action did to a device
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutcomeAction {
+  Open,
+  OpenCold,
+  OpenSnapshot,
+  DeleteSnapshot,
+  Stop,
+  /// Bringing a running device's window forward
+  Focus,
+}
+
+impl OutcomeAction {
+  fn label(self) -> &'static str {
+    match self {
+      OutcomeAction::Open => "open",
+      OutcomeAction::OpenCold => "cold boot",
+      OutcomeAction::OpenSnapshot => "open from snapshot",
+      OutcomeAction::DeleteSnapshot => "delete snapshot",
+      OutcomeAction::Stop => "stop",
+      OutcomeAction::Focus => "focus",
+    }
+  }
+}
+
+/// The result of one TUI action. Outcomes are kept in order for the whole
+/// session, shown as toasts while recent and printed once the terminal is
+/// restored.
+struct ActionOutcome {
+  key: EntryKey,
+  action: OutcomeAction,
+  result: Result<String, String>,
+  at: Instant,
+}
+
+/// The newest `MAX_LOG_ENTRIES` outcomes, as the activity log lists them
+fn log_window(outcomes: &[ActionOutcome]) -> &[ActionOutcome] {
+  &outcomes[outcomes.len().saturating_sub(MAX_LOG_ENTRIES)..]
+}
+
+/// Print the session's outcomes in order, errors on stderr; returns whether
+/// every action succeeded
+fn print_outcomes(outcomes: &[ActionOutcome]) -> bool {
+  for outcome in outcomes {
+    match &outcome.result {
+      Ok(msg) => println!("{}", msg),
+      Err(e) => eprintln!("Error: {} {}: {}", outcome.action.label(), outcome.key, e),
+    }
+  }
+  outcomes.iter().all(|o| o.result.is_ok())
+}
+
+/// Snapshot list shown in the details pane for one AVD
+struct SnapshotPane {
+  avd_id: String,
+  avd_name: String,
+  serial: Option<String>,
+  snapshots: Vec<snapshots::Snapshot>,
+  list_state: ListState,
+  /// Load error or the outcome of the last delete
+  message: Option<String>,
+}
+
+impl SnapshotPane {
+  /// Read the AVD's snapshots, keeping the selection position when reloading
+  fn load(avd_id: String, avd_name: String, serial: Option<String>, selected: usize) -> Self {
+    let (snapshots, message) = match snapshots::list(&avd_id) {
+      Ok(list) => (list, None),
+      Err(e) => (Vec::new(), Some(e)),
+    };
+    let mut list_state = ListState::default();
+    if !snapshots.is_empty() {
+      list_state.select(Some(selected.min(snapshots.len() - 1)));
+    }
+    SnapshotPane {
+      avd_id,
+      avd_name,
+      serial,
+      snapshots,
+      list_state,
+      message,
+    }
+  }
+
+  fn selected(&self) -> Option<&snapshots::Snapshot> {
+    self.snapshots.get(self.list_state.selected()?)
+  }
+
+  fn move_selection(&mut self, delta: i32) {
+    let Some(current) = self.list_state.selected() else {
+      return;
+    };
+    let next = if delta > 0 {
+      (current + 1).min(self.snapshots.len().saturating_sub(1))
+    } else {
+      current.saturating_sub(1)
+    };
+    self.list_state.select(Some(next));
+  }
 }
 
 struct App {
+  sections: Vec<(Platform, Vec<EmulatorEntry>)>,
   entries: Vec<EmulatorEntry>,
   filtered_indices: Vec<usize>,
+  /// How each of `entries` is shown (`None` for headers)
+  views: Vec<Option<present::DeviceView>>,
   list_state: ListState,
-  filter: String,
-  result_message: Option<String>,
+  /// Rows the list had in the last frame, the size of a page
+  list_rows: usize,
+  /// First row of `filtered_indices` in view, kept from frame to frame so
+  /// the list only scrolls when the selection leaves the viewport
+  list_offset: usize,
+  filter: line_edit::LineEdit,
+  focus_mode: FilterFocusMode,
+  focus: Focus,
+  keymap: keys::KeyMap,
+  /// The popup listing every binding is open; the next key closes it
+  help_open: bool,
+  /// A failed launch's title and error, shown in a popup until Esc or Enter
+  error_popup: Option<(String, String)>,
+  /// The theme in use, as a position in `theme::THEME_NAMES`
+  theme: usize,
+  /// `theme_overrides` from the config, applied over whichever theme is used
+  theme_overrides: Option<config::ThemeOverrides>,
+  /// Open while the theme picker is; the UI previews the selected theme
+  theme_picker: Option<ListState>,
+  /// Every launch or deletion this session, oldest first
+  outcomes: Vec<ActionOutcome>,
+  /// Open while the details pane shows the outcomes; scrolls on its own
+  log: Option<ListState>,
+  /// When the picker opened; log entries are stamped relative to it
+  started: Instant,
+  history: Vec<history::HistoryRecord>,
+  /// How devices are ordered within each section
+  sort: view::SortMode,
+  /// Last launch of each device, from `history`, for `SortMode::Recent`
+  last_launched: HashMap<EntryKey, u64>,
+  /// Platforms whose section is hidden; they aren't listed until shown
+  hidden_platforms: HashSet<Platform>,
+  /// Titles of sections folded down to their header
+  collapsed: HashSet<String>,
+  /// Rows start with Nerd Font icons (`icons`)
+  icons: bool,
+  /// Size of the Recent section (`recent_devices`)
+  recent_devices: usize,
+  /// Moving past either end of the list continues from the other
+  wrap_navigation: bool,
+  refreshing: HashSet<Platform>,
+  /// Platforms whose first listing hasn't arrived; the TUI opens before any
+  loading: HashSet<Platform>,
+  /// Set when every first listing came back empty, to close the picker
+  nothing_found: bool,
+  /// A restored selection, applied once its device is listed
+  pending_selection: Option<EntryKey>,
+  /// The device the confirmation prompt would shut down
+  pending_stop: Option<EntryKey>,
+  /// A running emulator the prompt would launch again, and how
+  pending_relaunch: Option<(EntryKey, emulators::LaunchOptions)>,
+  errors: Vec<(Platform, String)>,
+  last_refresh: Instant,
+  /// `None` when automatic refreshes are off
+  refresh_interval: Option<Duration>,
+  /// Android states are placeholders from a fast listing until the next refresh
+  states_unchecked: bool,
+  /// Rows highlighted since the given instant (state changes, user actions)
+  flashes: HashMap<EntryKey, Instant>,
+  /// Devices that appeared in a refresh, tagged "new" for `NEW_TAG_DURATION`
+  appeared: HashMap<EntryKey, Instant>,
+  /// Devices missing from the last refresh; they stay listed, struck
+  /// through, until the next refresh of their platform drops them
+  removed: HashSet<EntryKey>,
+  /// Open while the details pane lists the selected AVD's snapshots
+  snapshots: Option<SnapshotPane>,
+  /// Short notice on the details pane and when it was shown; recent
+  /// outcomes take its place (see `current_toast`)
+  toast: Option<(String, Instant)>,
+  /// Cap on listed devices (`max_devices`); `None` lists all
+  max_devices: Option<usize>,
+  /// Devices matching the filter, and how many of them the cap lets through
+  matched: usize,
+  shown: usize,
+  updates_tx: mpsc::Sender<PlatformUpdate>,
+  updates_rx: mpsc::Receiver<PlatformUpdate>,
+  /// Newer-release notice from the update check, shown as a toast
+  update_notice: Option<mpsc::Receiver<String>>,
+  /// Devices opened from this session, whose boot rings the bell
+  launched: HashSet<EntryKey>,
+  /// Devices opened from this session that aren't booted yet, with when
+  /// and how they were started, for the remaining-time estimate
+  booting: HashMap<EntryKey, (Instant, Option<history::BootMode>)>,
+  /// Boots started since none was running, for the "booting 2/5" progress
+  boot_batch: usize,
+  notify: config::BootNotify,
+  /// `--print`: Enter picks the selected device instead of opening it
+  pick_only: bool,
+  picked: Option<EntryKey>,
 }
 
-impl App {
-  fn new(entries: Vec<EmulatorEntry>) -> Self {
-    let filtered_indices: Vec<usize> = (0..entries.len()).collect();
-    let mut list_state = ListState::default();
-    // Select first non-header item
-    let first_selectable = filtered_indices
+impl App {
+  fn new(
+    sections: Vec<(Platform, Vec<EmulatorEntry>)>,
+    focus_mode: FilterFocusMode,
+    max_devices: Option<usize>,
+  ) -> Self {
+    let (updates_tx, updates_rx) = mpsc::channel();
+    let cfg = config::load_config();
+    let hidden_platforms = [
+      (Platform::Android, cfg.as_ref().and_then(|c| c.hide_android)),
+      (Platform::Ios, cfg.as_ref().and_then(|c| c.hide_ios)),
+    ]
+    .into_iter()
+    .filter(|&(_, hide)| hide == Some(true))
+    .map(|(platform, _)| platform)
+    .collect();
+    let history = history::load();
+    let last_launched = history::last_launched(&history);
+
+    let mut app = App {
+      sections,
+      entries: Vec::new(),
+      filtered_indices: Vec::new(),
+      views: Vec::new(),
+      list_state: ListState::default(),
+      list_rows: 0,
+      list_offset: 0,
+      filter: line_edit::LineEdit::default(),
+      focus_mode,
+      focus: Focus::List,
+      keymap: keys::KeyMap::default(),
+      help_open: false,
+      error_popup: None,
+      theme: theme::theme_index(cfg.as_ref().and_then(|c| c.theme.as_deref())),
+      theme_overrides: cfg.as_ref().and_then(|c| c.theme_overrides.clone()),
+      theme_picker: None,
+      outcomes: Vec::new(),
+      log: None,
+      started: Instant::now(),
+      history,
+      sort: view::SortMode::configured(),
+      last_launched,
+      hidden_platforms,
+      collapsed: view::configured_collapsed(),
+      icons: config::icons(),
+      recent_devices: config::recent_devices(),
+      wrap_navigation: config::wrap_navigation(),
+      refreshing: HashSet::new(),
+      loading: HashSet::new(),
+      nothing_found: false,
+      pending_selection: None,
+      pending_stop: None,
+      pending_relaunch: None,
+      errors: Vec::new(),
+      last_refresh: Instant::now(),
+      refresh_interval: Some(DEFAULT_REFRESH_INTERVAL),
+      states_unchecked: false,
+      flashes: HashMap::new(),
+      appeared: HashMap::new(),
+      removed: HashSet::new(),
+      snapshots: None,
+      toast: None,
+      max_devices,
+      matched: 0,
+      shown: 0,
+      updates_tx,
+      updates_rx,
+      update_notice: None,
+      launched: HashSet::new(),
+      booting: HashMap::new(),
+      boot_batch: 0,
+      notify: config::boot_notify(),
+      pick_only: false,
+      picked: None,
+    };
+    app.apply_filter();
+    app
+  }
+
+  fn apply_filter(&mut self) {
+    self.refilter();
+
+    // Select the first device, or a header when every section is collapsed
+    let first_device = self
+      .filtered_indices
+      .iter()
+      .position(|&i| !self.entries[i].is_header());
+    let first_row = (!self.filtered_indices.is_empty()).then_some(0);
+    self.list_state.select(first_device.or(first_row));
+  }
+
+  /// Rebuild `entries` and `filtered_indices` from the sections through the
+  /// view pipeline (see `view.rs` for the order of operations)
+  fn refilter(&mut self) {
+    let options = view::ViewOptions {
+      sort: self.sort,
+      last_launched: self.last_launched.clone(),
+      hidden_platforms: self.hidden_platforms.clone(),
+      collapsed: self.collapsed.clone(),
+      recent: self.recent_devices,
+      filter: self.filter.as_str().to_string(),
+      limit: self.max_devices,
+      ..Default::default()
+    };
+    let view = view::build(&self.sections, &options);
+    self.entries = view.entries;
+    self.views = present::views(&self.entries);
+    self.filtered_indices = view.visible;
+    self.matched = view.matched;
+    self.shown = view.shown;
+  }
+
+  /// List every platform for the first time, each on its own thread. With
+  /// `fast`, Android is listed from the AVD directory first and its real
+  /// states follow.
+  fn load(&mut self, fast: bool) {
+    for platform in self.listed_platforms() {
+      self.loading.insert(platform);
+      self.refreshing.insert(platform);
+      let tx = self.updates_tx.clone();
+      std::thread::spawn(move || {
+        if fast && platform == Platform::Android {
+          let result = list_traced(platform, true);
+          let _ = tx.send(PlatformUpdate {
+            platform,
+            result,
+            unchecked: true,
+          });
+        }
+        let result = list_traced(platform, false);
+        let _ = tx.send(PlatformUpdate {
+          platform,
+          result,
+          unchecked: false,
+        });
+      });
+    }
+    self.last_refresh = Instant::now();
+  }
+
+  /// Whether nothing is listed yet because the first listings are running
+  fn awaiting_first_list(&self) -> bool {
+    !self.loading.is_empty() && self.sections.iter().all(|(_, entries)| entries.is_empty())
+  }
+
+  /// Re-list every platform, each on its own thread
+  fn refresh_all(&mut self) {
+    for platform in self.listed_platforms() {
+      self.refresh(platform);
+    }
+    self.last_refresh = Instant::now();
+  }
+
+  /// Supported platforms whose section isn't hidden
+  fn listed_platforms(&self) -> Vec<Platform> {
+    Platform::supported()
+      .iter()
+      .copied()
+      .filter(|p| !self.hidden_platforms.contains(p))
+      .collect()
+  }
+
+  /// Hide or show a platform's section; a shown one is listed afresh
+  fn toggle_section(&mut self, platform: Platform) {
+    if !Platform::supported().contains(&platform) {
+      self.toast = Some((
+        format!("{} devices can't be listed here", platform.label()),
+        Instant::now(),
+      ));
+      return;
+    }
+    if !self.hidden_platforms.remove(&platform) {
+      self.hidden_platforms.insert(platform);
+    } else {
+      self.refresh(platform);
+    }
+    self.rebuild_entries();
+  }
+
+  /// Re-list one platform in the background unless it is already refreshing
+  fn refresh(&mut self, platform: Platform) {
+    if !self.refreshing.insert(platform) {
+      return;
+    }
+    let tx = self.updates_tx.clone();
+    std::thread::spawn(move || {
+      let result = list_traced(platform, false);
+      let _ = tx.send(PlatformUpdate {
+        platform,
+        result,
+        unchecked: false,
+      });
+    });
+  }
+
+  /// Apply every update that has arrived since the last frame
+  fn drain_updates(&mut self) {
+    while let Ok(update) = self.updates_rx.try_recv() {
+      self.apply_update(update);
+    }
+    if let Some(Ok(notice)) = self.update_notice.as_ref().map(|rx| rx.try_recv()) {
+      self.toast = Some((notice, Instant::now()));
+    }
+    self
+      .flashes
+      .retain(|_, since| since.elapsed() < FLASH_DURATION);
+    self
+      .appeared
+      .retain(|_, since| since.elapsed() < NEW_TAG_DURATION);
+    if self
+      .toast
+      .as_ref()
+      .is_some_and(|(_, since)| since.elapsed() >= TOAST_DURATION)
+    {
+      self.toast = None;
+    }
+  }
+
+  /// Record an action's result, to be shown as a toast and printed on exit
+  fn record(&mut self, key: EntryKey, action: OutcomeAction, result: Result<String, String>) {
+    if result.is_ok()
+      && matches!(
+        action,
+        OutcomeAction::Open | OutcomeAction::OpenCold | OutcomeAction::OpenSnapshot
+      )
+    {
+      self.launched.insert(key.clone());
+      let booted = self
+        .entries
+        .iter()
+        .any(|e| e.key().as_ref() == Some(&key) && e.state() == Some(emulators::STATE_BOOTED));
+      if !booted {
+        let mode = history::BootMode::of_launch(&key, action == OutcomeAction::OpenCold);
+        if self.booting.is_empty() {
+          self.boot_batch = 0;
+        }
+        self.boot_batch += 1;
+        self.booting.insert(key.clone(), (Instant::now(), mode));
+      }
+      let _ = metrics::record(metrics::MetricEvent::Launch { key: key.clone() });
+      if let Ok(record) = history::record_launch(&key, None) {
+        self.last_launched.insert(key.clone(), record.timestamp);
+        self.history.push(record);
+        self.rebuild_entries();
+      }
+    }
+    if result.is_ok() && action == OutcomeAction::Stop {
+      self.booting.remove(&key);
+      let _ = metrics::record(metrics::MetricEvent::Stop { key: key.clone() });
+    }
+    self.outcomes.push(ActionOutcome {
+      key,
+      action,
+      result,
+      at: Instant::now(),
+    });
+    // Follow the newest entry while the log is open
+    if let Some(log) = &mut self.log {
+      log.select(Some(log_window(&self.outcomes).len() - 1));
+    }
+  }
+
+  /// Record a launch and say whether the picker should close. A failure
+  /// keeps it open with the error in a popup. With `bell_on_boot` it stays
+  /// open after a successful launch too while there is a boot to ring for:
+  /// an emulator that isn't booted yet. `simctl boot` returns once the
+  /// simulator is up, so an iOS launch closes it as usual.
+  fn record_launch(
+    &mut self,
+    key: EntryKey,
+    action: OutcomeAction,
+    result: Result<String, String>,
+  ) -> bool {
+    if let Err(e) = &result {
+      let name = self
+        .entries
+        .iter()
+        .find(|entry| entry.key().as_ref() == Some(&key))
+        .map_or_else(|| key.to_string(), |entry| entry.display_name().to_string());
+      self.error_popup = Some((format!("{} {} failed", action.label(), name), e.clone()));
+    }
+    let launched = result.is_ok();
+    self.record(key.clone(), action, result);
+    let watching = self.notify.on_boot
+      && !self.pick_only
+      && matches!(key, EntryKey::Android(_))
+      && self.booting.contains_key(&key);
+    launched && !watching
+  }
+
+  /// Open the theme picker on the theme in use, or close it and go back to
+  /// that theme
+  fn toggle_themes(&mut self) {
+    if self.theme_picker.take().is_some() {
+      self.focus = Focus::List;
+      return;
+    }
+    let mut picker = ListState::default();
+    picker.select(Some(self.theme));
+    self.theme_picker = Some(picker);
+    self.focus = Focus::Themes;
+  }
+
+  fn move_theme_selection(&mut self, delta: i32) {
+    let Some(picker) = &mut self.theme_picker else {
+      return;
+    };
+    let last = theme::THEME_NAMES.len() - 1;
+    let current = picker.selected().unwrap_or(self.theme);
+    let next = match (delta > 0, current) {
+      (true, c) if c == last && self.wrap_navigation => 0,
+      (true, c) => (c + 1).min(last),
+      (false, 0) if self.wrap_navigation => last,
+      (false, c) => c.saturating_sub(1),
+    };
+    picker.select(Some(next));
+  }
+
+  /// Use the theme selected in the picker and close it, saving it to the
+  /// config file when `save` is set
+  fn apply_theme(&mut self, save: bool) {
+    let Some(picker) = self.theme_picker.take() else {
+      return;
+    };
+    self.focus = Focus::List;
+    self.theme = picker.selected().unwrap_or(self.theme);
+    let name = theme::THEME_NAMES[self.theme];
+    let message = if !save {
+      format!("Theme: {} (w in the picker saves it)", name)
+    } else {
+      match config::save_theme(name) {
+        Ok(path) => format!("Theme: {} (saved to {})", name, path.display()),
+        Err(e) => format!("Theme: {} (not saved: {})", name, e),
+      }
+    };
+    self.toast = Some((message, Instant::now()));
+  }
+
+  /// The theme to draw with: the picker's selection while it is open
+  fn shown_theme(&self) -> usize {
+    self
+      .theme_picker
+      .as_ref()
+      .and_then(ListState::selected)
+      .unwrap_or(self.theme)
+  }
+
+  /// Show the activity log in the details pane, or close it
+  fn toggle_log(&mut self) {
+    if self.log.take().is_some() {
+      self.focus = Focus::List;
+      return;
+    }
+    self.snapshots = None;
+    let mut log = ListState::default();
+    log.select(log_window(&self.outcomes).len().checked_sub(1));
+    self.log = Some(log);
+    self.focus = Focus::Log;
+  }
+
+  fn scroll_log(&mut self, delta: i32) {
+    let Some(log) = &mut self.log else {
+      return;
+    };
+    let Some(last) = log_window(&self.outcomes).len().checked_sub(1) else {
+      return;
+    };
+    let current = log.selected().unwrap_or(last);
+    let next = if delta > 0 {
+      (current + 1).min(last)
+    } else {
+      current.saturating_sub(1)
+    };
+    log.select(Some(next));
+  }
+
+  /// The newest toast still on screen: the last outcome or a notice
+  fn current_toast(&self) -> Option<String> {
+    let outcome = self
+      .outcomes
+      .last()
+      .filter(|o| o.at.elapsed() < TOAST_DURATION)
+      .map(|o| {
+        let text = match &o.result {
+          Ok(msg) => msg.clone(),
+          Err(e) => format!("Error: {}", e),
+        };
+        (text, o.at)
+      });
+    let notice = self.toast.clone();
+    match (outcome, notice) {
+      (Some(o), Some(n)) => Some(if o.1 >= n.1 { o.0 } else { n.0 }),
+      (o, n) => o.or(n).map(|(text, _)| text),
+    }
+  }
+
+  /// How long the selected device has been booting, and in which mode,
+  /// while a launch from this session hasn't finished
+  fn selected_boot(&self) -> Option<(Duration, Option<history::BootMode>)> {
+    let key = self.selected_entry()?.key()?;
+    let (since, mode) = self.booting.get(&key)?;
+    Some((since.elapsed(), *mode))
+  }
+
+  /// "booting 2/5…" while launches from this session are booting: the
+  /// first device of the batch not yet booted, and the batch size
+  fn boot_progress(&self) -> Option<String> {
+    if self.booting.is_empty() {
+      return None;
+    }
+    let done = self.boot_batch.saturating_sub(self.booting.len());
+    Some(format!(
+      "booting {}/{}\u{2026}",
+      done + 1,
+      self.boot_batch.max(done + 1)
+    ))
+  }
+
+  /// Highlight a row for `FLASH_DURATION`
+  fn flash(&mut self, key: EntryKey) {
+    self.flashes.insert(key, Instant::now());
+  }
+
+  /// Ring the bell, or run `notify_cmd`, when a device launched this session
+  /// has booted (or on any change with `bell_on_any_change`)
+  fn notify_change(&self, entry: &EmulatorEntry) {
+    let Some(key) = entry.key() else {
+      return;
+    };
+    let launched_booted =
+      entry.state() == Some(emulators::STATE_BOOTED) && self.launched.contains(&key);
+    if !(self.notify.on_any_change || self.notify.on_boot && launched_booted) {
+      return;
+    }
+    match &self.notify.command {
+      Some(command) => {
+        let child = runner::spawn(
+          std::process::Command::new(command)
+            .arg(entry.display_name())
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null()),
+        );
+        // Reap it without holding up the UI
+        if let Ok(mut child) = child {
+          std::thread::spawn(move || child.wait());
+        }
+      }
+      // BEL moves nothing on screen, so it can go straight to the terminal
+      // between frames
+      None => {
+        let mut out = tui_output(self.pick_only);
+        let _ = out.write_all(b"\x07");
+        let _ = out.flush();
+      }
+    }
+  }
+
+  /// Patch one platform's section, leaving the others untouched
+  fn apply_update(&mut self, update: PlatformUpdate) {
+    if !update.unchecked {
+      self.refreshing.remove(&update.platform);
+    }
+    let first = self.loading.remove(&update.platform);
+    // A hidden platform was never listed, so its devices may exist
+    if first
+      && self.hidden_platforms.is_empty()
+      && self.loading.is_empty()
+      && update.result.as_ref().map_or(true, Vec::is_empty)
+      && self.sections.iter().all(|(_, entries)| entries.is_empty())
+    {
+      self.nothing_found = true;
+      return;
+    }
+    self.errors.retain(|(p, _)| *p != update.platform);
+
+    let Some(section) = self
+      .sections
+      .iter_mut()
+      .find(|(p, _)| *p == update.platform)
+    else {
+      return;
+    };
+
+    match update.result {
+      Ok(entries) => {
+        // Placeholder states from a fast listing are not real transitions
+        let changed: Vec<EmulatorEntry> = entries
+          .iter()
+          .filter(|new| {
+            section.1.iter().any(|old| {
+              old.key() == new.key()
+                && old.state() != new.state()
+                && old.state() != Some(emulators::STATE_UNKNOWN)
+            })
+          })
+          .cloned()
+          .collect();
+        let new_keys: HashSet<EntryKey> = entries.iter().filter_map(EmulatorEntry::key).collect();
+        // With the same devices as before, only states changed: keep the
+        // rows where they are instead of re-sorting booted ones to the top.
+        // Placeholder states from a fast listing are still sorted for real.
+        let mut entries = entries;
+        let real_states = update.platform != Platform::Android || !self.states_unchecked;
+        let old_keys: Vec<EntryKey> = section.1.iter().filter_map(EmulatorEntry::key).collect();
+        if real_states
+          && old_keys.len() == new_keys.len()
+          && old_keys.iter().all(|key| new_keys.contains(key))
+        {
+          entries.sort_by_key(|e| e.key().and_then(|k| old_keys.iter().position(|o| *o == k)));
+        }
+        // A first successful listing isn't a batch of new devices
+        if !section.1.is_empty() {
+          for key in &new_keys {
+            let listed = section.1.iter().any(|old| old.key().as_ref() == Some(key));
+            if !listed {
+              self.appeared.insert(key.clone(), Instant::now());
+            }
+          }
+        }
+        // Devices gone since the last refresh stay one more cycle, marked
+        // removed; ones already marked are dropped now
+        for old in std::mem::take(&mut section.1) {
+          let Some(key) = old.key() else {
+            continue;
+          };
+          if new_keys.contains(&key) || self.removed.remove(&key) {
+            continue;
+          }
+          self.removed.insert(key);
+          entries.push(old);
+        }
+        self.removed.retain(|key| !new_keys.contains(key));
+        for entry in &entries {
+          if entry.state() == Some(emulators::STATE_BOOTED) {
+            if let Some(key) = entry.key() {
+              self.booting.remove(&key);
+            }
+          }
+        }
+        section.1 = entries;
+        for entry in &changed {
+          self.notify_change(entry);
+          if let Some(key) = entry.key() {
+            self.flash(key);
+          }
+        }
+        if update.platform == Platform::Android {
+          self.states_unchecked = update.unchecked;
+        }
+        self.rebuild_entries();
+      }
+      // A platform that never listed anything failing again isn't news
+      Err(e) if !section.1.is_empty() => {
+        let first_line = e.lines().next().unwrap_or_default().to_string();
+        self.errors.push((update.platform, first_line));
+      }
+      Err(_) => {}
+    }
+  }
+
+  /// What `restore_session` saves on exit
+  fn session(&self) -> session::SessionState {
+    let mut hidden: Vec<String> = self
+      .hidden_platforms
+      .iter()
+      .filter_map(|p| view::section_name(p.section_title()))
+      .map(str::to_string)
+      .collect();
+    let mut collapsed: Vec<String> = self
+      .collapsed
+      .iter()
+      .filter_map(|title| view::section_name(title))
+      .map(str::to_string)
+      .collect();
+    // Sets iterate in any order; keep the file stable between runs
+    hidden.sort();
+    collapsed.sort();
+    session::SessionState {
+      sort: Some(self.sort.name().to_string()),
+      hidden,
+      collapsed,
+      ..session::SessionState::new(
+        self.filter.as_str().to_string(),
+        self.selected_entry().and_then(EmulatorEntry::key),
+      )
+    }
+  }
+
+  /// Apply a saved session before the first listing. A selected device
+  /// that no longer matches keeps the default selection.
+  fn restore(&mut self, state: session::SessionState) {
+    self.filter = line_edit::LineEdit::new(state.filter);
+    self.apply_filter();
+    self.pending_selection = state.selected;
+    if let Some(sort) = state.sort.as_deref().and_then(view::SortMode::from_name) {
+      self.sort = sort;
+    }
+    self.hidden_platforms = state
+      .hidden
+      .iter()
+      .filter_map(|name| view::section_title(name))
+      .filter_map(Platform::from_section_title)
+      .collect();
+    self.collapsed = state
+      .collapsed
       .iter()
-      .position(|&i| !entries[i].is_header());
-    list_state.select(first_selectable);
+      .filter_map(|name| view::section_title(name))
+      .map(str::to_string)
+      .collect();
+  }
 
-    App {
-      entries,
-      filtered_indices,
-      list_state,
-      filter: String::new(),
-      result_message: None,
-    }
+  /// Switch to the next sort order, keeping the selected device
+  fn cycle_sort(&mut self) {
+    self.sort = self.sort.next(!self.last_launched.is_empty());
+    self.rebuild_entries();
   }
 
-  fn apply_filter(&mut self) {
-    let query = self.filter.to_lowercase();
-    self.filtered_indices = (0..self.entries.len())
-      .filter(|&i| {
-        let entry = &self.entries[i];
-        if entry.is_header() {
-          // Keep headers if any child in their section matches
-          return self.section_has_match(i, &query);
-        }
-        query.is_empty() || entry.display_name().to_lowercase().contains(&query)
+  /// Title of the selected row's section (the row itself for a header)
+  fn selected_section(&self) -> Option<String> {
+    let selected = self.list_state.selected()?;
+    self
+      .filtered_indices
+      .get(..=selected)?
+      .iter()
+      .rev()
+      .find_map(|&i| match &self.entries[i] {
+        EmulatorEntry::SectionHeader(title) => Some(title.clone()),
+        _ => None,
       })
-      .collect();
+  }
+
+  fn header_selected(&self) -> bool {
+    self
+      .list_state
+      .selected()
+      .and_then(|pos| self.filtered_indices.get(pos))
+      .is_some_and(|&i| self.entries[i].is_header())
+  }
 
-    // Select first non-header item
-    let first_selectable = self
+  /// Position of the header with the given title in `filtered_indices`
+  fn header_position(&self, title: &str) -> Option<usize> {
+    self
       .filtered_indices
       .iter()
-      .position(|&i| !self.entries[i].is_header());
-    self.list_state.select(first_selectable);
+      .position(|&i| matches!(&self.entries[i], EmulatorEntry::SectionHeader(t) if t == title))
   }
 
-  fn section_has_match(&self, header_idx: usize, query: &str) -> bool {
-    if query.is_empty() {
-      return true;
+  /// Collapse or expand the selected row's section (`None` toggles), and
+  /// select its header
+  fn set_collapsed(&mut self, collapse: Option<bool>) {
+    let Some(title) = self.selected_section() else {
+      return;
+    };
+    let collapse = collapse.unwrap_or(!self.collapsed.contains(&title));
+    if collapse {
+      self.collapsed.insert(title.clone());
+    } else {
+      self.collapsed.remove(&title);
     }
-    for i in (header_idx + 1)..self.entries.len() {
-      if self.entries[i].is_header() {
-        break;
-      }
-      if self.entries[i]
-        .display_name()
-        .to_lowercase()
-        .contains(query)
-      {
-        return true;
-      }
+    self.refilter();
+    self.list_state.select(self.header_position(&title));
+  }
+
+  /// Rebuild the flat list from the sections, keeping the same device (or
+  /// header) selected
+  fn rebuild_entries(&mut self) {
+    // A restored selection wins until its device is listed or loading ends
+    let selected = self
+      .pending_selection
+      .clone()
+      .or_else(|| self.selected_entry().and_then(EmulatorEntry::key));
+    let selected_pos = self.list_state.selected();
+    let selected_header = self
+      .header_selected()
+      .then(|| self.selected_section())
+      .flatten();
+
+    self.refilter();
+
+    // A device listed twice (also under Recent) keeps the row nearest the old one
+    let same_device = selected.and_then(|key| {
+      self
+        .filtered_indices
+        .iter()
+        .enumerate()
+        .filter(|(_, &i)| self.entries[i].key().as_ref() == Some(&key))
+        .map(|(pos, _)| pos)
+        .min_by_key(|&pos| pos.abs_diff(selected_pos.unwrap_or(0)))
+    });
+    if same_device.is_some() || self.loading.is_empty() {
+      self.pending_selection = None;
     }
-    false
+    let same_header = || self.header_position(selected_header.as_deref()?);
+    let fallback = || {
+      let last = self.filtered_indices.len().checked_sub(1)?;
+      let pos = selected_pos.unwrap_or(0).min(last);
+      // Step back over a header at the clamped position, unless there are
+      // only headers
+      (0..=pos)
+        .rev()
+        .chain(pos + 1..=last)
+        .find(|&p| !self.entries[self.filtered_indices[p]].is_header())
+        .or(Some(pos))
+    };
+    let new_selection = same_device.or_else(same_header).or_else(fallback);
+    self.list_state.select(new_selection);
   }
 
+  /// Move one row; headers are selectable, to collapse their section
   fn move_selection(&mut self, delta: i32) {
-    let selectable: Vec<usize> = self
-      .filtered_indices
-      .iter()
-      .enumerate()
-      .filter(|(_, &i)| !self.entries[i].is_header())
-      .map(|(pos, _)| pos)
-      .collect();
-
-    if selectable.is_empty() {
+    let Some(last) = self.filtered_indices.len().checked_sub(1) else {
       self.list_state.select(None);
       return;
+    };
+    let current = self.list_state.selected().unwrap_or(0).min(last);
+    let next = match (delta > 0, self.wrap_navigation) {
+      (true, true) if current == last => 0,
+      (false, true) if current == 0 => last,
+      (true, _) => (current + 1).min(last),
+      (false, _) => current.saturating_sub(1),
+    };
+    self.list_state.select(Some(next));
+  }
+
+  /// The offset that shows the selection in `rows` rows, moving
+  /// `list_offset` as little as possible
+  fn offset_for(&self, offset: usize, rows: usize) -> usize {
+    let offset = offset.min(self.filtered_indices.len().saturating_sub(rows));
+    match self.list_state.selected() {
+      Some(selected) if selected < offset => selected,
+      Some(selected) if rows > 0 && selected >= offset + rows => selected + 1 - rows,
+      _ => offset,
+    }
+  }
+
+  /// Position of the section header above row `offset`, if that row is a
+  /// device whose header has scrolled out of view
+  fn header_above(&self, offset: usize) -> Option<usize> {
+    let header = (0..=offset.min(self.filtered_indices.len().checked_sub(1)?))
+      .rev()
+      .find(|&pos| self.entries[self.filtered_indices[pos]].is_header())?;
+    (header < offset).then_some(header)
+  }
+
+  /// Scroll the list for a viewport of `height` rows. Returns the header
+  /// to pin on the top row, above a list one row shorter, when the top
+  /// device's section header is out of view.
+  fn scroll_list(&mut self, height: usize) -> Option<usize> {
+    let mut offset = self.offset_for(self.list_offset, height);
+    let mut sticky = None;
+    if height > 1 && self.header_above(offset).is_some() {
+      let shorter = self.offset_for(offset, height - 1);
+      // Scrolling down for the shorter list can bring a header to the top
+      if let Some(header) = self.header_above(shorter) {
+        offset = shorter;
+        sticky = Some(header);
+      }
     }
+    self.list_offset = offset;
+    *self.list_state.offset_mut() = offset;
+    self.list_rows = height;
+    sticky
+  }
+
+  /// Select the device at row `pos` of `filtered_indices`, or the nearest
+  /// one when that row is a header or past the end; ties go `forward`.
+  /// With every section collapsed, the nearest header is selected.
+  fn select_near(&mut self, pos: usize, forward: bool) {
+    let Some(last) = self.filtered_indices.len().checked_sub(1) else {
+      return;
+    };
+    let pos = pos.min(last);
+    let nearest = (0..=last).min_by_key(|&p| {
+      (
+        self.entries[self.filtered_indices[p]].is_header(),
+        p.abs_diff(pos),
+        (p < pos) == forward,
+      )
+    });
+    self.list_state.select(nearest);
+  }
 
+  /// Move the selection `rows` rows without wrapping
+  fn jump_selection(&mut self, rows: isize) {
     let current = self.list_state.selected().unwrap_or(0);
-    let current_pos = selectable.iter().position(|&p| p == current).unwrap_or(0);
-    let new_pos = if delta > 0 {
-      (current_pos + 1).min(selectable.len() - 1)
+    self.select_near(current.saturating_add_signed(rows), rows > 0);
+  }
+
+  /// Open the snapshot list for the selected Android entry, or close it
+  fn toggle_snapshots(&mut self) {
+    if self.snapshots.take().is_some() {
+      self.focus = Focus::List;
+      return;
+    }
+    self.log = None;
+    // Snapshots are read lazily, only when the pane opens
+    if let Some(EmulatorEntry::Android(e)) = self.selected_entry() {
+      let pane = SnapshotPane::load(e.id.clone(), e.name.clone(), e.serial.clone(), 0);
+      self.snapshots = Some(pane);
+      self.focus = Focus::Snapshots;
+    }
+  }
+
+  /// Delete the selected snapshot after the user confirmed it
+  fn delete_selected_snapshot(&mut self) {
+    self.focus = Focus::Snapshots;
+    let Some(pane) = &self.snapshots else {
+      return;
+    };
+    let Some(snapshot) = pane.selected() else {
+      return;
+    };
+    let result = snapshots::delete(&pane.avd_id, &snapshot.name, pane.serial.as_deref());
+    let mut reloaded = SnapshotPane::load(
+      pane.avd_id.clone(),
+      pane.avd_name.clone(),
+      pane.serial.clone(),
+      pane.list_state.selected().unwrap_or(0),
+    );
+    reloaded.message = Some(match &result {
+      Ok(msg) => msg.clone(),
+      Err(e) => format!("Error: {}", e),
+    });
+    let key = EntryKey::Android(reloaded.avd_id.clone());
+    self.snapshots = Some(reloaded);
+    self.record(key, OutcomeAction::DeleteSnapshot, result);
+  }
+
+  /// Ask before shutting down the selected device; a device that isn't
+  /// running only gets a note
+  fn ask_stop_selected(&mut self) {
+    let Some(entry) = self.selected_entry() else {
+      return;
+    };
+    if matches!(
+      entry.state(),
+      Some(emulators::STATE_SHUTDOWN | emulators::STATE_BROKEN)
+    ) {
+      self.toast = Some((
+        format!("{} is not running", entry.display_name()),
+        Instant::now(),
+      ));
+      return;
+    }
+    self.pending_stop = entry.key();
+    self.focus = Focus::Confirm;
+  }
+
+  /// Launch a device from the list. A booted emulator isn't launched again
+  /// without asking, since a second instance fails or duplicates its window.
+  /// Returns whether the picker should close.
+  fn launch(&mut self, key: EntryKey, options: emulators::LaunchOptions) -> bool {
+    let booted = self
+      .selected_entry()
+      .is_some_and(|e| e.state() == Some(emulators::STATE_BOOTED));
+    if booted && matches!(key, EntryKey::Android(_)) {
+      self.pending_relaunch = Some((key, options));
+      self.focus = Focus::Confirm;
+      return false;
+    }
+    self.launch_confirmed(key, options)
+  }
+
+  fn launch_confirmed(&mut self, key: EntryKey, options: emulators::LaunchOptions) -> bool {
+    self.focus = Focus::List;
+    let result = emulators::open_key(&key, &options);
+    let outcome = if options.cold_boot {
+      OutcomeAction::OpenCold
     } else {
-      current_pos.saturating_sub(1)
+      OutcomeAction::Open
     };
-    self.list_state.select(Some(selectable[new_pos]));
+    self.record_launch(key, outcome, result)
+  }
+
+  /// Shut down the device the user confirmed, and show it stopped in place
+  /// rather than waiting for the next refresh
+  fn stop_confirmed(&mut self, key: EntryKey) {
+    self.focus = Focus::List;
+    let result = emulators::stop_device(&key);
+    if result.is_ok() {
+      for (_, entries) in &mut self.sections {
+        for entry in entries.iter_mut() {
+          if entry.key().as_ref() == Some(&key) {
+            entry.mark_shutdown();
+          }
+        }
+      }
+      self.rebuild_entries();
+    }
+    self.record(key, OutcomeAction::Stop, result);
   }
 
   fn selected_entry(&self) -> Option<&EmulatorEntry> {
@@ -178,174 +2287,1324 @@ impl App {
   }
 }
 
-fn state_color(state: &str, theme: &theme::ThemeColors) -> ratatui::style::Color {
-  match state {
-    emulators::STATE_BOOTED => theme.state_booted_fg,
-    emulators::STATE_SHUTDOWN => theme.state_shutdown_fg,
-    _ => theme.state_unknown_fg,
+/// A device row in the list, with an optional tag such as "new" at the end
+fn device_line<'a>(
+  view: &'a present::DeviceView,
+  tag: Option<&'static str>,
+  columns: &present::Columns,
+  theme: &theme::ThemeColors,
+) -> Line<'a> {
+  let mut spans = vec![Span::raw(DEVICE_INDENT)];
+  spans.extend(view.parts(columns).into_iter().map(|(part, text)| {
+    let color = match part {
+      None => return Span::raw(text),
+      Some(present::Part::Platform(Platform::Android)) => theme.icon_android_fg,
+      Some(present::Part::Platform(Platform::Ios)) => theme.icon_ios_fg,
+      Some(present::Part::Name) => theme.name_fg,
+      Some(present::Part::State(kind)) => theme::state_color(kind, theme),
+      Some(present::Part::Disambiguator | present::Part::Meta) => theme.meta_fg,
+    };
+    Span::styled(text, Style::default().fg(color))
+  }));
+  if let Some(tag) = tag {
+    spans.push(Span::styled(
+      format!("  {}", tag),
+      Style::default()
+        .fg(theme.help_key_fg)
+        .add_modifier(Modifier::BOLD),
+    ));
+  }
+  Line::from(spans)
+}
+
+/// `columns` with the name column capped and narrowed so rows fit in
+/// `width`. Long names give way so the state stays on screen; past the
+/// minimum, the meta is what gets clipped.
+fn fit_columns(columns: present::Columns, width: usize) -> present::Columns {
+  let columns = columns.capped();
+  let others = columns.width() - columns.name + DEVICE_INDENT.len();
+  present::Columns {
+    name: columns
+      .name
+      .min(width.saturating_sub(others).max(MIN_NAME_WIDTH)),
+    ..columns
+  }
+}
+
+/// `net-info` as aligned `Label: value` lines, skipping what isn't known
+fn format_net_info(info: &emulators::NetInfo) -> String {
+  let not_booted = (info.state != emulators::STATE_BOOTED).then(|| match info.platform {
+    "android" => format!("{} (boot it for the serial, ports and IP)", info.state),
+    _ => info.state.clone(),
+  });
+  let rows = [
+    ("State", not_booted),
+    ("Serial", info.serial.clone()),
+    ("Console port", info.console_port.map(|p| p.to_string())),
+    ("adb port", info.adb_port.map(|p| p.to_string())),
+    ("Host alias", info.host_alias.map(str::to_string)),
+    ("wlan0 IP", info.wlan_ip.clone()),
+    ("Data path", info.data_path.clone()),
+  ];
+  let mut output = String::new();
+  for (label, value) in rows {
+    if let Some(value) = value {
+      output.push_str(&format!("{:<14}{}\n", format!("{}:", label), value));
+    }
+  }
+  output.push_str(info.note);
+  output.push('\n');
+  output
+}
+
+/// Lines shown in the details pane for the selected entry. `booting` is
+/// how long, and in which mode, it has been booting since it was opened.
+fn details_lines<'a>(
+  entry: Option<&'a EmulatorEntry>,
+  history: &[history::HistoryRecord],
+  booting: Option<(Duration, Option<history::BootMode>)>,
+  theme: &theme::ThemeColors,
+) -> Vec<Line<'a>> {
+  let label = |s: &'static str| Span::styled(s, Style::default().fg(theme.meta_fg));
+  let mut lines = match entry {
+    Some(EmulatorEntry::Android(e)) => {
+      let mut lines = vec![Line::from(vec![
+        label(" ID: "),
+        Span::raw(e.id.as_str()),
+        label("  Type: "),
+        Span::raw(e.device_type.as_str()),
+      ])];
+      if let Some(serial) = &e.serial {
+        let mut spans = vec![label(" Serial: "), Span::raw(serial.as_str())];
+        if let Some(port) = console::console_port(serial) {
+          spans.push(label("  Console: "));
+          spans.push(Span::raw(format!("localhost:{}", port)));
+        }
+        lines.push(Line::from(spans));
+      } else {
+        // One of these may be this AVD, running but never identified
+        let unresponsive = emulators::unresponsive_serials();
+        if !unresponsive.is_empty() {
+          lines.push(Line::from(vec![
+            label(" Unidentified: "),
+            Span::raw(unresponsive.join(", ")),
+            label(" (avd name timed out; skipped this session)"),
+          ]));
+        }
+      }
+      // The wlan address needs an adb call; `net-info` has it
+      let mut network = vec![
+        label(" Network: "),
+        label("host is "),
+        Span::raw(emulators::ANDROID_HOST_ALIAS),
+      ];
+      let adb_port = e
+        .serial
+        .as_deref()
+        .and_then(console::console_port)
+        .and_then(|p| p.checked_add(1));
+      if let Some(port) = adb_port {
+        network.push(label("  adb: "));
+        network.push(Span::raw(format!("localhost:{}", port)));
+      }
+      lines.push(Line::from(network));
+      lines
+    }
+    Some(EmulatorEntry::IOS(s)) => {
+      let mut lines = vec![
+        Line::from(vec![label(" UDID: "), Span::raw(s.udid.as_str())]),
+        Line::from(vec![
+          label(" Network: "),
+          Span::raw("shares the host's"),
+          label(" (localhost is this Mac)"),
+        ]),
+      ];
+      if !s.paired.is_empty() {
+        let mut spans = vec![label(" \u{21c4} Paired: ")];
+        for (i, p) in s.paired.iter().enumerate() {
+          if i > 0 {
+            spans.push(Span::raw(", "));
+          }
+          spans.push(Span::styled(
+            p.name.as_str(),
+            Style::default().fg(theme.name_fg),
+          ));
+          spans.push(Span::styled(
+            format!(" [{}]", p.state),
+            Style::default().fg(theme::state_color(present::StateKind::of(&p.state), theme)),
+          ));
+        }
+        lines.push(Line::from(spans));
+      }
+      lines
+    }
+    _ => Vec::new(),
+  };
+
+  if let Some(Err(reason)) = entry
+    .filter(|e| !e.is_header())
+    .map(EmulatorEntry::launchable)
+  {
+    lines.push(Line::from(vec![
+      label(" Can't open: "),
+      Span::styled(reason, Style::default().fg(theme.state_shutdown_fg)),
+    ]));
+  }
+
+  if let (Some(first), Some(key)) = (lines.first_mut(), entry.and_then(EmulatorEntry::key)) {
+    match booting {
+      Some((elapsed, mode)) => {
+        let estimate = history::typical_boot(history, &key, mode);
+        first.push_span(label("  Booting… "));
+        first.push_span(Span::raw(match estimate {
+          Some(typical) if typical > elapsed => {
+            format!("~{}s remaining", (typical - elapsed).as_secs().max(1))
+          }
+          Some(typical) => format!("{}s (usually ~{}s)", elapsed.as_secs(), typical.as_secs()),
+          None => format!("{}s", elapsed.as_secs()),
+        }));
+      }
+      None => {
+        let quick = history::BootMode::of_launch(&key, false);
+        if let Some(boot) = history::typical_boot(history, &key, quick) {
+          first.push_span(label("  Typical boot: "));
+          first.push_span(Span::raw(format!("~{}s", boot.as_secs())));
+        }
+        let cold = history::typical_boot(history, &key, Some(history::BootMode::Cold));
+        if let Some(cold) = cold.filter(|_| quick.is_some()) {
+          first.push_span(label("  Cold: "));
+          first.push_span(Span::raw(format!("~{}s", cold.as_secs())));
+        }
+      }
+    }
+  }
+  lines
+}
+
+/// Help bar contents for the current focus
+fn help_spans<'a>(app: &App, theme: &theme::ThemeColors) -> Vec<Span<'a>> {
+  let keymap = &app.keymap;
+  let label = |action: Action| keymap.label(action, 2);
+  // "j/k" from the first key of each direction
+  let navigate = keymap
+    .label(Action::MoveDown, 1)
+    .zip(keymap.label(Action::MoveUp, 1))
+    .map(|(down, up)| format!("{}/{}", down, up));
+  let bindings: Vec<(Option<String>, &str)> = match (app.focus_mode, app.focus) {
+    (_, Focus::Snapshots) => vec![
+      (navigate, "navigate"),
+      (label(Action::Open), "boot from snapshot"),
+      (label(Action::DeleteSnapshot), "delete"),
+      (label(Action::ToggleSnapshots), "close"),
+    ],
+    (_, Focus::Confirm) if app.pending_relaunch.is_some() => vec![
+      (Some("y".to_string()), "launch anyway"),
+      (Some("f".to_string()), "focus"),
+      (Some("any other key".to_string()), "cancel"),
+    ],
+    (_, Focus::Confirm) => vec![
+      (Some("y".to_string()), "confirm"),
+      (Some("any other key".to_string()), "cancel"),
+    ],
+    (_, Focus::Log) => vec![(navigate, "scroll"), (label(Action::ToggleLog), "close")],
+    (_, Focus::Themes) => vec![
+      (navigate, "preview"),
+      (label(Action::Open), "use"),
+      (Some("w".to_string()), "use and save"),
+      (label(Action::ToggleThemes), "cancel"),
+    ],
+    (mode, Focus::Filter) if mode.is_modal() => vec![
+      (Some("Enter/Esc".to_string()), "done"),
+      (Some("Backspace".to_string()), "delete"),
+      (navigate, "navigate"),
+    ],
+    (mode, _) => {
+      let open = match app.selected_section() {
+        Some(title) if app.header_selected() && app.collapsed.contains(&title) => "expand",
+        Some(_) if app.header_selected() => "collapse",
+        _ => "open",
+      };
+      let mut bindings = vec![(navigate, "navigate"), (label(Action::Open), open)];
+      if mode.is_modal() {
+        bindings.push((keymap.label(Action::FocusFilter, 1), "filter"));
+      }
+      bindings.extend([
+        (label(Action::ToggleSnapshots), "snapshots"),
+        (keymap.label(Action::OpenPaired, 1), "open paired"),
+        (keymap.label(Action::Refresh, 1), "refresh"),
+        (label(Action::Quit), "quit"),
+      ]);
+      bindings
+    }
+  };
+  // Actions left without keys drop out
+  let bindings: Vec<(String, &str)> = bindings
+    .into_iter()
+    .filter_map(|(key, label)| Some((key?, label)))
+    .collect();
+
+  let mut spans = vec![Span::raw(" ")];
+  for (i, (key, label)) in bindings.iter().enumerate() {
+    let sep = if i + 1 < bindings.len() { "  " } else { "" };
+    spans.push(Span::styled(
+      key.clone(),
+      Style::default().fg(theme.help_key_fg),
+    ));
+    spans.push(Span::styled(
+      format!(" {}{}", label, sep),
+      Style::default().fg(theme.help_text_fg),
+    ));
+  }
+  spans
+}
+
+/// The popup listing every binding, centered over the picker and shrunk to
+/// fit small terminals
+fn render_help_popup(
+  frame: &mut ratatui::Frame,
+  keymap: &keys::KeyMap,
+  theme: &theme::ThemeColors,
+) {
+  let rows = keymap.describe();
+  let key_width = rows
+    .iter()
+    .map(|(keys, _)| text::display_width(keys))
+    .max()
+    .unwrap_or(0);
+  let lines: Vec<Line> = rows
+    .iter()
+    .map(|(keys, description)| {
+      Line::from(vec![
+        Span::styled(
+          format!(
+            " {}{}  ",
+            keys,
+            " ".repeat(key_width - text::display_width(keys))
+          ),
+          Style::default().fg(theme.help_key_fg),
+        ),
+        Span::styled(*description, Style::default().fg(theme.help_text_fg)),
+      ])
+    })
+    .collect();
+  let content_width = lines.iter().map(Line::width).max().unwrap_or(0) + 1;
+
+  let area = frame.area();
+  let width = (content_width as u16 + 2).min(area.width);
+  let height = (lines.len() as u16 + 2).min(area.height);
+  let popup = ratatui::layout::Rect {
+    x: area.x + (area.width - width) / 2,
+    y: area.y + (area.height - height) / 2,
+    width,
+    height,
+  };
+  let block = Block::default()
+    .borders(Borders::ALL)
+    .border_style(Style::default().fg(theme.help_key_fg))
+    .title(" Keys ")
+    .title_bottom(Span::styled(
+      " any key closes ",
+      Style::default().fg(theme.meta_fg),
+    ));
+  frame.render_widget(Clear, popup);
+  frame.render_widget(Paragraph::new(lines).block(block), popup);
+}
+
+/// The theme picker, centered over the list and drawn in the theme it
+/// previews
+fn render_theme_picker(
+  frame: &mut ratatui::Frame,
+  picker: &mut ListState,
+  in_use: usize,
+  theme: &theme::ThemeColors,
+) {
+  let items: Vec<ListItem> = theme::THEME_NAMES
+    .iter()
+    .enumerate()
+    .map(|(i, name)| {
+      let mut spans = vec![Span::styled(
+        format!(" {}", name),
+        Style::default().fg(theme.name_fg),
+      )];
+      if i == in_use {
+        spans.push(Span::styled(
+          "  (in use)",
+          Style::default().fg(theme.meta_fg),
+        ));
+      }
+      ListItem::new(Line::from(spans))
+    })
+    .collect();
+  let hint = " Enter use \u{b7} w save \u{b7} Esc cancel ";
+  let content_width = items
+    .iter()
+    .map(ListItem::width)
+    .chain([text::display_width(hint)])
+    .max()
+    .unwrap_or(0)
+    + 1;
+
+  let area = frame.area();
+  let width = (content_width as u16 + 2).min(area.width);
+  let height = (items.len() as u16 + 2).min(area.height);
+  let popup = ratatui::layout::Rect {
+    x: area.x + (area.width - width) / 2,
+    y: area.y + (area.height - height) / 2,
+    width,
+    height,
+  };
+  let block = Block::default()
+    .borders(Borders::ALL)
+    .border_style(Style::default().fg(theme.help_key_fg))
+    .title(" Theme ")
+    .title_bottom(Span::styled(hint, Style::default().fg(theme.meta_fg)));
+  let list = List::new(items).block(block).highlight_style(
+    Style::default()
+      .bg(theme.selection_bg)
+      .add_modifier(Modifier::BOLD),
+  );
+  frame.render_widget(Clear, popup);
+  frame.render_stateful_widget(list, popup, picker);
+}
+
+// Widest the error popup gets, borders included
+const ERROR_POPUP_WIDTH: u16 = 72;
+
+/// A failed action's error, wrapped in a popup over the picker
+fn render_error_popup(
+  frame: &mut ratatui::Frame,
+  title: &str,
+  message: &str,
+  theme: &theme::ThemeColors,
+) {
+  let area = frame.area();
+  let width = ERROR_POPUP_WIDTH.min(area.width);
+  let inner = usize::from(width.saturating_sub(2));
+  // `CommandNotFoundError`s span several lines before any wrapping
+  let rows: usize = message
+    .lines()
+    .map(|line| text::wrapped_rows(line, inner))
+    .sum();
+  let height = (rows as u16 + 2).min(area.height);
+  let popup = ratatui::layout::Rect {
+    x: area.x + (area.width - width) / 2,
+    y: area.y + (area.height - height) / 2,
+    width,
+    height,
+  };
+  let block = Block::default()
+    .borders(Borders::ALL)
+    .border_style(Style::default().fg(theme.state_shutdown_fg))
+    .title(Span::styled(
+      format!(" {} ", title),
+      Style::default()
+        .fg(theme.state_shutdown_fg)
+        .add_modifier(Modifier::BOLD),
+    ))
+    .title_bottom(Span::styled(
+      " Esc/Enter closes ",
+      Style::default().fg(theme.meta_fg),
+    ));
+  let body = Paragraph::new(message)
+    .style(Style::default().fg(theme.help_text_fg))
+    .wrap(Wrap { trim: false })
+    .block(block);
+  frame.render_widget(Clear, popup);
+  frame.render_widget(body, popup);
+}
+
+/// The picker's terminal: stdout, or stderr with `--print` so that stdout
+/// only ever carries the selection
+type TuiTerminal = Terminal<ratatui::backend::CrosstermBackend<Box<dyn Write>>>;
+
+fn tui_output(print: bool) -> Box<dyn Write> {
+  if print {
+    Box::new(io::stderr())
+  } else {
+    Box::new(io::stdout())
   }
 }
 
-fn run_tui() -> io::Result<()> {
-  let entries = emulators::collect_all_entries();
-  if entries.is_empty() {
-    println!("No emulators or simulators found.");
-    return Ok(());
+/// Put the terminal back: leave the alternate screen, or clear the inline
+/// viewport so what is printed next starts where the picker was
+fn restore_terminal(terminal: &mut TuiTerminal, print: bool, inline: bool) -> io::Result<()> {
+  if inline {
+    terminal.clear()?;
+  }
+  disable_raw_mode()?;
+  if !inline {
+    tui_output(print).execute(LeaveAlternateScreen)?;
+  }
+  // Later panics have no terminal to restore; give back the hook that
+  // set_panic_hook wrapped
+  if let Some(previous) = PREVIOUS_PANIC_HOOK
+    .lock()
+    .unwrap_or_else(|e| e.into_inner())
+    .take()
+  {
+    std::panic::set_hook(Box::new(move |info| previous(info)));
   }
+  terminal.show_cursor()
+}
+
+type PanicHook = dyn Fn(&std::panic::PanicHookInfo<'_>) + Send + Sync + 'static;
+
+// The hook set_panic_hook replaced, for restore_terminal to reinstall
+static PREVIOUS_PANIC_HOOK: Mutex<Option<Arc<PanicHook>>> = Mutex::new(None);
+
+/// Restore the terminal before a panic on the main thread is reported, so
+/// the message is readable and the shell usable. Worker threads' panics
+/// reach the main thread when they are joined.
+fn set_panic_hook(print: bool, inline: bool) {
+  let report: Arc<PanicHook> = Arc::from(std::panic::take_hook());
+  *PREVIOUS_PANIC_HOOK
+    .lock()
+    .unwrap_or_else(|e| e.into_inner()) = Some(Arc::clone(&report));
+  std::panic::set_hook(Box::new(move |info| {
+    if std::thread::current().name() == Some("main") {
+      let _ = disable_raw_mode();
+      if !inline {
+        let _ = tui_output(print).execute(LeaveAlternateScreen);
+      }
+      let _ = tui_output(print).execute(crossterm::cursor::Show);
+    }
+    report(info);
+  }));
+}
+
+/// Run the picker; returns whether every action taken in it succeeded
+fn run_tui(fast: bool, print: bool, inline: bool, no_refresh: bool) -> io::Result<bool> {
+  let mut cfg = config::load_config();
+  let fast = fast || cfg.as_ref().and_then(|c| c.fast_mode).unwrap_or(false);
+  let inline = inline || cfg.as_ref().and_then(|c| c.inline).unwrap_or(false);
+  let first_run = onboarding::needed();
 
-  let cfg = config::load_config();
   let theme = theme::resolve_theme(
     cfg.as_ref().and_then(|c| c.theme.as_deref()),
     cfg.as_ref().and_then(|c| c.theme_overrides.as_ref()),
   );
 
+  set_panic_hook(print, inline);
   enable_raw_mode()?;
-  io::stdout().execute(EnterAlternateScreen)?;
-  let backend = ratatui::backend::CrosstermBackend::new(io::stdout());
-  let mut terminal = Terminal::new(backend)?;
+  let backend = ratatui::backend::CrosstermBackend::new(tui_output(print));
+  let mut terminal = if inline {
+    Terminal::with_options(
+      backend,
+      TerminalOptions {
+        viewport: Viewport::Inline(INLINE_HEIGHT),
+      },
+    )?
+  } else {
+    tui_output(print).execute(EnterAlternateScreen)?;
+    Terminal::new(backend)?
+  };
 
-  let mut app = App::new(entries);
-  let result = run_app(&mut terminal, &mut app, &theme);
+  let mut setup_message = None;
+  if first_run {
+    setup_message = match onboarding::run(&mut terminal, &theme) {
+      Ok(msg) => msg,
+      Err(e) => {
+        restore_terminal(&mut terminal, print, inline)?;
+        return Err(e);
+      }
+    };
+    if setup_message.is_some() {
+      cfg = config::load_config();
+    }
+  }
 
-  disable_raw_mode()?;
-  io::stdout().execute(LeaveAlternateScreen)?;
+  let mut session_error = None;
+  let result = {
+    let focus_mode =
+      FilterFocusMode::from_config(cfg.as_ref().and_then(|c| c.filter_focus.as_deref()));
+    let max_devices = match cfg.as_ref().and_then(|c| c.max_devices) {
+      Some(0) => None,
+      Some(max) => Some(max),
+      None => Some(DEFAULT_MAX_DEVICES),
+    };
+    let refresh_interval = match cfg.as_ref().and_then(|c| c.refresh_interval_secs) {
+      _ if no_refresh => None,
+      Some(0) => None,
+      Some(secs) => Some(Duration::from_secs(secs)),
+      None => Some(DEFAULT_REFRESH_INTERVAL),
+    };
+    let restore_session = cfg
+      .as_ref()
+      .and_then(|c| c.restore_session)
+      .unwrap_or(false);
+    // Sections fill in as each platform's listing arrives
+    let sections = Platform::supported()
+      .iter()
+      .map(|&platform| (platform, Vec::new()))
+      .collect();
+    let mut app = App::new(sections, focus_mode, max_devices);
+    // Before loading, so a section the session hides isn't listed
+    if let Some(state) = restore_session.then(session::load).flatten() {
+      app.restore(state);
+    }
+    app.load(fast);
+    app.pick_only = print;
+    app.refresh_interval = refresh_interval;
+    app.keymap = keys::KeyMap::build(cfg.as_ref().and_then(|c| c.keys.as_ref())).0;
+    app.update_notice = Some(update::spawn_notice());
+    let warnings = config::warnings();
+    if !warnings.is_empty() {
+      app.toast = Some((
+        format!("Config warning: {}", warnings.join("; ")),
+        Instant::now(),
+      ));
+    }
+    let opened = Instant::now();
+    let result = run_app(&mut terminal, &mut app);
+    if !app.nothing_found {
+      let _ = metrics::record(metrics::MetricEvent::Session {
+        secs: opened.elapsed().as_secs_f64(),
+      });
+    }
+    if restore_session && !app.nothing_found {
+      session_error = session::save(&app.session()).err();
+    }
+    (!app.nothing_found).then_some((result, app.outcomes, app.picked))
+  };
+
+  restore_terminal(&mut terminal, print, inline)?;
 
-  if let Some(msg) = app.result_message {
-    println!("{}", msg);
+  if let Some(e) = session_error {
+    eprintln!("Warning: couldn't save the session: {}", e);
   }
+  if let Some(msg) = setup_message {
+    if print {
+      eprintln!("{}", msg);
+    } else {
+      println!("{}", msg);
+    }
+  }
+  match result {
+    Some((result, _, picked)) if print => {
+      result?;
+      let Some(key) = picked else {
+        return Ok(false);
+      };
+      match key {
+        EntryKey::Android(id) | EntryKey::Ios(id) => println!("{}", id),
+      }
+      Ok(true)
+    }
+    Some((result, outcomes, _)) => {
+      // Actions already happened even if drawing failed, so report them first
+      let all_ok = print_outcomes(&outcomes);
+      result.map(|_| all_ok)
+    }
+    // stdout is kept for the picked id
+    None if print => {
+      eprintln!("No emulators or simulators found.");
+      Ok(false)
+    }
+    None => {
+      println!("No emulators or simulators found.");
+      Ok(true)
+    }
+  }
+}
 
+/// `list_platform_entries`, with the refresh traced under `EMULAUNCH_TRACE`
+fn list_traced(platform: Platform, fast: bool) -> Result<Vec<EmulatorEntry>, String> {
+  trace::record(|| trace::TraceEvent::RefreshStarted {
+    platform: platform.label().to_string(),
+  });
+  let started = Instant::now();
+  let result = emulators::list_platform_entries(platform, fast);
+  trace::record(|| trace::TraceEvent::RefreshFinished {
+    platform: platform.label().to_string(),
+    ms: trace::millis(started.elapsed()),
+    entries: result.as_ref().ok().map(Vec::len),
+    error: result.as_ref().err().cloned(),
+  });
   result
 }
 
-fn run_app(
-  terminal: &mut Terminal<ratatui::backend::CrosstermBackend<io::Stdout>>,
-  app: &mut App,
-  theme: &theme::ThemeColors,
-) -> io::Result<()> {
+fn run_app(terminal: &mut TuiTerminal, app: &mut App) -> io::Result<()> {
+  // Resolved again only when the theme shown changes
+  let mut colors: Option<(usize, theme::ThemeColors)> = None;
   loop {
+    app.drain_updates();
+    if app.nothing_found {
+      break;
+    }
+    if app
+      .refresh_interval
+      .is_some_and(|interval| app.last_refresh.elapsed() >= interval)
+    {
+      app.refresh_all();
+    }
+
+    let shown = app.shown_theme();
+    if colors.as_ref().is_some_and(|(i, _)| *i != shown) {
+      colors = None;
+    }
+    let (_, theme) = colors.get_or_insert_with(|| {
+      let name = theme::THEME_NAMES[shown];
+      (
+        shown,
+        theme::resolve_theme(Some(name), app.theme_overrides.as_ref()),
+      )
+    });
+    let theme = &*theme;
+
+    let draw_started = Instant::now();
+    let mut too_small = false;
     terminal.draw(|frame| {
+      let area = frame.area();
+      too_small = area.width < MIN_TUI_WIDTH || area.height < MIN_TUI_HEIGHT;
+      if too_small {
+        let note = format!(
+          "Terminal too small (need at least {}x{})",
+          MIN_TUI_WIDTH, MIN_TUI_HEIGHT
+        );
+        let rows = text::wrapped_rows(&note, usize::from(area.width)) as u16;
+        let [middle] = Layout::vertical([Constraint::Length(rows)])
+          .flex(Flex::Center)
+          .areas(area);
+        let note = Paragraph::new(note)
+          .style(Style::default().fg(theme.meta_fg))
+          .alignment(Alignment::Center)
+          .wrap(Wrap { trim: true });
+        frame.render_widget(note, middle);
+        return;
+      }
+      let error_height = if app.errors.is_empty() { 0 } else { 1 };
+      // Borders, one row per snapshot (or the empty note) and the status row;
+      // otherwise borders plus the details (at least two rows, so it doesn't jump)
+      let details_height = match &app.snapshots {
+        _ if app.log.is_some() => (log_window(&app.outcomes).len().max(1) as u16 + 2).min(12),
+        Some(p) => (p.snapshots.len().max(1) as u16 + 3).min(12),
+        None => {
+          details_lines(app.selected_entry(), &app.history, None, theme)
+            .len()
+            .max(2) as u16
+            + 2
+        }
+      };
+      // In a short viewport (`--inline`) the details give way to the list
+      let details_height = details_height.min(
+        frame
+          .area()
+          .height
+          .saturating_sub(3 + MIN_LIST_HEIGHT + error_height + 1),
+      );
       let chunks = Layout::vertical([
-        Constraint::Length(3), // filter input
-        Constraint::Min(1),    // list
-        Constraint::Length(1), // help bar
+        Constraint::Length(3),              // filter input
+        Constraint::Min(1),                 // list
+        Constraint::Length(details_height), // details
+        Constraint::Length(error_height),   // error banner
+        Constraint::Length(1),              // help bar
       ])
       .split(frame.area());
 
       // Filter input
+      let filter_focused = app.focus_mode.is_modal() && app.focus == Focus::Filter;
+      let placeholder = match (app.focus_mode, filter_focused) {
+        (FilterFocusMode::Implicit, _) | (_, true) => "Type to filter...",
+        (FilterFocusMode::Explicit, false) => "Press / to filter...",
+        (FilterFocusMode::Auto, false) => "Press / or start typing to filter...",
+      };
       let filter_text = if app.filter.is_empty() {
-        "Type to filter..."
+        placeholder
       } else {
-        &app.filter
+        app.filter.as_str()
       };
       let filter_style = if app.filter.is_empty() {
         Style::default().fg(theme.filter_placeholder_fg)
       } else {
         Style::default().fg(theme.filter_active_fg)
       };
+      // While typing, the box says so in its own color
+      let filter_block = if filter_focused {
+        Block::default()
+          .borders(Borders::ALL)
+          .border_style(Style::default().fg(theme.filter_mode_fg))
+          .title(Span::styled(
+            " Filter \u{2014} typing ",
+            Style::default()
+              .fg(theme.filter_mode_fg)
+              .add_modifier(Modifier::BOLD),
+          ))
+      } else {
+        Block::default().borders(Borders::ALL).title(" Filter ")
+      };
       let filter = Paragraph::new(filter_text)
         .style(filter_style)
-        .block(Block::default().borders(Borders::ALL).title(" Filter "));
+        .block(filter_block);
       frame.render_widget(filter, chunks[0]);
+      // Typing always goes to the filter in implicit mode, so its cursor
+      // shows once there is a query to move through
+      let implicit_typing = app.focus_mode == FilterFocusMode::Implicit && !app.filter.is_empty();
+      if filter_focused || implicit_typing {
+        let cursor_x = chunks[0].x + 1 + app.filter.cursor_width() as u16;
+        frame.set_cursor_position((
+          cursor_x.min(chunks[0].right().saturating_sub(2)),
+          chunks[0].y + 1,
+        ));
+      }
 
       // Emulator list
+      let list_width = usize::from(chunks[1].width.saturating_sub(2));
+      let list_height = usize::from(chunks[1].height.saturating_sub(2));
+      let sticky = app.scroll_list(list_height);
+      let columns = fit_columns(
+        present::Columns::fit(
+          app
+            .filtered_indices
+            .iter()
+            .filter_map(|&i| app.views[i].as_ref()),
+          app.icons,
+        ),
+        list_width,
+      );
+      let header_line = |title: &str| {
+        let fold = if app.collapsed.contains(title) {
+          "\u{25b8}"
+        } else {
+          "\u{25be}"
+        };
+        let mut spans = vec![Span::styled(
+          format!(" {} {}", fold, title),
+          Style::default()
+            .fg(theme.header_fg)
+            .add_modifier(Modifier::BOLD),
+        )];
+        if Platform::from_section_title(title).is_some_and(|p| app.refreshing.contains(&p)) {
+          spans.push(Span::styled(
+            "  refreshing\u{2026}",
+            Style::default().fg(theme.meta_fg),
+          ));
+        }
+        Line::from(spans)
+      };
       let items: Vec<ListItem> = app
         .filtered_indices
         .iter()
         .map(|&i| {
           let entry = &app.entries[i];
-          match entry {
-            EmulatorEntry::SectionHeader(s) => ListItem::new(Line::from(Span::styled(
-              format!(" {}", s),
+          let item = match entry {
+            EmulatorEntry::SectionHeader(s) => ListItem::new(header_line(s)),
+            _ => match &app.views[i] {
+              Some(view) => {
+                let key = entry.key();
+                let tag = match &key {
+                  Some(k) if app.removed.contains(k) => Some("removed"),
+                  Some(k) if app.appeared.contains_key(k) => Some("new"),
+                  _ => None,
+                };
+                ListItem::new(device_line(view, tag, &columns, theme))
+              }
+              None => ListItem::new(""),
+            },
+          };
+          // Entries that can't be opened are dimmed, with the reason as meta
+          let removed = entry.key().is_some_and(|k| app.removed.contains(&k));
+          let item = if removed {
+            item.style(
               Style::default()
-                .fg(theme.header_fg)
-                .add_modifier(Modifier::BOLD),
-            ))),
-            EmulatorEntry::Android(e) => ListItem::new(Line::from(vec![
-              Span::raw("   "),
-              Span::styled(&e.name, Style::default().fg(theme.name_fg)),
-              Span::raw("  "),
-              Span::styled(
-                format!("[{}]", e.state),
-                Style::default().fg(state_color(&e.state, theme)),
-              ),
-              Span::styled(
-                format!("  ({})", e.device_type),
-                Style::default().fg(theme.meta_fg),
-              ),
-            ])),
-            EmulatorEntry::IOS(s) => ListItem::new(Line::from(vec![
-              Span::raw("   "),
-              Span::styled(&s.name, Style::default().fg(theme.name_fg)),
-              Span::raw("  "),
-              Span::styled(
-                format!("[{}]", s.state),
-                Style::default().fg(state_color(&s.state, theme)),
-              ),
-              Span::styled(
-                format!("  ({})", s.runtime),
-                Style::default().fg(theme.meta_fg),
-              ),
-            ])),
+                .add_modifier(Modifier::DIM)
+                .add_modifier(Modifier::CROSSED_OUT),
+            )
+          } else if app.views[i].as_ref().is_some_and(|v| !v.launchable) {
+            item.style(Style::default().add_modifier(Modifier::DIM))
+          } else {
+            item
+          };
+          let flashing = entry
+            .key()
+            .and_then(|k| app.flashes.get(&k))
+            .is_some_and(|since| since.elapsed() < FLASH_DURATION);
+          if flashing {
+            item.style(Style::default().add_modifier(Modifier::REVERSED))
+          } else {
+            item
           }
         })
         .collect();
 
-      let list = List::new(items)
-        .block(Block::default().borders(Borders::ALL).title(" Emulators "))
-        .highlight_style(
+      let hidden: Vec<&str> = Platform::supported()
+        .iter()
+        .filter(|p| app.hidden_platforms.contains(p))
+        .map(|p| p.label())
+        .collect();
+      let hidden_note = if hidden.is_empty() {
+        String::new()
+      } else {
+        format!(" [{} hidden]", hidden.join(", "))
+      };
+      let boot_note = app
+        .boot_progress()
+        .map_or_else(String::new, |p| format!(" \u{2014} {}", p));
+      let mut list_block = Block::default().borders(Borders::ALL).title(format!(
+        " Emulators{} (sorted: {}){}{} ",
+        hidden_note,
+        app.sort.name(),
+        boot_note,
+        if app.states_unchecked {
+          " \u{2014} states not checked (fast mode)"
+        } else {
+          ""
+        }
+      ));
+      if app.shown < app.matched {
+        list_block = list_block.title_bottom(Span::styled(
+          format!(
+            " showing {} of {} \u{2014} refine your filter ",
+            app.shown, app.matched
+          ),
+          Style::default().fg(theme.help_key_fg),
+        ));
+      }
+      if app.awaiting_first_list() {
+        let loading = Paragraph::new(Span::styled(
+          " Loading emulators\u{2026}",
+          Style::default().fg(theme.meta_fg),
+        ))
+        .block(list_block);
+        frame.render_widget(loading, chunks[1]);
+      } else {
+        let mut list_area = list_block.inner(chunks[1]);
+        frame.render_widget(list_block, chunks[1]);
+        // The header of the section scrolled into, pinned above its devices
+        if let Some(EmulatorEntry::SectionHeader(title)) =
+          sticky.map(|pos| &app.entries[app.filtered_indices[pos]])
+        {
+          let [pinned, rest] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(list_area);
+          frame.render_widget(Paragraph::new(header_line(title)), pinned);
+          list_area = rest;
+        }
+        let list = List::new(items).highlight_style(
           Style::default()
             .bg(theme.selection_bg)
             .add_modifier(Modifier::BOLD),
         );
-      frame.render_stateful_widget(list, chunks[1], &mut app.list_state);
+        frame.render_stateful_widget(list, list_area, &mut app.list_state);
+        // Position on the right border once the list doesn't fit
+        if app.filtered_indices.len() > list_height {
+          // One position per offset, so the thumb reaches the bottom with the last row
+          let rows = usize::from(list_area.height);
+          let mut scrollbar =
+            ScrollbarState::new(app.filtered_indices.len().saturating_sub(rows) + 1)
+              .viewport_content_length(rows)
+              .position(app.list_offset);
+          frame.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight)
+              .begin_symbol(None)
+              .end_symbol(None)
+              .thumb_style(Style::default().fg(theme.help_key_fg))
+              .track_style(Style::default().fg(theme.meta_fg)),
+            chunks[1].inner(Margin {
+              vertical: 1,
+              horizontal: 0,
+            }),
+            &mut scrollbar,
+          );
+        }
+      }
+
+      // Details pane, or the activity log or snapshot list when one is open
+      if let Some(log) = &mut app.log {
+        let block = Block::default().borders(Borders::ALL).title(" Activity ");
+        if app.outcomes.is_empty() {
+          let empty = Paragraph::new(Span::styled(
+            " Nothing yet this session",
+            Style::default().fg(theme.meta_fg),
+          ))
+          .block(block);
+          frame.render_widget(empty, chunks[2]);
+        } else {
+          let items: Vec<ListItem> = log_window(&app.outcomes)
+            .iter()
+            .map(|o| {
+              let since = o.at.duration_since(app.started).as_secs();
+              let (text, color) = match &o.result {
+                Ok(msg) => (msg.clone(), theme.state_booted_fg),
+                Err(e) => (format!("Error: {}", e), theme.state_shutdown_fg),
+              };
+              ListItem::new(Line::from(vec![
+                Span::styled(
+                  format!(" +{:02}:{:02}  ", since / 60, since % 60),
+                  Style::default().fg(theme.meta_fg),
+                ),
+                Span::styled(
+                  format!("{} {}  ", o.action.label(), o.key),
+                  Style::default().fg(theme.name_fg),
+                ),
+                // Multi-line results are shown on one row
+                Span::styled(text.replace('\n', " "), Style::default().fg(color)),
+              ]))
+            })
+            .collect();
+          let list = List::new(items)
+            .block(block)
+            .highlight_style(Style::default().bg(theme.selection_bg));
+          frame.render_stateful_widget(list, chunks[2], log);
+        }
+      } else if let Some(pane) = &mut app.snapshots {
+        let status = if app.focus == Focus::Confirm {
+          pane
+            .selected()
+            .map(|snap| format!(" Delete snapshot '{}'? y/n ", snap.name))
+        } else {
+          pane.message.as_ref().map(|m| format!(" {} ", m))
+        };
+        let mut block = Block::default()
+          .borders(Borders::ALL)
+          .title(format!(" Snapshots \u{2014} {} ", pane.avd_name));
+        if let Some(status) = status {
+          block = block.title_bottom(Span::styled(status, Style::default().fg(theme.help_key_fg)));
+        }
+
+        if pane.snapshots.is_empty() {
+          let empty = Paragraph::new(Span::styled(
+            " No snapshots",
+            Style::default().fg(theme.meta_fg),
+          ))
+          .block(block);
+          frame.render_widget(empty, chunks[2]);
+        } else {
+          let items: Vec<ListItem> = pane
+            .snapshots
+            .iter()
+            .map(|snap| {
+              let mut spans = vec![
+                Span::raw(" "),
+                Span::styled(snap.name.as_str(), Style::default().fg(theme.name_fg)),
+              ];
+              let meta: Vec<String> = [
+                snap.size_bytes.map(snapshots::format_size),
+                snap.created.map(snapshots::format_age),
+              ]
+              .into_iter()
+              .flatten()
+              .collect();
+              if !meta.is_empty() {
+                spans.push(Span::styled(
+                  format!("  {}", meta.join("  ")),
+                  Style::default().fg(theme.meta_fg),
+                ));
+              }
+              ListItem::new(Line::from(spans))
+            })
+            .collect();
+          let list = List::new(items).block(block).highlight_style(
+            Style::default()
+              .bg(theme.selection_bg)
+              .add_modifier(Modifier::BOLD),
+          );
+          frame.render_stateful_widget(list, chunks[2], &mut pane.list_state);
+        }
+      } else {
+        let mut block = Block::default().borders(Borders::ALL).title(" Details ");
+        let name_of = |key: &EntryKey| {
+          app
+            .entries
+            .iter()
+            .find(|e| e.key().as_ref() == Some(key))
+            .map(|e| e.display_name().to_string())
+        };
+        let prompt = app
+          .pending_stop
+          .as_ref()
+          .and_then(name_of)
+          .map(|name| format!("Shut down {}? y/n", name))
+          .or_else(|| {
+            let (key, _) = app.pending_relaunch.as_ref()?;
+            Some(format!(
+              "{} is already running \u{2014} y launch anyway / f focus / n cancel",
+              name_of(key)?
+            ))
+          });
+        if let Some(toast) = prompt.or_else(|| app.current_toast()) {
+          block = block.title_bottom(Span::styled(
+            format!(" {} ", toast),
+            Style::default().fg(theme.help_key_fg),
+          ));
+        }
+        let details = Paragraph::new(details_lines(
+          app.selected_entry(),
+          &app.history,
+          app.selected_boot(),
+          theme,
+        ))
+        .block(block);
+        frame.render_widget(details, chunks[2]);
+      }
+
+      // Error banner
+      if !app.errors.is_empty() {
+        let text = app
+          .errors
+          .iter()
+          .map(|(p, e)| format!("{}: {}", p.label(), e))
+          .collect::<Vec<_>>()
+          .join("  ");
+        let banner =
+          Paragraph::new(format!(" {}", text)).style(Style::default().fg(theme.state_shutdown_fg));
+        frame.render_widget(banner, chunks[3]);
+      }
 
       // Help bar
-      let help = Paragraph::new(Line::from(vec![
-        Span::styled(" j/k", Style::default().fg(theme.help_key_fg)),
-        Span::styled(" navigate  ", Style::default().fg(theme.help_text_fg)),
-        Span::styled("Enter", Style::default().fg(theme.help_key_fg)),
-        Span::styled(" open  ", Style::default().fg(theme.help_text_fg)),
-        Span::styled("q/Esc", Style::default().fg(theme.help_key_fg)),
-        Span::styled(" quit", Style::default().fg(theme.help_text_fg)),
-      ]));
-      frame.render_widget(help, chunks[2]);
+      let help = Paragraph::new(Line::from(help_spans(app, theme)));
+      frame.render_widget(help, chunks[4]);
+
+      if app.help_open {
+        render_help_popup(frame, &app.keymap, theme);
+      }
+      if let Some(picker) = &mut app.theme_picker {
+        render_theme_picker(frame, picker, app.theme, theme);
+      }
+      if let Some((title, message)) = &app.error_popup {
+        render_error_popup(frame, title, message, theme);
+      }
     })?;
+    trace::record(|| trace::TraceEvent::Draw {
+      ms: trace::millis(draw_started.elapsed()),
+    });
 
-    if event::poll(std::time::Duration::from_millis(100))? {
-      if let Event::Key(key) = event::read()? {
+    if event::poll(Duration::from_millis(100))? {
+      let event = event::read()?;
+      // Redraw at the new size right away, the too-small note included
+      if let Event::Resize(..) = event {
+        terminal.autoresize()?;
+        continue;
+      }
+      if let Event::Key(key) = event {
+        trace::record(|| trace::TraceEvent::Key {
+          key: key.code.to_string(),
+          modifiers: key.modifiers.to_string(),
+          kind: format!("{:?}", key.kind),
+        });
         if key.kind != KeyEventKind::Press {
           continue;
         }
-        match key.code {
-          KeyCode::Esc => break,
-          KeyCode::Char('q') if app.filter.is_empty() => break,
-          KeyCode::Char('j') if app.filter.is_empty() => app.move_selection(1),
-          KeyCode::Char('k') if app.filter.is_empty() => app.move_selection(-1),
-          KeyCode::Down => app.move_selection(1),
-          KeyCode::Up => app.move_selection(-1),
-          KeyCode::Enter => {
-            if let Some(entry) = app.selected_entry() {
-              match emulators::open_entry(entry) {
-                Ok(msg) => {
-                  app.result_message = Some(msg);
-                  break;
-                }
-                Err(e) => {
-                  app.result_message = Some(format!("Error: {}", e));
-                  break;
-                }
+        // Only the note is on screen, so there is nothing else to act on
+        if too_small {
+          if app.keymap.is_quit(key) {
+            break;
+          }
+          continue;
+        }
+        if app.help_open {
+          app.help_open = false;
+          continue;
+        }
+        if app.error_popup.is_some() {
+          if matches!(key.code, event::KeyCode::Esc | event::KeyCode::Enter) {
+            app.error_popup = None;
+          }
+          continue;
+        }
+        // Until something is listed, typing goes to the filter
+        let action = if app.awaiting_first_list() {
+          keys::dispatch_loading(key)
+        } else {
+          keys::dispatch(
+            key,
+            &app.keymap,
+            app.focus_mode,
+            app.focus,
+            app.filter.is_empty(),
+          )
+        };
+        trace::record(|| trace::TraceEvent::Action {
+          action: action.trace_name(),
+        });
+        // The picker only moves, applies, saves or closes
+        if app.focus == Focus::Themes {
+          match action {
+            Action::MoveDown => app.move_theme_selection(1),
+            Action::MoveUp => app.move_theme_selection(-1),
+            Action::Open => app.apply_theme(false),
+            Action::SaveTheme => app.apply_theme(true),
+            Action::ToggleThemes => app.toggle_themes(),
+            _ => {}
+          }
+          continue;
+        }
+        match action {
+          Action::Quit => break,
+          Action::MoveDown | Action::MoveUp => {
+            let delta = if action == Action::MoveDown { 1 } else { -1 };
+            match &mut app.snapshots {
+              Some(pane) if app.focus == Focus::Snapshots => pane.move_selection(delta),
+              _ if app.focus == Focus::Log => app.scroll_log(delta),
+              _ => app.move_selection(delta),
+            }
+          }
+          // The snapshot list and the log don't page
+          Action::PageDown
+          | Action::PageUp
+          | Action::HalfPageDown
+          | Action::HalfPageUp
+          | Action::SelectFirst
+          | Action::SelectLast
+            if matches!(app.focus, Focus::Snapshots | Focus::Log) => {}
+          Action::PageDown | Action::PageUp | Action::HalfPageDown | Action::HalfPageUp => {
+            let page = app.list_rows.max(1) as isize;
+            app.jump_selection(match action {
+              Action::PageDown => page,
+              Action::PageUp => -page,
+              Action::HalfPageDown => (page / 2).max(1),
+              _ => -(page / 2).max(1),
+            });
+          }
+          Action::SelectFirst => app.select_near(0, true),
+          Action::SelectLast => app.select_near(usize::MAX, false),
+          Action::Open if app.focus == Focus::List && app.header_selected() => {
+            app.set_collapsed(None)
+          }
+          Action::Open | Action::OpenPaired | Action::OpenNoGui | Action::OpenCold
+            if app.pick_only =>
+          {
+            let key = match &app.snapshots {
+              Some(pane) => Some(EntryKey::Android(pane.avd_id.clone())),
+              None => app.selected_entry().and_then(EmulatorEntry::key),
+            };
+            if let Some(key) = key.filter(|k| !app.removed.contains(k)) {
+              app.picked = Some(key);
+              break;
+            }
+          }
+          Action::Open if app.focus == Focus::Snapshots => {
+            let Some(pane) = &app.snapshots else {
+              continue;
+            };
+            if let Some(snapshot) = pane.selected() {
+              let result =
+                emulators::open_android_emulator_from_snapshot(&pane.avd_id, &snapshot.name);
+              let key = EntryKey::Android(pane.avd_id.clone());
+              if app.record_launch(key, OutcomeAction::OpenSnapshot, result) {
+                break;
+              }
+            }
+          }
+          Action::Open | Action::OpenPaired | Action::OpenNoGui | Action::OpenCold => {
+            let selected = app.selected_entry().and_then(EmulatorEntry::key);
+            if action == Action::OpenCold && matches!(selected, Some(EntryKey::Ios(_))) {
+              app.toast = Some((
+                "Cold boot only applies to Android emulators".to_string(),
+                Instant::now(),
+              ));
+              continue;
+            }
+            if selected.as_ref().is_some_and(|k| app.removed.contains(k)) {
+              app.toast = Some(("This device no longer exists".to_string(), Instant::now()));
+              continue;
+            }
+            if let Some(Err(reason)) = app.selected_entry().map(EmulatorEntry::launchable) {
+              app.toast = Some((reason.to_string(), Instant::now()));
+              continue;
+            }
+            if let Some(key) = selected {
+              let options = emulators::LaunchOptions {
+                with_paired: action == Action::OpenPaired,
+                no_gui: action == Action::OpenNoGui,
+                cold_boot: action == Action::OpenCold,
+                ..Default::default()
+              };
+              if app.launch(key, options) {
+                break;
               }
             }
           }
-          KeyCode::Backspace => {
-            app.filter.pop();
+          Action::Refresh => app.refresh_all(),
+          Action::FocusFilter => app.focus = Focus::Filter,
+          Action::FocusList => app.focus = Focus::List,
+          Action::ClearFilter => {
+            app.filter.clear();
             app.apply_filter();
           }
-          KeyCode::Char(c) => {
-            app.filter.push(c);
+          Action::FilterPop => {
+            app.filter.backspace();
             app.apply_filter();
           }
-          _ => {}
+          Action::FilterStart(c) => {
+            app.focus = Focus::Filter;
+            app.filter.insert(c);
+            app.apply_filter();
+          }
+          Action::FilterPush(c) => {
+            app.filter.insert(c);
+            app.apply_filter();
+          }
+          Action::FilterEdit(edit) => {
+            let before = app.filter.clone();
+            app.filter.apply(edit);
+            // Moving the cursor keeps the selection
+            if app.filter != before {
+              app.apply_filter();
+            }
+          }
+          Action::ToggleSnapshots => app.toggle_snapshots(),
+          Action::ToggleLog => app.toggle_log(),
+          Action::CycleSort => app.cycle_sort(),
+          Action::YankId | Action::YankName => {
+            let value = app.selected_entry().and_then(|entry| match action {
+              Action::YankName => Some(entry.display_name().to_string()),
+              _ => match entry.key()? {
+                EntryKey::Android(id) | EntryKey::Ios(id) => Some(id),
+              },
+            });
+            if let Some(value) = value {
+              // Without a clipboard the value is at least on screen to copy
+              let message = match clipboard::copy(&value, terminal.backend_mut()) {
+                Ok(clipboard::Method::Tool(tool)) => format!("Copied {} via {}", value, tool),
+                // The terminal may refuse OSC 52 without saying so
+                Ok(clipboard::Method::Osc52) => format!("Copied {} via OSC 52", value),
+                Err(e) => format!("{} ({})", value, e),
+              };
+              app.toast = Some((message, Instant::now()));
+            }
+          }
+          Action::ToggleSection(platform) => app.toggle_section(platform),
+          Action::ToggleThemes => app.toggle_themes(),
+          Action::SaveTheme => {}
+          Action::Collapse => app.set_collapsed(Some(true)),
+          Action::Expand => app.set_collapsed(Some(false)),
+          Action::ToggleCollapse => app.set_collapsed(None),
+          Action::ToggleHelp => app.help_open = true,
+          Action::DeleteSnapshot => {
+            if app
+              .snapshots
+              .as_ref()
+              .is_some_and(|p| p.selected().is_some())
+            {
+              app.focus = Focus::Confirm;
+            }
+          }
+          Action::Stop => app.ask_stop_selected(),
+          Action::Confirm => match (app.pending_stop.take(), app.pending_relaunch.take()) {
+            (Some(key), _) => app.stop_confirmed(key),
+            (None, Some((key, options))) => {
+              if app.launch_confirmed(key, options) {
+                break;
+              }
+            }
+            (None, None) => app.delete_selected_snapshot(),
+          },
+          Action::FocusWindow if app.pending_relaunch.is_some() => {
+            if let Some((key, _)) = app.pending_relaunch.take() {
+              app.focus = Focus::List;
+              let result = emulators::focus_device(&key);
+              app.record(key, OutcomeAction::Focus, result);
+            }
+          }
+          Action::FocusWindow | Action::Cancel => {
+            let was_list_prompt =
+              app.pending_stop.take().is_some() || app.pending_relaunch.take().is_some();
+            app.focus = if was_list_prompt {
+              Focus::List
+            } else {
+              Focus::Snapshots
+            }
+          }
+          Action::None => {}
         }
       }
     }
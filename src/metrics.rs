@@ -0,0 +1,234 @@
+//! Local usage records for `emulaunch stats` (`metrics = true`).
+//!
+//! Nothing leaves the machine: events are appended to a JSON-lines file in
+//! the data dir, with the same locking as the history file, and only read
+//! back by `summarize`.
+
+use crate::config;
+use crate::emulators::EntryKey;
+use crate::filelock;
+use crate::history::{self, BootMode};
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const METRICS_FILE: &str = "metrics.jsonl";
+
+// Older events are dropped when the file is rewritten
+const MAX_RECORDS: usize = 5000;
+
+// `metrics` as read by the first event, so later ones don't reparse the config
+static ENABLED: OnceLock<bool> = OnceLock::new();
+
+/// Something worth counting
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "lowercase")]
+pub enum MetricEvent {
+  Launch {
+    key: EntryKey,
+  },
+  /// A launch that was waited on until the device booted
+  Boot {
+    key: EntryKey,
+    secs: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mode: Option<BootMode>,
+  },
+  Stop {
+    key: EntryKey,
+  },
+  /// The TUI was open this long
+  Session {
+    secs: f64,
+  },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricRecord {
+  /// Unix timestamp (seconds) of the event
+  pub timestamp: u64,
+  #[serde(flatten)]
+  pub event: MetricEvent,
+}
+
+fn metrics_path() -> Option<PathBuf> {
+  Some(config::data_dir()?.join(METRICS_FILE))
+}
+
+/// All recorded events, skipping lines that don't parse
+pub fn load() -> Vec<MetricRecord> {
+  let Some(contents) = metrics_path().and_then(|p| std::fs::read_to_string(p).ok()) else {
+    return Vec::new();
+  };
+  filelock::parse_jsonl(&contents)
+}
+
+/// Record an event if `metrics` is on. Callers ignore the error: a failed
+/// write shouldn't fail the command it is counting.
+pub fn record(event: MetricEvent) -> Result<(), String> {
+  if !*ENABLED.get_or_init(config::metrics_enabled) {
+    return Ok(());
+  }
+  let path = metrics_path().ok_or_else(|| config::ERR_NO_HOME.to_string())?;
+  let record = MetricRecord {
+    timestamp: SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map_or(0, |d| d.as_secs()),
+    event,
+  };
+  filelock::append_jsonl(&path, record, MAX_RECORDS)
+}
+
+/// Counts for one device
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceStats {
+  pub key: EntryKey,
+  pub launches: usize,
+  pub stops: usize,
+  /// Median of the measured boots, in any mode
+  pub median_boot_secs: Option<f64>,
+}
+
+/// Everything `emulaunch stats` shows
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Summary {
+  /// Most launched first
+  pub devices: Vec<DeviceStats>,
+  /// UTC hour (0-23) with the most launches, earliest on a tie
+  pub busiest_hour: Option<u8>,
+  pub busiest_hour_launches: usize,
+  pub sessions: usize,
+  pub total_session_secs: f64,
+  pub median_session_secs: Option<f64>,
+}
+
+/// Index of a device's stats, adding it on first sight
+fn device_slot(
+  devices: &mut Vec<DeviceStats>,
+  index: &mut HashMap<EntryKey, usize>,
+  key: &EntryKey,
+) -> usize {
+  *index.entry(key.clone()).or_insert_with(|| {
+    devices.push(DeviceStats {
+      key: key.clone(),
+      launches: 0,
+      stops: 0,
+      median_boot_secs: None,
+    });
+    devices.len() - 1
+  })
+}
+
+/// Aggregate recorded events
+pub fn summarize(records: &[MetricRecord]) -> Summary {
+  let mut devices: Vec<DeviceStats> = Vec::new();
+  let mut index: HashMap<EntryKey, usize> = HashMap::new();
+  let mut boots: HashMap<EntryKey, Vec<f64>> = HashMap::new();
+  let mut hours = [0usize; 24];
+  let mut sessions = Vec::new();
+
+  for record in records {
+    match &record.event {
+      MetricEvent::Launch { key } => {
+        let i = device_slot(&mut devices, &mut index, key);
+        devices[i].launches += 1;
+        hours[((record.timestamp / 3600) % 24) as usize] += 1;
+      }
+      MetricEvent::Stop { key } => {
+        let i = device_slot(&mut devices, &mut index, key);
+        devices[i].stops += 1;
+      }
+      MetricEvent::Boot { key, secs, .. } => {
+        device_slot(&mut devices, &mut index, key);
+        if secs.is_finite() && *secs >= 0.0 {
+          boots.entry(key.clone()).or_default().push(*secs);
+        }
+      }
+      MetricEvent::Session { secs } => {
+        if secs.is_finite() && *secs >= 0.0 {
+          sessions.push(*secs);
+        }
+      }
+    }
+  }
+  for stats in &mut devices {
+    stats.median_boot_secs = boots
+      .get_mut(&stats.key)
+      .and_then(|samples| history::median(samples));
+  }
+  devices.sort_by(|a, b| {
+    b.launches
+      .cmp(&a.launches)
+      .then_with(|| a.key.to_string().cmp(&b.key.to_string()))
+  });
+
+  let busiest = (0..24u8)
+    .filter(|&h| hours[h as usize] > 0)
+    .max_by(|&a, &b| hours[a as usize].cmp(&hours[b as usize]).then(b.cmp(&a)));
+  Summary {
+    devices,
+    busiest_hour: busiest,
+    busiest_hour_launches: busiest.map_or(0, |h| hours[h as usize]),
+    sessions: sessions.len(),
+    total_session_secs: sessions.iter().sum(),
+    median_session_secs: history::median(&mut sessions),
+  }
+}
+
+/// `1m 05s`, `42s`, `2h 03m`
+fn format_duration(secs: f64) -> String {
+  let secs = Duration::from_secs_f64(secs.max(0.0)).as_secs();
+  match secs {
+    0..60 => format!("{}s", secs),
+    60..3600 => format!("{}m {:02}s", secs / 60, secs % 60),
+    _ => format!("{}h {:02}m", secs / 3600, secs % 3600 / 60),
+  }
+}
+
+/// The summary as a table and a few totals
+pub fn format_summary(summary: &Summary) -> String {
+  if summary.devices.is_empty() && summary.sessions == 0 {
+    return "No usage recorded yet\n".to_string();
+  }
+  let names: Vec<String> = summary.devices.iter().map(|d| d.key.to_string()).collect();
+  let width = names.iter().map(String::len).chain([6]).max().unwrap_or(6);
+  let mut out = format!(
+    "{:<width$}  {:>8}  {:>11}  {:>5}\n",
+    "Device", "Launches", "Median boot", "Stops"
+  );
+  for (stats, name) in summary.devices.iter().zip(&names) {
+    let boot = stats
+      .median_boot_secs
+      .map_or_else(|| "-".to_string(), format_duration);
+    out.push_str(&format!(
+      "{:<width$}  {:>8}  {:>11}  {:>5}\n",
+      name, stats.launches, boot, stats.stops
+    ));
+  }
+  out.push('\n');
+  if let Some(hour) = summary.busiest_hour {
+    out.push_str(&format!(
+      "Busiest hour: {:02}:00-{:02}:00 UTC ({} {})\n",
+      hour,
+      (hour + 1) % 24,
+      summary.busiest_hour_launches,
+      if summary.busiest_hour_launches == 1 {
+        "launch"
+      } else {
+        "launches"
+      }
+    ));
+  }
+  if let Some(median) = summary.median_session_secs {
+    out.push_str(&format!(
+      "TUI sessions: {}, {} in total, median {}\n",
+      summary.sessions,
+      format_duration(summary.total_session_secs),
+      format_duration(median)
+    ));
+  }
+  out
+}
@@ -0,0 +1,145 @@
+use crate::config::{self, DetectedTools};
+use crate::text;
+use crate::theme::ThemeColors;
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::{
+  layout::{Constraint, Layout},
+  style::{Modifier, Style},
+  text::{Line, Span},
+  widgets::{Block, Borders, Paragraph, Wrap},
+};
+use std::io;
+
+/// First run: no config file exists and no tool could be resolved
+pub fn needed() -> bool {
+  !config::config_file_exists() && config::no_tools_resolved()
+}
+
+/// Show the first-run screen until the user saves a config or skips with Esc.
+///
+/// Returns the message to show after the TUI exits, if any.
+pub fn run(terminal: &mut crate::TuiTerminal, theme: &ThemeColors) -> io::Result<Option<String>> {
+  let searched = config::searched_locations();
+  let config_path = config::preferred_config_path();
+  let mut input = String::new();
+  let mut tools = DetectedTools::default();
+  let mut error: Option<String> = None;
+
+  loop {
+    terminal.draw(|frame| {
+      let chunks = Layout::vertical([
+        Constraint::Min(1),    // explanation
+        Constraint::Length(3), // path input
+        Constraint::Length(2), // validation
+        Constraint::Length(1), // help bar
+      ])
+      .split(frame.area());
+
+      let meta = Style::default().fg(theme.meta_fg);
+      let mut lines = vec![
+        Line::from(Span::styled(
+          " Welcome to emulaunch",
+          Style::default()
+            .fg(theme.header_fg)
+            .add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+        Line::from(" No config file was found and no Android or iOS tools could be located."),
+        Line::from(Span::styled(" Searched:", meta)),
+      ];
+      lines.extend(
+        searched
+          .iter()
+          .map(|s| Line::from(Span::styled(format!("   {}", s), meta))),
+      );
+      lines.push(Line::from(""));
+      lines.push(Line::from(
+        " Type or paste the path to your Android SDK, or to adb or the emulator binary.",
+      ));
+      if let Some(path) = &config_path {
+        lines.push(Line::from(vec![
+          Span::raw(" Enter saves a starter config to "),
+          Span::styled(
+            path.display().to_string(),
+            Style::default().fg(theme.name_fg),
+          ),
+        ]));
+      }
+      let intro = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(Block::default().borders(Borders::ALL).title(" Setup "));
+      frame.render_widget(intro, chunks[0]);
+
+      let path_input = Paragraph::new(input.as_str())
+        .style(Style::default().fg(theme.filter_active_fg))
+        .block(
+          Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.help_key_fg))
+            .title(" SDK or tool path "),
+        );
+      frame.render_widget(path_input, chunks[1]);
+      let cursor_x = chunks[1].x + 1 + text::display_width(&input) as u16;
+      frame.set_cursor_position((
+        cursor_x.min(chunks[1].right().saturating_sub(2)),
+        chunks[1].y + 1,
+      ));
+
+      let status = |label: &'static str, found: &Option<String>| match found {
+        Some(path) => Line::from(vec![
+          Span::styled(" \u{2713} ", Style::default().fg(theme.state_booted_fg)),
+          Span::raw(format!("{}: {}", label, path)),
+        ]),
+        None => Line::from(vec![
+          Span::styled(" \u{2717} ", Style::default().fg(theme.state_shutdown_fg)),
+          Span::styled(format!("{}: not found", label), meta),
+        ]),
+      };
+      let validation = match &error {
+        Some(e) => Paragraph::new(Span::styled(
+          format!(" {}", e),
+          Style::default().fg(theme.state_shutdown_fg),
+        )),
+        None => Paragraph::new(vec![
+          status("emulator", &tools.android_emulator_cmd),
+          status("adb", &tools.adb_cmd),
+        ]),
+      };
+      frame.render_widget(validation, chunks[2]);
+
+      let help = Paragraph::new(Line::from(vec![
+        Span::styled(" Enter", Style::default().fg(theme.help_key_fg)),
+        Span::styled(" save config  ", Style::default().fg(theme.help_text_fg)),
+        Span::styled("Esc", Style::default().fg(theme.help_key_fg)),
+        Span::styled(" skip", Style::default().fg(theme.help_text_fg)),
+      ]));
+      frame.render_widget(help, chunks[3]);
+    })?;
+
+    let Event::Key(key) = event::read()? else {
+      continue;
+    };
+    if key.kind != KeyEventKind::Press {
+      continue;
+    }
+    match key.code {
+      KeyCode::Esc => return Ok(None),
+      KeyCode::Enter => match config::init_config(&tools) {
+        Ok(path) => return Ok(Some(format!("Created config: {}", path.display()))),
+        Err(e) => error = Some(e),
+      },
+      KeyCode::Backspace => {
+        input.pop();
+        tools = config::detect_tools(&input);
+        error = None;
+      }
+      KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+        input.push(c);
+        tools = config::detect_tools(&input);
+        error = None;
+      }
+      _ => {}
+    }
+  }
+}
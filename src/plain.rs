@@ -0,0 +1,167 @@
+//! Line-based picker for screen readers and dumb terminals (`pick --plain`,
+//! or any picker run with `TERM=dumb`).
+//!
+//! No raw mode, alternate screen or colors: the list is printed with
+//! numbers and each answer is read as a line. It lists, filters and
+//! launches through the same code as the TUI (`view::build`,
+//! `present::views`, `emulators::open_entry`).
+
+use crate::config;
+use crate::emulators::{self, EmulatorEntry, EntryKey};
+use crate::history;
+use crate::present;
+use crate::view;
+
+use std::io::{self, BufRead, Write};
+
+/// Whether the terminal can't host the TUI
+pub fn terminal_is_dumb() -> bool {
+  std::env::var("TERM").is_ok_and(|term| term == "dumb")
+}
+
+/// The devices that pass `filter`, numbered from 1 in list order
+fn numbered(sections: &[(emulators::Platform, Vec<EmulatorEntry>)], filter: &str) -> Vec<Row> {
+  let options = view::ViewOptions {
+    sort: view::SortMode::configured(),
+    last_launched: history::last_launched(&history::load()),
+    recent: config::recent_devices(),
+    filter: filter.to_string(),
+    ..Default::default()
+  };
+  let view = view::build(sections, &options);
+  let views = present::views(&view.entries);
+  view
+    .visible
+    .iter()
+    .map(|&i| match &views[i] {
+      Some(device) => Row::Device(view.entries[i].clone(), device.line()),
+      None => Row::Header(view.entries[i].display_name().to_string()),
+    })
+    .collect()
+}
+
+enum Row {
+  Header(String),
+  Device(EmulatorEntry, String),
+}
+
+fn print_list(out: &mut dyn Write, rows: &[Row], filter: &str) -> io::Result<()> {
+  if !filter.is_empty() {
+    writeln!(out, "Devices matching \"{}\":", filter)?;
+  }
+  let mut number = 0;
+  for row in rows {
+    match row {
+      Row::Header(title) => writeln!(out, "{}:", title)?,
+      Row::Device(_, line) => {
+        number += 1;
+        writeln!(out, "  {}. {}", number, line)?;
+      }
+    }
+  }
+  if number == 0 {
+    writeln!(out, "No devices match.")?;
+  }
+  writeln!(
+    out,
+    "Type a number to open it, text to filter, an empty line to clear the filter, or q to quit."
+  )
+}
+
+/// Read one answer; `None` at end of input
+fn prompt(
+  out: &mut dyn Write,
+  input: &mut dyn BufRead,
+  question: &str,
+) -> io::Result<Option<String>> {
+  write!(out, "{}", question)?;
+  out.flush()?;
+  let mut line = String::new();
+  if input.read_line(&mut line)? == 0 {
+    return Ok(None);
+  }
+  Ok(Some(line.trim().to_string()))
+}
+
+/// Run the picker. With `print`, prompts go to stderr and the chosen AVD id
+/// or UDID is printed on stdout instead of opening the device. Returns
+/// false when the launch failed, or when `print` found nothing picked.
+pub fn run(fast: bool, print: bool) -> io::Result<bool> {
+  let sections = emulators::collect_sections(fast);
+  let mut out: Box<dyn Write> = if print {
+    Box::new(io::stderr())
+  } else {
+    Box::new(io::stdout())
+  };
+  if sections.iter().all(|(_, entries)| entries.is_empty()) {
+    writeln!(out, "No emulators or simulators found.")?;
+    return Ok(!print);
+  }
+
+  let stdin = io::stdin();
+  let mut input = stdin.lock();
+  let mut filter = String::new();
+  let mut rows = numbered(&sections, &filter);
+  print_list(&mut out, &rows, &filter)?;
+  loop {
+    // Quitting is only a failure when a script is waiting for a pick
+    let Some(answer) = prompt(&mut out, &mut input, "> ")? else {
+      return Ok(!print);
+    };
+    if answer.eq_ignore_ascii_case("q") {
+      return Ok(!print);
+    }
+    let Ok(number) = answer.parse::<usize>() else {
+      // Anything that isn't a number (or is empty) re-filters the list
+      filter = answer;
+      rows = numbered(&sections, &filter);
+      print_list(&mut out, &rows, &filter)?;
+      continue;
+    };
+    let chosen = rows
+      .iter()
+      .filter_map(|row| match row {
+        Row::Device(entry, line) => Some((entry, line)),
+        Row::Header(_) => None,
+      })
+      .nth(number.wrapping_sub(1));
+    let Some((entry, line)) = chosen else {
+      writeln!(out, "There is no device {}.", number)?;
+      continue;
+    };
+    if let Err(reason) = entry.launchable() {
+      writeln!(out, "Can't open {}: {}", entry.display_name(), reason)?;
+      continue;
+    }
+    let verb = if print { "Pick" } else { "Open" };
+    let confirm = prompt(&mut out, &mut input, &format!("{} {}? [Y/n] ", verb, line))?;
+    match confirm.as_deref() {
+      None => return Ok(!print),
+      Some("" | "y" | "Y" | "yes") => {}
+      Some(_) => {
+        print_list(&mut out, &rows, &filter)?;
+        continue;
+      }
+    }
+    if print {
+      match entry.key() {
+        Some(EntryKey::Android(id) | EntryKey::Ios(id)) => println!("{}", id),
+        None => return Ok(false),
+      }
+      return Ok(true);
+    }
+    return match emulators::open_entry(entry, &emulators::LaunchOptions::default()) {
+      Ok(message) => {
+        writeln!(out, "{}", message)?;
+        if let Some(key) = entry.key() {
+          let _ = history::record_launch(&key, None);
+        }
+        Ok(true)
+      }
+      Err(e) => {
+        eprintln!("Error: {}", e);
+        Ok(false)
+      }
+    };
+  }
+}
@@ -0,0 +1,278 @@
+//! How a device is presented, shared by `list` and the TUI.
+//!
+//! A `DeviceView` is derived once per entry; the text listing and the TUI
+//! rows both render from it, so they can't drift apart on what a row says.
+
+use crate::emulators::{self, EmulatorEntry, EntryKey, Platform};
+use crate::text;
+
+use std::collections::{HashMap, HashSet};
+
+/// The state a device is in, as far as styling is concerned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateKind {
+  Booted,
+  Shutdown,
+  Broken,
+  /// Anything else simctl or the emulator reports (`Booting`, `Unknown`, ...)
+  Other,
+}
+
+impl StateKind {
+  pub fn of(state: &str) -> StateKind {
+    match state {
+      emulators::STATE_BOOTED => StateKind::Booted,
+      emulators::STATE_SHUTDOWN => StateKind::Shutdown,
+      emulators::STATE_BROKEN => StateKind::Broken,
+      _ => StateKind::Other,
+    }
+  }
+}
+
+/// The parts of a row a caller can style, for `DeviceView::line_with`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Part {
+  /// The platform's icon (`icons = true`)
+  Platform(Platform),
+  Name,
+  Disambiguator,
+  State(StateKind),
+  Meta,
+}
+
+/// One device row, as every output shows it
+#[derive(Debug, Clone)]
+pub struct DeviceView {
+  pub platform: Platform,
+  pub name: String,
+  /// The AVD id or short UDID, set only when another device in the same
+  /// list shows the same name and meta
+  pub disambiguator: Option<String>,
+  pub state: String,
+  pub state_kind: StateKind,
+  /// The device type or iOS runtime, or why the device can't be opened
+  pub meta: String,
+  pub launchable: bool,
+}
+
+// Enough of a UDID to tell two simulators apart at a glance
+const SHORT_UDID_LEN: usize = 8;
+
+// Names are padded to at most this width; longer ones push their own
+// row's state aside in `list` and are cut short in the TUI
+pub const MAX_NAME_COLUMN: usize = 40;
+
+// Wide enough for `[Shutdown]`, so the meta column doesn't move as
+// devices boot and shut down
+const MIN_STATE_COLUMN: usize = 10;
+
+// Space between columns
+const COLUMN_GAP: &str = "  ";
+
+// Nerd Font glyphs (Font Awesome range), each one cell wide
+const ICON_ANDROID: &str = "\u{f17b}";
+const ICON_APPLE: &str = "\u{f179}";
+const ICON_BOOTED: &str = "\u{f111}";
+const ICON_SHUTDOWN: &str = "\u{f10c}";
+const ICON_OTHER_STATE: &str = "\u{f192}";
+
+// Both icons and a space after each
+const ICONS_WIDTH: usize = 4;
+
+/// Column widths that line rows up, fitted to the rows being shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Columns {
+  /// Platform and state icons lead each row (`icons = true`)
+  pub icons: bool,
+  /// The name and its disambiguator
+  pub name: usize,
+  /// `[state]`
+  pub state: usize,
+  /// `(meta)`, which is never padded, being last
+  pub meta: usize,
+}
+
+impl Columns {
+  pub fn fit<'a>(views: impl IntoIterator<Item = &'a DeviceView>, icons: bool) -> Columns {
+    let empty = Columns {
+      icons,
+      name: 0,
+      state: MIN_STATE_COLUMN,
+      meta: 0,
+    };
+    views.into_iter().fold(empty, |columns, view| Columns {
+      icons,
+      name: columns.name.max(view.name_width()),
+      state: columns.state.max(text::display_width(&view.state) + 2),
+      meta: columns.meta.max(text::display_width(&view.meta) + 2),
+    })
+  }
+
+  /// These columns with names padded to at most `MAX_NAME_COLUMN`
+  pub fn capped(self) -> Columns {
+    Columns {
+      name: self.name.min(MAX_NAME_COLUMN),
+      ..self
+    }
+  }
+
+  /// Columns of a whole row, gaps included
+  pub fn width(&self) -> usize {
+    let icons = if self.icons { ICONS_WIDTH } else { 0 };
+    icons + self.name + self.state + self.meta + 2 * COLUMN_GAP.len()
+  }
+}
+
+impl DeviceView {
+  /// The view of one entry on its own (`None` for headers)
+  pub fn of(entry: &EmulatorEntry) -> Option<DeviceView> {
+    let launchable = entry.launchable();
+    let (platform, name, state, meta) = match entry {
+      EmulatorEntry::SectionHeader(_) => return None,
+      EmulatorEntry::Android(e) => (Platform::Android, &e.name, &e.state, &e.device_type),
+      EmulatorEntry::IOS(s) => (Platform::Ios, &s.name, &s.state, &s.runtime),
+    };
+    Some(DeviceView {
+      platform,
+      name: name.clone(),
+      disambiguator: None,
+      state: state.clone(),
+      state_kind: StateKind::of(state),
+      meta: launchable.err().unwrap_or(meta).to_string(),
+      launchable: launchable.is_ok(),
+    })
+  }
+
+  /// Columns the name and its disambiguator take
+  fn name_width(&self) -> usize {
+    let name = text::display_width(&self.name);
+    match &self.disambiguator {
+      Some(d) => name + text::display_width(d) + 3,
+      None => name,
+    }
+  }
+
+  /// The row split into styleable parts and padding (`None`), padded to
+  /// `columns`. A name too long for its column is cut short with `…`.
+  pub fn parts(&self, columns: &Columns) -> Vec<(Option<Part>, String)> {
+    self.parts_with(columns, true)
+  }
+
+  fn parts_with(&self, columns: &Columns, truncate: bool) -> Vec<(Option<Part>, String)> {
+    let disambiguator = self.disambiguator.as_ref().map(|d| format!("<{}>", d));
+    let reserved = disambiguator
+      .as_deref()
+      .map_or(0, |d| text::display_width(d) + 1);
+    let name = if truncate {
+      text::truncate_to_width(&self.name, columns.name.saturating_sub(reserved))
+    } else {
+      self.name.clone()
+    };
+    let state = format!("[{}]", self.state);
+    let mut used = text::display_width(&name);
+    let mut parts = Vec::new();
+    if columns.icons {
+      let platform = match self.platform {
+        Platform::Android => ICON_ANDROID,
+        Platform::Ios => ICON_APPLE,
+      };
+      let state = match self.state_kind {
+        StateKind::Booted => ICON_BOOTED,
+        StateKind::Shutdown | StateKind::Broken => ICON_SHUTDOWN,
+        StateKind::Other => ICON_OTHER_STATE,
+      };
+      parts.extend([
+        (Some(Part::Platform(self.platform)), platform.to_string()),
+        (None, " ".to_string()),
+        (Some(Part::State(self.state_kind)), state.to_string()),
+        (None, " ".to_string()),
+      ]);
+    }
+    parts.push((Some(Part::Name), name));
+    if let Some(d) = disambiguator {
+      used += text::display_width(&d) + 1;
+      parts.push((None, " ".to_string()));
+      parts.push((Some(Part::Disambiguator), d));
+    }
+    let state_pad = columns.state.saturating_sub(text::display_width(&state));
+    parts.extend([
+      (
+        None,
+        " ".repeat(columns.name.saturating_sub(used)) + COLUMN_GAP,
+      ),
+      (Some(Part::State(self.state_kind)), state),
+      (None, " ".repeat(state_pad) + COLUMN_GAP),
+      (Some(Part::Meta), format!("({})", self.meta)),
+    ]);
+    parts
+  }
+
+  /// `parts` joined, each styled part passed through `style`. Names are
+  /// kept whole; one longer than its column pushes the rest of the row
+  pub fn aligned_line_with(
+    &self,
+    columns: &Columns,
+    style: impl Fn(Part, &str) -> String,
+  ) -> String {
+    self
+      .parts_with(columns, false)
+      .iter()
+      .map(|(part, text)| match part {
+        Some(part) => style(*part, text),
+        None => text.clone(),
+      })
+      .collect()
+  }
+
+  /// `Pixel 7 [Booted] (AVD)`, with the disambiguator after the name
+  pub fn line(&self) -> String {
+    self.line_with(|_, text| text.to_string())
+  }
+
+  /// `line` with each part passed through `style`, which may wrap it (in
+  /// color codes, say) but should keep its text
+  pub fn line_with(&self, style: impl Fn(Part, &str) -> String) -> String {
+    let name = style(Part::Name, &self.name);
+    let state = style(Part::State(self.state_kind), &format!("[{}]", self.state));
+    let meta = style(Part::Meta, &format!("({})", self.meta));
+    match &self.disambiguator {
+      Some(d) => {
+        let d = style(Part::Disambiguator, &format!("<{}>", d));
+        format!("{} {} {} {}", name, d, state, meta)
+      }
+      None => format!("{} {} {}", name, state, meta),
+    }
+  }
+}
+
+fn disambiguator(entry: &EmulatorEntry) -> Option<String> {
+  match entry {
+    EmulatorEntry::SectionHeader(_) => None,
+    EmulatorEntry::Android(e) => Some(e.id.clone()),
+    EmulatorEntry::IOS(s) => Some(s.udid.chars().take(SHORT_UDID_LEN).collect()),
+  }
+}
+
+/// Views for a whole list, aligned with `entries` (`None` for headers).
+/// Devices that would otherwise print identically get a disambiguator; a
+/// device listed twice (under Recent too) doesn't count as two.
+pub fn views(entries: &[EmulatorEntry]) -> Vec<Option<DeviceView>> {
+  let mut views: Vec<Option<DeviceView>> = entries.iter().map(DeviceView::of).collect();
+  let mut seen: HashMap<(String, String), HashSet<Option<EntryKey>>> = HashMap::new();
+  for (view, entry) in views.iter().zip(entries) {
+    if let Some(view) = view {
+      seen
+        .entry((text::fold(&view.name), view.meta.clone()))
+        .or_default()
+        .insert(entry.key());
+    }
+  }
+  for (view, entry) in views.iter_mut().zip(entries) {
+    if let Some(view) = view {
+      if seen[&(text::fold(&view.name), view.meta.clone())].len() > 1 {
+        view.disambiguator = disambiguator(entry);
+      }
+    }
+  }
+  views
+}
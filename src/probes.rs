@@ -0,0 +1,119 @@
+//! Tools recently looked for and not found.
+//!
+//! On a machine without the Android SDK every listing would otherwise run
+//! `which emulator` and `which adb` again. A miss is remembered for the
+//! rest of the process, and in the data dir for `PROBE_TTL` as long as
+//! PATH and the SDK variables haven't changed. `--reprobe` and
+//! `config show` clear it.
+
+use crate::config;
+use crate::filelock::{self, FileLock};
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const PROBES_FILE: &str = "probes.json";
+
+// How long a tool that wasn't found is assumed to still be missing
+const PROBE_TTL: Duration = Duration::from_secs(600);
+
+// Misses seen by this process, checked before the file
+static MISSING: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ProbeCache {
+  /// PATH and the SDK variables when the misses were recorded; a change
+  /// makes them stale
+  fingerprint: String,
+  /// Tool name to the Unix time it was last not found
+  missing: HashMap<String, u64>,
+}
+
+fn probes_path() -> Option<PathBuf> {
+  Some(config::data_dir()?.join(PROBES_FILE))
+}
+
+fn fingerprint() -> String {
+  std::iter::once("PATH")
+    .chain(config::SDK_ROOT_VARS)
+    .map(|var| {
+      std::env::var_os(var)
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned()
+    })
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+fn now_secs() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map_or(0, |d| d.as_secs())
+}
+
+fn load() -> Option<ProbeCache> {
+  let contents = std::fs::read_to_string(probes_path()?).ok()?;
+  serde_json::from_str(&contents).ok()
+}
+
+/// Whether `tool` was looked for on PATH recently and not found
+pub fn recently_missing(tool: &str) -> bool {
+  let mut missing = MISSING.lock().unwrap_or_else(|e| e.into_inner());
+  let missing = missing.get_or_insert_with(HashSet::new);
+  if missing.contains(tool) {
+    return true;
+  }
+  let fresh = load().is_some_and(|cache| {
+    cache.fingerprint == fingerprint()
+      && cache
+        .missing
+        .get(tool)
+        .is_some_and(|&at| now_secs().saturating_sub(at) < PROBE_TTL.as_secs())
+  });
+  if fresh {
+    missing.insert(tool.to_string());
+  }
+  fresh
+}
+
+/// Remember that `tool` isn't on PATH. Failing to write the file only
+/// costs a probe next time, so errors are dropped.
+pub fn remember_missing(tool: &str) {
+  MISSING
+    .lock()
+    .unwrap_or_else(|e| e.into_inner())
+    .get_or_insert_with(HashSet::new)
+    .insert(tool.to_string());
+  let Some(path) = probes_path() else {
+    return;
+  };
+  if let Some(dir) = path.parent() {
+    if std::fs::create_dir_all(dir).is_err() {
+      return;
+    }
+  }
+  let Ok(_lock) = FileLock::acquire(&path) else {
+    return;
+  };
+  let fingerprint = fingerprint();
+  let mut cache = load()
+    .filter(|cache| cache.fingerprint == fingerprint)
+    .unwrap_or_default();
+  cache.fingerprint = fingerprint;
+  cache.missing.insert(tool.to_string(), now_secs());
+  if let Ok(contents) = serde_json::to_string(&cache) {
+    let _ = filelock::write_atomic(&path, &contents);
+  }
+}
+
+/// Forget every miss, so tools are looked for again
+pub fn clear() {
+  *MISSING.lock().unwrap_or_else(|e| e.into_inner()) = None;
+  if let Some(path) = probes_path() {
+    let _ = std::fs::remove_file(path);
+  }
+}
@@ -0,0 +1,72 @@
+//! Order of boots when several emulators are opened at once.
+//!
+//! Emulators booting together fight over disk and CPU, so at most `limit`
+//! boot at a time: the next one starts once the oldest booting one has
+//! finished or given up. The queue only decides what happens next; the
+//! caller launches, waits and reports back.
+
+/// What the caller should do next
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+  /// Launch the device at this index
+  Start(usize),
+  /// Wait for the device at this index to finish booting, to free a slot
+  Wait(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Slot {
+  Queued,
+  Booting,
+  Done,
+}
+
+#[derive(Debug, Clone)]
+pub struct BootQueue {
+  slots: Vec<Slot>,
+  /// Most devices booting at once; `None` starts them all
+  limit: Option<usize>,
+}
+
+impl BootQueue {
+  pub fn new(len: usize, limit: Option<usize>) -> Self {
+    BootQueue {
+      slots: vec![Slot::Queued; len],
+      limit: limit.map(|l| l.max(1)),
+    }
+  }
+
+  /// Whether any device has to wait for another before it starts
+  pub fn is_limited(&self) -> bool {
+    self.limit.is_some_and(|l| self.slots.len() > l)
+  }
+
+  /// The next thing to do, or `None` once every device has started. Devices
+  /// still booting then are the caller's to wait for or not.
+  pub fn next(&self) -> Option<Step> {
+    let queued = self.slots.iter().position(|&s| s == Slot::Queued)?;
+    let booting = self.slots.iter().filter(|&&s| s == Slot::Booting).count();
+    if self.limit.is_none_or(|l| booting < l) {
+      return Some(Step::Start(queued));
+    }
+    let oldest = self.slots.iter().position(|&s| s == Slot::Booting)?;
+    Some(Step::Wait(oldest))
+  }
+
+  /// The device was launched and is still booting
+  pub fn booting(&mut self, index: usize) {
+    self.slots[index] = Slot::Booting;
+  }
+
+  /// The device booted, was already running, failed to launch or timed out
+  pub fn done(&mut self, index: usize) {
+    self.slots[index] = Slot::Done;
+  }
+
+  /// Indices of the devices still booting, oldest first
+  pub fn still_booting(&self) -> Vec<usize> {
+    (0..self.slots.len())
+      .filter(|&i| self.slots[i] == Slot::Booting)
+      .collect()
+  }
+}
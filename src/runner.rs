@@ -0,0 +1,231 @@
+//! Running external commands: every `std::process::Command` goes through
+//! here so `--verbose` can echo it, and simctl gets timeouts and retries.
+
+// simctl's timeouts and retries are unused off macOS
+#![cfg_attr(not(target_os = "macos"), allow(dead_code))]
+
+use crate::config;
+
+use std::fmt;
+use std::io::{self, Read};
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+const DEFAULT_SIMCTL_TIMEOUT: Duration = Duration::from_secs(60);
+const DEFAULT_SIMCTL_RETRIES: u32 = 1;
+
+// Pause before retrying, giving e.g. CoreSimulatorService time to come back
+const RETRY_BACKOFF: Duration = Duration::from_secs(1);
+
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+// Set once from `--timeout`; takes precedence over the config file
+static TIMEOUT_OVERRIDE: OnceLock<Duration> = OnceLock::new();
+
+/// Use this timeout for external commands instead of the configured one
+pub fn set_timeout_override(timeout: Duration) {
+  let _ = TIMEOUT_OVERRIDE.set(timeout);
+}
+
+/// The `--timeout` value, if one was given
+pub fn timeout_override() -> Option<Duration> {
+  TIMEOUT_OVERRIDE.get().copied()
+}
+
+// Set once from `--verbose`
+static VERBOSE: OnceLock<bool> = OnceLock::new();
+
+/// Echo every command run from now on to stderr
+pub fn set_verbose() {
+  let _ = VERBOSE.set(true);
+}
+
+pub fn verbose() -> bool {
+  VERBOSE.get().copied().unwrap_or(false)
+}
+
+/// The command line as it would be typed, quoting arguments with spaces
+fn command_line(command: &Command) -> String {
+  std::iter::once(command.get_program())
+    .chain(command.get_args())
+    .map(|arg| {
+      let arg = arg.to_string_lossy();
+      if arg.is_empty() || arg.contains(char::is_whitespace) {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+      } else {
+        arg.into_owned()
+      }
+    })
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+/// Under `--verbose`, print a command and how it ended on stderr (never
+/// stdout, so `list --json` stays parseable)
+pub fn trace(command: &Command, outcome: impl fmt::Display) {
+  if verbose() {
+    eprintln!("+ {}  ({})", command_line(command), outcome);
+  }
+}
+
+/// Trace how a command ended, under `--verbose` and in the
+/// `EMULAUNCH_TRACE` file with how long it ran
+fn trace_result<T>(
+  command: &Command,
+  started: Instant,
+  result: &io::Result<T>,
+  status: impl Fn(&T) -> String,
+) {
+  if !verbose() && !crate::trace::enabled() {
+    return;
+  }
+  let outcome = match result {
+    Ok(value) => status(value),
+    Err(e) if e.kind() == io::ErrorKind::TimedOut => e.to_string(),
+    Err(e) => format!("failed to start: {}", e),
+  };
+  crate::trace::record(|| crate::trace::TraceEvent::Command {
+    command: command_line(command),
+    ms: crate::trace::millis(started.elapsed()),
+    outcome: outcome.clone(),
+  });
+  trace(command, outcome);
+}
+
+/// `Command::output`, traced
+pub fn output(command: &mut Command) -> io::Result<Output> {
+  let started = Instant::now();
+  let result = command.output();
+  trace_result(command, started, &result, |o| o.status.to_string());
+  result
+}
+
+/// `Command::status`, traced
+pub fn status(command: &mut Command) -> io::Result<ExitStatus> {
+  let started = Instant::now();
+  let result = command.status();
+  trace_result(command, started, &result, ExitStatus::to_string);
+  result
+}
+
+/// `Command::spawn`, traced when it starts; the exit isn't waited for
+pub fn spawn(command: &mut Command) -> io::Result<Child> {
+  let started = Instant::now();
+  let result = command.spawn();
+  trace_result(command, started, &result, |c| {
+    format!("started, pid {}", c.id())
+  });
+  result
+}
+
+/// How long one invocation may take and how often a failure is retried
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+  pub timeout: Duration,
+  pub retries: u32,
+  pub backoff: Duration,
+}
+
+impl RetryPolicy {
+  /// Policy for `xcrun simctl`, from `simctl_timeout_secs` / `simctl_retries`
+  pub fn simctl() -> Self {
+    let cfg = config::load_config();
+    let timeout = timeout_override().unwrap_or_else(|| {
+      cfg
+        .as_ref()
+        .and_then(|c| c.simctl_timeout_secs)
+        .map_or(DEFAULT_SIMCTL_TIMEOUT, Duration::from_secs)
+    });
+    RetryPolicy {
+      timeout,
+      retries: cfg
+        .as_ref()
+        .and_then(|c| c.simctl_retries)
+        .unwrap_or(DEFAULT_SIMCTL_RETRIES),
+      backoff: RETRY_BACKOFF,
+    }
+  }
+}
+
+/// Run `attempt` until it succeeds, at most `1 + policy.retries` times.
+///
+/// Returns the first success or the last failure; failures followed by a
+/// success are treated as transient and not reported.
+pub fn with_retry<T, E>(
+  policy: &RetryPolicy,
+  mut attempt: impl FnMut() -> Result<T, E>,
+) -> Result<T, E> {
+  let mut remaining = policy.retries;
+  loop {
+    match attempt() {
+      Ok(value) => return Ok(value),
+      Err(e) if remaining == 0 => return Err(e),
+      Err(_) => {
+        remaining -= 1;
+        std::thread::sleep(policy.backoff);
+      }
+    }
+  }
+}
+
+/// Like `Command::output`, but kill the process and fail with `TimedOut` if
+/// it runs longer than `timeout`
+pub fn output_with_timeout(command: &mut Command, timeout: Duration) -> io::Result<Output> {
+  let started = Instant::now();
+  let result = run_with_timeout(command, timeout);
+  trace_result(command, started, &result, |o| o.status.to_string());
+  result
+}
+
+fn run_with_timeout(command: &mut Command, timeout: Duration) -> io::Result<Output> {
+  let mut child = command
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()?;
+
+  // Drain both pipes on threads so a chatty child can't block on a full pipe
+  let drain = |pipe: Option<Box<dyn Read + Send>>| {
+    std::thread::spawn(move || {
+      let mut buf = Vec::new();
+      if let Some(mut pipe) = pipe {
+        let _ = pipe.read_to_end(&mut buf);
+      }
+      buf
+    })
+  };
+  let stdout = drain(
+    child
+      .stdout
+      .take()
+      .map(|p| Box::new(p) as Box<dyn Read + Send>),
+  );
+  let stderr = drain(
+    child
+      .stderr
+      .take()
+      .map(|p| Box::new(p) as Box<dyn Read + Send>),
+  );
+
+  let deadline = Instant::now() + timeout;
+  let status = loop {
+    if let Some(status) = child.try_wait()? {
+      break status;
+    }
+    if Instant::now() >= deadline {
+      let _ = child.kill();
+      let _ = child.wait();
+      return Err(io::Error::new(
+        io::ErrorKind::TimedOut,
+        format!("timed out after {}s", timeout.as_secs()),
+      ));
+    }
+    std::thread::sleep(WAIT_POLL_INTERVAL);
+  };
+
+  Ok(Output {
+    status,
+    stdout: stdout.join().unwrap_or_default(),
+    stderr: stderr.join().unwrap_or_default(),
+  })
+}
@@ -0,0 +1,284 @@
+use crate::emulators::{self, EmulatorList, EntryKey, Platform};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Bumped when requests or responses change incompatibly; clients send the
+/// version they speak in `initialize`
+pub const PROTOCOL_VERSION: u32 = 1;
+
+const METHODS: &[&str] = &["initialize", "list", "open", "stop", "status", "subscribe"];
+
+// How often subscribed sessions re-list devices to detect state changes
+const SUBSCRIBE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+// JSON-RPC 2.0 error codes, plus LSP's "not initialized"
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const NOT_INITIALIZED: i64 = -32002;
+// A core operation (list, open, ...) failed; the message is the CLI's error
+const COMMAND_FAILED: i64 = -32000;
+
+/// An error response: a JSON-RPC code and a human-readable message
+#[derive(Debug)]
+struct RpcError {
+  code: i64,
+  message: String,
+}
+
+impl RpcError {
+  fn new(code: i64, message: impl Into<String>) -> Self {
+    RpcError {
+      code,
+      message: message.into(),
+    }
+  }
+}
+
+// Errors from the shared core functions are plain strings
+impl From<String> for RpcError {
+  fn from(message: String) -> Self {
+    RpcError::new(COMMAND_FAILED, message)
+  }
+}
+
+#[derive(Deserialize)]
+struct Request {
+  #[serde(default)]
+  id: Value,
+  method: String,
+  #[serde(default)]
+  params: Value,
+}
+
+#[derive(Deserialize)]
+struct InitializeParams {
+  protocol: u32,
+}
+
+#[derive(Deserialize)]
+struct DeviceParams {
+  name: String,
+  #[serde(default)]
+  with_paired: bool,
+  #[serde(default)]
+  cold_boot: bool,
+  #[serde(default)]
+  fullscreen: bool,
+  #[serde(default)]
+  hide_window: bool,
+  #[serde(default)]
+  headless: bool,
+  #[serde(default)]
+  wipe_data: bool,
+  /// Required with `wipe_data`, which has no prompt here
+  #[serde(default)]
+  confirm: bool,
+  #[serde(default)]
+  no_gui: bool,
+}
+
+fn parse_params<T: for<'de> Deserialize<'de>>(params: Value) -> Result<T, RpcError> {
+  serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+/// Writes one JSON message per line; shared with the subscription thread
+#[derive(Clone)]
+struct Output(Arc<Mutex<io::Stdout>>);
+
+impl Output {
+  fn send(&self, message: &Value) {
+    let mut out = self.0.lock().unwrap_or_else(|e| e.into_inner());
+    let _ = writeln!(out, "{}", message);
+    let _ = out.flush();
+  }
+
+  fn respond(&self, id: Value, result: Result<Value, RpcError>) {
+    self.send(&match result {
+      Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+      Err(e) => json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": e.code, "message": e.message },
+      }),
+    });
+  }
+}
+
+/// One `stateChanged` notification per device whose state differs between
+/// two listings; `state` is null for devices that disappeared
+fn state_changes(before: &EmulatorList, after: &EmulatorList) -> Vec<Value> {
+  let old: HashMap<EntryKey, &str> = before
+    .states()
+    .into_iter()
+    .map(|(key, _, state)| (key, state))
+    .collect();
+  let current = after.states();
+
+  let mut changes: Vec<Value> = current
+    .iter()
+    .filter(|(key, _, state)| old.get(key) != Some(state))
+    .map(|(key, name, state)| {
+      json!({ "device": key, "name": name, "state": state, "previous": old.get(key) })
+    })
+    .collect();
+  changes.extend(
+    before
+      .states()
+      .into_iter()
+      .filter(|(key, _, _)| !current.iter().any(|(k, _, _)| k == key))
+      .map(|(key, name, state)| {
+        json!({ "device": key, "name": name, "state": null, "previous": state })
+      }),
+  );
+  changes
+    .into_iter()
+    .map(|params| json!({ "jsonrpc": "2.0", "method": "stateChanged", "params": params }))
+    .collect()
+}
+
+/// Poll the device lists and push a notification for every state change
+fn spawn_subscription(output: Output) {
+  std::thread::spawn(move || {
+    let mut last = emulators::collect_emulator_list(Platform::supported());
+    loop {
+      std::thread::sleep(SUBSCRIBE_POLL_INTERVAL);
+      let current = emulators::collect_emulator_list(Platform::supported());
+      for notification in state_changes(&last, &current) {
+        output.send(&notification);
+      }
+      last = current;
+    }
+  });
+}
+
+/// Per-connection state
+struct Session {
+  output: Output,
+  initialized: bool,
+  subscribed: bool,
+}
+
+impl Session {
+  fn handle(&mut self, request: Request) -> Result<Value, RpcError> {
+    if request.method != "initialize" && !self.initialized {
+      return Err(RpcError::new(
+        NOT_INITIALIZED,
+        "Send initialize before other requests",
+      ));
+    }
+
+    match request.method.as_str() {
+      "initialize" => {
+        let params: InitializeParams = parse_params(request.params)?;
+        if params.protocol != PROTOCOL_VERSION {
+          return Err(RpcError::new(
+            INVALID_REQUEST,
+            format!(
+              "Unsupported protocol version {} (server speaks {})",
+              params.protocol, PROTOCOL_VERSION
+            ),
+          ));
+        }
+        self.initialized = true;
+        Ok(json!({
+          "protocol": PROTOCOL_VERSION,
+          "server": env!("CARGO_PKG_NAME"),
+          "version": env!("CARGO_PKG_VERSION"),
+          "methods": METHODS,
+        }))
+      }
+      "list" => Ok(json!(emulators::list_document(
+        &emulators::collect_emulator_list(Platform::supported())
+      ))),
+      "open" => {
+        let params: DeviceParams = parse_params(request.params)?;
+        let options = emulators::LaunchOptions {
+          with_paired: params.with_paired,
+          cold_boot: params.cold_boot,
+          fullscreen: params.fullscreen,
+          hide_window: params.hide_window,
+          headless: params.headless,
+          wipe_data: params.wipe_data,
+          no_gui: params.no_gui,
+        };
+        let key = if params.wipe_data {
+          if !params.confirm {
+            return Err(RpcError::new(
+              INVALID_PARAMS,
+              "wipe_data erases the device and can't be undone; pass \"confirm\": true",
+            ));
+          }
+          // Only an exact name or id, never a guess
+          emulators::find_emulator_exact(&params.name)?
+        } else {
+          emulators::find_emulator(&params.name)?
+        };
+        let message = emulators::open_key(&key, &options)?;
+        Ok(json!({ "message": message }))
+      }
+      "stop" => {
+        let params: DeviceParams = parse_params(request.params)?;
+        let key = emulators::resolve_device(&params.name)?;
+        Ok(json!({ "message": emulators::stop_device(&key)? }))
+      }
+      "status" => {
+        let params: DeviceParams = parse_params(request.params)?;
+        let key = emulators::resolve_device(&params.name)?;
+        let state = emulators::device_state(&key)?;
+        Ok(json!({ "device": key, "state": state }))
+      }
+      "subscribe" => {
+        if !self.subscribed {
+          self.subscribed = true;
+          spawn_subscription(self.output.clone());
+        }
+        Ok(json!({ "interval_ms": SUBSCRIBE_POLL_INTERVAL.as_millis() as u64 }))
+      }
+      other => Err(RpcError::new(
+        METHOD_NOT_FOUND,
+        format!("Unknown method '{}'", other),
+      )),
+    }
+  }
+}
+
+/// Serve newline-delimited JSON-RPC requests on stdin until it is closed
+pub fn run_stdio() -> Result<(), String> {
+  let mut session = Session {
+    output: Output(Arc::new(Mutex::new(io::stdout()))),
+    initialized: false,
+    subscribed: false,
+  };
+
+  for line in io::stdin().lock().lines() {
+    let line = line.map_err(|e| format!("Failed to read stdin: {}", e))?;
+    if line.trim().is_empty() {
+      continue;
+    }
+    let request: Request = match serde_json::from_str(&line) {
+      Ok(request) => request,
+      Err(e) => {
+        let code = if serde_json::from_str::<Value>(&line).is_ok() {
+          INVALID_REQUEST
+        } else {
+          PARSE_ERROR
+        };
+        session
+          .output
+          .respond(Value::Null, Err(RpcError::new(code, e.to_string())));
+        continue;
+      }
+    };
+    let id = request.id.clone();
+    let result = session.handle(request);
+    session.output.respond(id, result);
+  }
+  Ok(())
+}
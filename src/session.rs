@@ -0,0 +1,70 @@
+//! The TUI's state between runs (`restore_session = true`).
+//!
+//! Written to the data dir when the picker closes and applied once the
+//! first listing is in. A file from another schema version, or one that
+//! doesn't parse, is ignored rather than reported.
+
+use crate::config;
+use crate::emulators::EntryKey;
+use crate::filelock;
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+const SESSION_FILE: &str = "session.json";
+
+// Bump when a field changes meaning; older files are then ignored
+const SESSION_VERSION: u32 = 2;
+
+/// What the picker restores on the next run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionState {
+  pub version: u32,
+  /// Filter text as typed
+  #[serde(default)]
+  pub filter: String,
+  /// The device that was selected; dropped on restore if it's gone
+  #[serde(default)]
+  pub selected: Option<EntryKey>,
+  /// Sort order by its config name (`state`, `name`, `recent`)
+  #[serde(default)]
+  pub sort: Option<String>,
+  /// Hidden and collapsed sections by their config names (`android`, `ios`,
+  /// `recent`); these replace the config's `hide_*` and `collapsed_sections`
+  #[serde(default)]
+  pub hidden: Vec<String>,
+  #[serde(default)]
+  pub collapsed: Vec<String>,
+}
+
+impl SessionState {
+  pub fn new(filter: String, selected: Option<EntryKey>) -> Self {
+    SessionState {
+      version: SESSION_VERSION,
+      filter,
+      selected,
+      ..SessionState::default()
+    }
+  }
+}
+
+fn session_path() -> Option<PathBuf> {
+  Some(config::data_dir()?.join(SESSION_FILE))
+}
+
+/// The saved session, if there is one of this version
+pub fn load() -> Option<SessionState> {
+  let contents = std::fs::read_to_string(session_path()?).ok()?;
+  let state: SessionState = serde_json::from_str(&contents).ok()?;
+  (state.version == SESSION_VERSION).then_some(state)
+}
+
+pub fn save(state: &SessionState) -> Result<(), String> {
+  let path = session_path().ok_or_else(|| config::ERR_NO_HOME.to_string())?;
+  if let Some(dir) = path.parent() {
+    std::fs::create_dir_all(dir)
+      .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+  }
+  let contents = serde_json::to_string(state).map_err(|e| e.to_string())?;
+  filelock::write_atomic(&path, &contents)
+}
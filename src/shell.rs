@@ -0,0 +1,206 @@
+use crate::{emulators, run_command, Cli, CommandError};
+
+use clap::{CommandFactory, Parser};
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use std::cell::OnceCell;
+
+const PROMPT: &str = "emulaunch> ";
+
+// Words that end the session
+const EXIT_WORDS: &[&str] = &["exit", "quit"];
+
+/// Completes subcommand names, then device names
+struct ShellHelper {
+  subcommands: Vec<String>,
+  // Listed on first use so the prompt appears without waiting on adb/simctl
+  device_names: OnceCell<Vec<String>>,
+}
+
+impl ShellHelper {
+  fn new() -> Self {
+    let mut subcommands: Vec<String> = Cli::command()
+      .get_subcommands()
+      .map(|c| c.get_name().to_string())
+      .filter(|name| name != "shell")
+      .collect();
+    subcommands.extend(EXIT_WORDS.iter().map(|w| w.to_string()));
+
+    ShellHelper {
+      subcommands,
+      device_names: OnceCell::new(),
+    }
+  }
+
+  fn device_names(&self) -> &[String] {
+    self.device_names.get_or_init(|| {
+      emulators::collect_sections(true)
+        .into_iter()
+        .flat_map(|(_, entries)| entries)
+        .map(|e| e.display_name().to_string())
+        .collect()
+    })
+  }
+}
+
+impl Completer for ShellHelper {
+  type Candidate = Pair;
+
+  fn complete(
+    &self,
+    line: &str,
+    pos: usize,
+    _ctx: &Context<'_>,
+  ) -> rustyline::Result<(usize, Vec<Pair>)> {
+    let before = &line[..pos];
+    let start = before
+      .char_indices()
+      .rfind(|(_, c)| c.is_whitespace())
+      .map_or(0, |(i, c)| i + c.len_utf8());
+    let partial = before[start..].trim_start_matches(['"', '\'']);
+    let first_word = before[..start].trim().is_empty();
+
+    let candidates = if first_word {
+      self
+        .subcommands
+        .iter()
+        .filter(|name| name.starts_with(partial))
+        .map(|name| Pair {
+          display: name.clone(),
+          replacement: format!("{} ", name),
+        })
+        .collect()
+    } else {
+      let partial = partial.to_lowercase();
+      self
+        .device_names()
+        .iter()
+        .filter(|name| name.to_lowercase().starts_with(&partial))
+        .map(|name| Pair {
+          display: name.clone(),
+          replacement: if name.contains(char::is_whitespace) {
+            format!("\"{}\"", name)
+          } else {
+            name.clone()
+          },
+        })
+        .collect()
+    };
+
+    Ok((start, candidates))
+  }
+}
+
+impl Hinter for ShellHelper {
+  type Hint = String;
+}
+
+impl Highlighter for ShellHelper {}
+
+impl Validator for ShellHelper {}
+
+impl Helper for ShellHelper {}
+
+/// Split a line into words, honoring single/double quotes and backslash escapes
+fn split_words(line: &str) -> Result<Vec<String>, String> {
+  let mut words = Vec::new();
+  let mut current = String::new();
+  let mut in_word = false;
+  let mut quote: Option<char> = None;
+  let mut chars = line.chars();
+
+  while let Some(c) = chars.next() {
+    match (quote, c) {
+      (Some(q), c) if c == q => quote = None,
+      (Some('"'), '\\') | (None, '\\') => {
+        if let Some(escaped) = chars.next() {
+          current.push(escaped);
+        }
+        in_word = true;
+      }
+      (Some(_), c) => current.push(c),
+      (None, '"' | '\'') => {
+        quote = Some(c);
+        in_word = true;
+      }
+      (None, c) if c.is_whitespace() => {
+        if in_word {
+          words.push(std::mem::take(&mut current));
+          in_word = false;
+        }
+      }
+      (None, c) => {
+        current.push(c);
+        in_word = true;
+      }
+    }
+  }
+
+  if quote.is_some() {
+    return Err("Unterminated quote".to_string());
+  }
+  if in_word {
+    words.push(current);
+  }
+  Ok(words)
+}
+
+/// Run the interactive `emulaunch shell` prompt until `exit` or Ctrl+D
+pub fn run() -> Result<(), String> {
+  let mut editor: Editor<ShellHelper, rustyline::history::DefaultHistory> =
+    Editor::new().map_err(|e| format!("Failed to start shell: {}", e))?;
+  editor.set_helper(Some(ShellHelper::new()));
+
+  loop {
+    let line = match editor.readline(PROMPT) {
+      Ok(line) => line,
+      // Ctrl+C discards the current line, like a regular shell
+      Err(ReadlineError::Interrupted) => continue,
+      Err(ReadlineError::Eof) => break,
+      Err(e) => return Err(format!("Failed to read input: {}", e)),
+    };
+
+    let words = match split_words(&line) {
+      Ok(words) => words,
+      Err(e) => {
+        eprintln!("Error: {}", e);
+        continue;
+      }
+    };
+    let Some(first) = words.first() else {
+      continue;
+    };
+    let _ = editor.add_history_entry(line.as_str());
+    if EXIT_WORDS.contains(&first.as_str()) {
+      break;
+    }
+
+    let args = std::iter::once("emulaunch".to_string()).chain(words);
+    match Cli::try_parse_from(args) {
+      Ok(Cli {
+        command: Some(command),
+        reprobe,
+        ..
+      }) => {
+        if reprobe {
+          crate::probes::clear();
+        }
+        match run_command(command) {
+          Ok(()) | Err(CommandError::Exit(_)) => {}
+          Err(CommandError::Message(e)) => eprintln!("Error: {}", e),
+        }
+      }
+      Ok(Cli { command: None, .. }) => {}
+      // Covers --help and --version too, which clap reports as errors
+      Err(e) => {
+        let _ = e.print();
+      }
+    }
+  }
+
+  Ok(())
+}
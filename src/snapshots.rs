@@ -0,0 +1,142 @@
+use crate::console;
+use crate::emulators;
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// Quick-boot state the emulator saves on exit; not a user-named snapshot
+const DEFAULT_BOOT_SNAPSHOT: &str = "default_boot";
+
+// Written by the emulator into every snapshot directory
+const SNAPSHOT_METADATA_FILE: &str = "snapshot.pb";
+
+/// A named snapshot of an AVD
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+  pub name: String,
+  /// Total size of the snapshot files, if they could all be read
+  pub size_bytes: Option<u64>,
+  pub created: Option<SystemTime>,
+}
+
+fn snapshots_dir(avd_id: &str) -> Result<PathBuf, String> {
+  Ok(emulators::avd_dir(avd_id)?.join("snapshots"))
+}
+
+/// Whether a snapshot name is a plain directory name (no path components)
+/// that the emulator won't mistake for a flag
+pub fn is_valid_name(name: &str) -> bool {
+  !name.is_empty()
+    && name != "."
+    && name != ".."
+    && !name.starts_with('-')
+    && !name
+      .chars()
+      .any(|c| c == '/' || c == '\\' || c.is_control())
+}
+
+fn dir_size(dir: &Path) -> Option<u64> {
+  let mut total = 0;
+  for entry in std::fs::read_dir(dir).ok()? {
+    let entry = entry.ok()?;
+    let metadata = entry.metadata().ok()?;
+    total += if metadata.is_dir() {
+      dir_size(&entry.path())?
+    } else {
+      metadata.len()
+    };
+  }
+  Some(total)
+}
+
+/// Named snapshots of an AVD, newest first.
+///
+/// Only directory names and modification times are used, so snapshots with
+/// unreadable metadata are still listed (without size or date).
+pub fn list(avd_id: &str) -> Result<Vec<Snapshot>, String> {
+  let dir = snapshots_dir(avd_id)?;
+  let entries = match std::fs::read_dir(&dir) {
+    Ok(entries) => entries,
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+    Err(e) => return Err(format!("Cannot read {}: {}", dir.display(), e)),
+  };
+
+  let mut snapshots: Vec<Snapshot> = entries
+    .flatten()
+    .filter(|e| e.path().is_dir())
+    .filter_map(|e| {
+      let name = e.file_name().to_str()?.to_string();
+      if name == DEFAULT_BOOT_SNAPSHOT {
+        return None;
+      }
+      let path = e.path();
+      let created = std::fs::metadata(path.join(SNAPSHOT_METADATA_FILE))
+        .or_else(|_| e.metadata())
+        .and_then(|m| m.modified())
+        .ok();
+      Some(Snapshot {
+        name,
+        size_bytes: dir_size(&path),
+        created,
+      })
+    })
+    .collect();
+
+  snapshots.sort_by(|a, b| b.created.cmp(&a.created).then_with(|| a.name.cmp(&b.name)));
+  Ok(snapshots)
+}
+
+/// Delete a snapshot: through the console when the emulator is running (so
+/// it doesn't keep using the files), otherwise by removing its directory
+pub fn delete(avd_id: &str, name: &str, serial: Option<&str>) -> Result<String, String> {
+  if !is_valid_name(name) || name == DEFAULT_BOOT_SNAPSHOT {
+    return Err(format!("Invalid snapshot name '{}'", name));
+  }
+  match serial {
+    // The console splits commands on whitespace
+    Some(_) if name.contains(char::is_whitespace) => {
+      return Err(format!(
+        "Cannot delete snapshot '{}' while the emulator is running",
+        name
+      ));
+    }
+    Some(serial) => {
+      console::run_command(serial, &format!("avd snapshot delete {}", name))?;
+    }
+    None => {
+      let dir = snapshots_dir(avd_id)?.join(name);
+      std::fs::remove_dir_all(&dir)
+        .map_err(|e| format!("Failed to delete {}: {}", dir.display(), e))?;
+    }
+  }
+  Ok(format!("Deleted snapshot {}", name))
+}
+
+/// Human-readable size, e.g. `1.4 GB`
+pub fn format_size(bytes: u64) -> String {
+  const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+  let mut size = bytes as f64;
+  let mut unit = 0;
+  while size >= 1024.0 && unit + 1 < UNITS.len() {
+    size /= 1024.0;
+    unit += 1;
+  }
+  if unit == 0 {
+    format!("{} B", bytes)
+  } else {
+    format!("{:.1} {}", size, UNITS[unit])
+  }
+}
+
+/// How long ago a time was, e.g. `5m ago` or `3d ago`
+pub fn format_age(time: SystemTime) -> String {
+  let secs = SystemTime::now()
+    .duration_since(time)
+    .map_or(0, |d| d.as_secs());
+  match secs {
+    0..60 => "just now".to_string(),
+    60..3600 => format!("{}m ago", secs / 60),
+    3600..86400 => format!("{}h ago", secs / 3600),
+    _ => format!("{}d ago", secs / 86400),
+  }
+}
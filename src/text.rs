@@ -0,0 +1,156 @@
+use std::cmp::Ordering;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+// Marks a name cut short by `truncate_to_width`
+const ELLIPSIS: &str = "\u{2026}";
+
+/// Trim and collapse runs of whitespace (tabs included) to one space, so a
+/// name stored with stray spacing equals the same name typed normally
+pub fn normalize_whitespace(s: &str) -> String {
+  s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Case-folded, NFC-normalized form used for name matching, so composed
+/// and decomposed spellings of the same name (`Ö` vs `O` + `¨`) match.
+/// Whitespace is normalized first.
+pub fn fold(s: &str) -> String {
+  normalize_whitespace(s).to_lowercase().nfc().collect()
+}
+
+/// How well a name matches a query, best first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MatchQuality {
+  /// Equal, ignoring case and normalization form
+  Exact,
+  /// The query appears in the name
+  Substring,
+  /// The query's characters appear in order; fewer gaps between them is better
+  Fuzzy { gaps: usize },
+}
+
+impl MatchQuality {
+  /// Whether two qualities are in the same tier, ignoring the fuzzy score
+  pub fn same_tier(self, other: MatchQuality) -> bool {
+    std::mem::discriminant(&self) == std::mem::discriminant(&other)
+  }
+}
+
+/// How `name` matches a query already passed through `fold`: exact, then
+/// substring, then a subsequence match scored by the number of gaps
+/// (`pixel7` matches `Pixel 7 API 34` with one gap). Whitespace in the
+/// query is ignored for the subsequence match.
+pub fn match_quality(name: &str, folded_query: &str) -> Option<MatchQuality> {
+  let name = fold(name);
+  if name == folded_query {
+    return Some(MatchQuality::Exact);
+  }
+  if name.contains(folded_query) {
+    return Some(MatchQuality::Substring);
+  }
+
+  let mut query = folded_query
+    .chars()
+    .filter(|c| !c.is_whitespace())
+    .peekable();
+  let mut gaps = 0;
+  let mut in_gap = false;
+  let mut started = false;
+  for c in name.chars() {
+    let Some(&next) = query.peek() else {
+      break;
+    };
+    if c == next {
+      query.next();
+      if in_gap && started {
+        gaps += 1;
+      }
+      in_gap = false;
+      started = true;
+    } else {
+      in_gap = true;
+    }
+  }
+  query
+    .peek()
+    .is_none()
+    .then_some(MatchQuality::Fuzzy { gaps })
+}
+
+/// Whether `name` matches a query already passed through `fold` (see
+/// `match_quality`). An empty query matches everything.
+pub fn matches(name: &str, folded_query: &str) -> bool {
+  folded_query.is_empty() || match_quality(name, folded_query).is_some()
+}
+
+/// Primary collation key: decomposed, accents dropped, lowercased
+fn collation_key(s: &str) -> String {
+  s.nfd()
+    .filter(|c| !is_combining_mark(*c))
+    .flat_map(char::to_lowercase)
+    .collect()
+}
+
+/// Locale-independent name ordering: case- and accent-insensitive first
+/// (`Ömer` sorts with `omer`, not after `z`), then by the exact string so
+/// the order is stable
+pub fn collate(a: &str, b: &str) -> Ordering {
+  collation_key(a)
+    .cmp(&collation_key(b))
+    .then_with(|| a.cmp(b))
+}
+
+/// Terminal columns `s` takes: CJK and emoji count two, combining marks
+/// and zero-width joiners none
+pub fn display_width(s: &str) -> usize {
+  s.width()
+}
+
+/// `s` cut to at most `max` columns, ending in `…` when anything was cut.
+/// Cuts fall between grapheme clusters, so a combining mark or an emoji
+/// ZWJ sequence is kept whole or dropped whole.
+pub fn truncate_to_width(s: &str, max: usize) -> String {
+  if display_width(s) <= max {
+    return s.to_string();
+  }
+  let budget = max.saturating_sub(display_width(ELLIPSIS));
+  let mut out = String::new();
+  let mut used = 0;
+  for grapheme in s.graphemes(true) {
+    let width = display_width(grapheme);
+    if used + width > budget {
+      break;
+    }
+    used += width;
+    out.push_str(grapheme);
+  }
+  if max > 0 {
+    out.push_str(ELLIPSIS);
+  }
+  out
+}
+
+/// Rows `line` takes when wrapped at spaces to `width` columns, as a
+/// wrapping `Paragraph` lays it out; words wider than a row are split
+pub fn wrapped_rows(line: &str, width: usize) -> usize {
+  let width = width.max(1);
+  let mut rows = 1;
+  let mut column = 0;
+  for word in line.split(' ') {
+    let word_width = display_width(word);
+    if column > 0 && column + 1 + word_width > width {
+      rows += 1;
+      column = 0;
+    } else if column > 0 {
+      column += 1;
+    }
+    column += word_width;
+    while column > width {
+      rows += 1;
+      column -= width;
+    }
+  }
+  rows
+}
@@ -1,6 +1,7 @@
 use ratatui::style::Color;
 
 use crate::config::ThemeOverrides;
+use crate::present::StateKind;
 
 /// All color slots used by the TUI.
 pub struct ThemeColors {
@@ -12,12 +13,17 @@ pub struct ThemeColors {
   pub meta_fg: Color,
   pub filter_placeholder_fg: Color,
   pub filter_active_fg: Color,
+  /// Border and title of the filter while keys type into it
+  pub filter_mode_fg: Color,
   pub selection_bg: Color,
   pub help_key_fg: Color,
   pub help_text_fg: Color,
+  /// Platform glyphs in front of each device with `icons = true`
+  pub icon_android_fg: Color,
+  pub icon_ios_fg: Color,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum ThemeName {
   Default,
   CatppuccinMocha,
@@ -29,20 +35,53 @@ enum ThemeName {
 }
 
 impl ThemeName {
-  fn from_str(s: &str) -> Self {
+  /// The theme a configured name refers to, if any
+  fn parse(s: &str) -> Option<Self> {
     match s.to_lowercase().replace(' ', "-").as_str() {
-      "catppuccin-mocha" => Self::CatppuccinMocha,
-      "catppuccin-latte" => Self::CatppuccinLatte,
-      "dracula" => Self::Dracula,
-      "tokyo-night" | "tokyonight" => Self::TokyoNight,
-      "gruvbox-dark" | "gruvbox" => Self::GruvboxDark,
-      "nord" => Self::Nord,
-      _ => Self::Default,
+      "default" => Some(Self::Default),
+      "catppuccin-mocha" => Some(Self::CatppuccinMocha),
+      "catppuccin-latte" => Some(Self::CatppuccinLatte),
+      "dracula" => Some(Self::Dracula),
+      "tokyo-night" | "tokyonight" => Some(Self::TokyoNight),
+      "gruvbox-dark" | "gruvbox" => Some(Self::GruvboxDark),
+      "nord" => Some(Self::Nord),
+      _ => None,
     }
   }
+
+  fn from_str(s: &str) -> Self {
+    Self::parse(s).unwrap_or(Self::Default)
+  }
+}
+
+/// Theme names `theme` accepts, as documented
+pub const THEME_NAMES: &[&str] = &[
+  "default",
+  "catppuccin-mocha",
+  "catppuccin-latte",
+  "dracula",
+  "tokyo-night",
+  "gruvbox-dark",
+  "nord",
+];
+
+/// Whether `theme = "<name>"` names a theme rather than falling back to the
+/// default
+pub fn is_known_theme(name: &str) -> bool {
+  ThemeName::parse(name).is_some()
 }
 
-fn parse_hex_color(s: &str) -> Option<Color> {
+/// Position in `THEME_NAMES` of the theme a configured name resolves to
+/// (unknown names fall back to the default, as in `resolve_theme`)
+pub fn theme_index(name: Option<&str>) -> usize {
+  let resolved = name.map_or(ThemeName::Default, ThemeName::from_str);
+  THEME_NAMES
+    .iter()
+    .position(|n| ThemeName::parse(n) == Some(resolved))
+    .unwrap_or(0)
+}
+
+pub fn parse_hex_color(s: &str) -> Option<Color> {
   let s = s.strip_prefix('#')?;
   if s.len() != 6 {
     return None;
@@ -53,6 +92,37 @@ fn parse_hex_color(s: &str) -> Option<Color> {
   Some(Color::Rgb(r, g, b))
 }
 
+/// Warnings for `[theme_overrides]` keys that aren't slots and values that
+/// aren't `#rrggbb`; `resolve_theme` skips both
+pub fn validate_overrides(overrides: &ThemeOverrides) -> Vec<String> {
+  let slots = overrides.slots();
+  let mut warnings: Vec<String> = overrides
+    .unknown
+    .keys()
+    .map(|key| {
+      let valid: Vec<&str> = slots.iter().map(|(name, _)| *name).collect();
+      format!(
+        "theme_overrides: unknown slot '{}' is ignored (valid slots: {})",
+        key,
+        valid.join(", ")
+      )
+    })
+    .collect();
+  warnings.extend(
+    slots
+      .iter()
+      .filter_map(|(name, value)| Some((name, (*value)?)))
+      .filter(|(_, value)| parse_hex_color(value).is_none())
+      .map(|(name, value)| {
+        format!(
+          "theme_overrides: {} = \"{}\" is not a #rrggbb color and is ignored",
+          name, value
+        )
+      }),
+  );
+  warnings
+}
+
 /// Shorthand for 256-color indexed palette.
 const fn c(n: u8) -> Color {
   Color::Indexed(n)
@@ -69,9 +139,12 @@ fn base_theme(name: ThemeName) -> ThemeColors {
       meta_fg: Color::DarkGray,
       filter_placeholder_fg: Color::DarkGray,
       filter_active_fg: Color::White,
+      filter_mode_fg: Color::LightBlue,
       selection_bg: Color::DarkGray,
       help_key_fg: Color::Yellow,
       help_text_fg: Color::White,
+      icon_android_fg: Color::Green,
+      icon_ios_fg: Color::White,
     },
     // https://github.com/catppuccin/catppuccin — Mocha palette
     ThemeName::CatppuccinMocha => ThemeColors {
@@ -83,9 +156,12 @@ fn base_theme(name: ThemeName) -> ThemeColors {
       meta_fg: c(103),          // Overlay0 (#9399b2)
       filter_placeholder_fg: c(103),
       filter_active_fg: c(189), // Text (#cdd6f4)
+      filter_mode_fg: c(183),   // Mauve (#cba6f7)
       selection_bg: c(59),      // Surface1 (#45475a)
       help_key_fg: c(218),      // Pink (#f5c2e7)
       help_text_fg: c(146),     // Subtext0 (#bac2de)
+      icon_android_fg: c(151),  // Green (#a6e3a1)
+      icon_ios_fg: c(189),      // Text (#cdd6f4)
     },
     // https://github.com/catppuccin/catppuccin — Latte palette
     ThemeName::CatppuccinLatte => ThemeColors {
@@ -97,9 +173,12 @@ fn base_theme(name: ThemeName) -> ThemeColors {
       meta_fg: c(103),          // Overlay0 (#8c8fa1)
       filter_placeholder_fg: c(103),
       filter_active_fg: c(59),  // Text (#4c4f69)
+      filter_mode_fg: c(98),    // Mauve (#8839ef)
       selection_bg: c(146),     // Surface1 (#bcc0cc)
       help_key_fg: c(170),      // Pink (#ea76cb)
       help_text_fg: c(60),      // Subtext0 (#6c6f85)
+      icon_android_fg: c(70),   // Green (#40a02b)
+      icon_ios_fg: c(59),       // Text (#4c4f69)
     },
     // https://draculatheme.com/contribute
     ThemeName::Dracula => ThemeColors {
@@ -111,9 +190,12 @@ fn base_theme(name: ThemeName) -> ThemeColors {
       meta_fg: c(61),           // Comment (#6272a4)
       filter_placeholder_fg: c(61),
       filter_active_fg: c(231), // Foreground (#f8f8f2)
+      filter_mode_fg: c(141),   // Purple (#bd93f9)
       selection_bg: c(59),      // Current Line (#44475a)
       help_key_fg: c(206),      // Pink (#ff79c6)
       help_text_fg: c(231),     // Foreground
+      icon_android_fg: c(83),   // Green (#50fa7b)
+      icon_ios_fg: c(231),      // Foreground (#f8f8f2)
     },
     // https://github.com/enkia/tokyo-night-vscode-theme
     ThemeName::TokyoNight => ThemeColors {
@@ -125,9 +207,12 @@ fn base_theme(name: ThemeName) -> ThemeColors {
       meta_fg: c(60),           // Comment (#565f89)
       filter_placeholder_fg: c(60),
       filter_active_fg: c(146), // Foreground (#a9b1d6)
+      filter_mode_fg: c(111),   // Blue (#7aa2f7)
       selection_bg: c(236),     // Selection (#292e42)
       help_key_fg: c(141),      // Purple (#bb9af7)
       help_text_fg: c(146),     // Foreground
+      icon_android_fg: c(149),  // Green (#9ece6a)
+      icon_ios_fg: c(146),      // Foreground (#a9b1d6)
     },
     // https://github.com/morhetz/gruvbox
     ThemeName::GruvboxDark => ThemeColors {
@@ -139,9 +224,12 @@ fn base_theme(name: ThemeName) -> ThemeColors {
       meta_fg: c(101),          // Gray (#928374)
       filter_placeholder_fg: c(101),
       filter_active_fg: c(223), // Foreground (#ebdbb2)
+      filter_mode_fg: c(208),   // Orange (#fe8019)
       selection_bg: c(239),     // Bg2 (#504945)
       help_key_fg: c(174),      // Purple (#d3869b)
       help_text_fg: c(181),     // Fg2 (#d5c4a1)
+      icon_android_fg: c(142),  // Green (#b8bb26)
+      icon_ios_fg: c(223),      // Foreground (#ebdbb2)
     },
     // https://www.nordtheme.com/docs/colors-and-palettes
     ThemeName::Nord => ThemeColors {
@@ -153,9 +241,12 @@ fn base_theme(name: ThemeName) -> ThemeColors {
       meta_fg: c(240),          // Nord3 comment (#4c566a)
       filter_placeholder_fg: c(240),
       filter_active_fg: c(188), // Nord4 snow (#d8dee9)
+      filter_mode_fg: c(109),   // Nord9 frost (#81a1c1)
       selection_bg: c(239),     // Nord2 (#434c5e)
       help_key_fg: c(139),      // Nord15 purple (#b48ead)
       help_text_fg: c(189),     // Nord5 (#e5e9f0)
+      icon_android_fg: c(144),  // Nord14 green (#a3be8c)
+      icon_ios_fg: c(188),      // Nord4 snow (#d8dee9)
     },
   }
 }
@@ -185,10 +276,98 @@ pub fn resolve_theme(theme_name: Option<&str>, overrides: Option<&ThemeOverrides
     apply!(meta_fg);
     apply!(filter_placeholder_fg);
     apply!(filter_active_fg);
+    apply!(filter_mode_fg);
     apply!(selection_bg);
     apply!(help_key_fg);
     apply!(help_text_fg);
+    apply!(icon_android_fg);
+    apply!(icon_ios_fg);
   }
 
   colors
 }
+
+/// The slot a device state is drawn in
+pub fn state_color(kind: StateKind, theme: &ThemeColors) -> Color {
+  match kind {
+    StateKind::Booted => theme.state_booted_fg,
+    StateKind::Shutdown | StateKind::Broken => theme.state_shutdown_fg,
+    StateKind::Other => theme.state_unknown_fg,
+  }
+}
+
+// Where the 6x6x6 cube and the gray ramp start in the 256-color palette
+const CUBE_START: u8 = 16;
+const GRAY_START: u8 = 232;
+
+/// The nearest 256-color palette index for an RGB color, for terminals
+/// without 24-bit color
+pub fn rgb_to_ansi256(r: u8, g: u8, b: u8) -> u8 {
+  // Cube levels are 0, 95, 135, 175, 215, 255; round to the nearest
+  let level = |v: u8| match v {
+    0..48 => 0,
+    48..115 => 1,
+    _ => ((v as u16 - 35) / 40) as u8,
+  };
+  let value = |l: u8| if l == 0 { 0 } else { 55 + 40 * l as i32 };
+  let (lr, lg, lb) = (level(r), level(g), level(b));
+  let cube = CUBE_START + 36 * lr + 6 * lg + lb;
+
+  // The gray ramp runs 8, 18, ... 238; use it when it is closer
+  let avg = (r as u16 + g as u16 + b as u16) / 3;
+  let gray_step = ((avg.saturating_sub(3)) / 10).min(23) as u8;
+  let gray = GRAY_START + gray_step;
+  let gray_value = 8 + 10 * gray_step as i32;
+
+  let distance = |cr: i32, cg: i32, cb: i32| {
+    (r as i32 - cr).pow(2) + (g as i32 - cg).pow(2) + (b as i32 - cb).pow(2)
+  };
+  let cube_distance = distance(value(lr), value(lg), value(lb));
+  let gray_distance = distance(gray_value, gray_value, gray_value);
+  if gray_distance < cube_distance {
+    gray
+  } else {
+    cube
+  }
+}
+
+/// Whether the terminal says it shows 24-bit color
+pub fn truecolor_supported() -> bool {
+  std::env::var("COLORTERM").is_ok_and(|v| v == "truecolor" || v == "24bit")
+}
+
+/// SGR parameters that set `color` as the foreground, or `None` for the
+/// terminal default. RGB colors become 256-color ones unless `truecolor`.
+pub fn ansi_fg(color: Color, truecolor: bool) -> Option<String> {
+  let code = match color {
+    Color::Reset => return None,
+    Color::Black => "30".to_string(),
+    Color::Red => "31".to_string(),
+    Color::Green => "32".to_string(),
+    Color::Yellow => "33".to_string(),
+    Color::Blue => "34".to_string(),
+    Color::Magenta => "35".to_string(),
+    Color::Cyan => "36".to_string(),
+    Color::Gray => "37".to_string(),
+    Color::DarkGray => "90".to_string(),
+    Color::LightRed => "91".to_string(),
+    Color::LightGreen => "92".to_string(),
+    Color::LightYellow => "93".to_string(),
+    Color::LightBlue => "94".to_string(),
+    Color::LightMagenta => "95".to_string(),
+    Color::LightCyan => "96".to_string(),
+    Color::White => "97".to_string(),
+    Color::Indexed(n) => format!("38;5;{}", n),
+    Color::Rgb(r, g, b) if truecolor => format!("38;2;{};{};{}", r, g, b),
+    Color::Rgb(r, g, b) => format!("38;5;{}", rgb_to_ansi256(r, g, b)),
+  };
+  Some(code)
+}
+
+/// `text` wrapped in the escape sequences for `color`
+pub fn paint(text: &str, color: Color, truecolor: bool) -> String {
+  match ansi_fg(color, truecolor) {
+    Some(code) if !text.is_empty() => format!("\x1b[{}m{}\x1b[0m", code, text),
+    _ => text.to_string(),
+  }
+}
@@ -0,0 +1,323 @@
+//! Developer trace of a session, for bug reports (`EMULAUNCH_TRACE=1`).
+//!
+//! Keys, dispatched actions, draws, refreshes and external commands are
+//! appended as JSON lines to `trace.log` in the data dir. Once the file
+//! passes `MAX_TRACE_BYTES` it is moved to `trace.log.1`, replacing the
+//! previous one. With the variable unset, `record` returns before building
+//! anything. `emulaunch trace-info` prints a redacted summary.
+
+use crate::config;
+use crate::filelock;
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const TRACE_ENV: &str = "EMULAUNCH_TRACE";
+
+const TRACE_FILE: &str = "trace.log";
+
+// Size at which the trace is rotated
+const MAX_TRACE_BYTES: u64 = 1024 * 1024;
+
+// Draws slower than this are counted as slow in the summary
+const SLOW_DRAW: Duration = Duration::from_millis(50);
+
+// How many of the last events the summary lists
+const SUMMARY_TAIL: usize = 20;
+
+static ENABLED: OnceLock<bool> = OnceLock::new();
+
+// Open trace file; `None` until the first event or after a rotation
+static WRITER: Mutex<Option<File>> = Mutex::new(None);
+
+/// Whether `EMULAUNCH_TRACE` is set to something other than empty or `0`
+pub fn enabled() -> bool {
+  *ENABLED.get_or_init(|| std::env::var_os(TRACE_ENV).is_some_and(|v| !v.is_empty() && v != "0"))
+}
+
+/// Something that happened in the event loop or a command it ran
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum TraceEvent {
+  /// A key event from the terminal, presses or not
+  Key {
+    key: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    modifiers: String,
+    kind: String,
+  },
+  /// What a key press was dispatched to
+  Action {
+    action: String,
+  },
+  Draw {
+    ms: f64,
+  },
+  RefreshStarted {
+    platform: String,
+  },
+  RefreshFinished {
+    platform: String,
+    ms: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    entries: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+  },
+  /// An external command, with how long it ran and how it ended
+  Command {
+    command: String,
+    ms: f64,
+    outcome: String,
+  },
+}
+
+impl TraceEvent {
+  fn name(&self) -> &'static str {
+    match self {
+      TraceEvent::Key { .. } => "key",
+      TraceEvent::Action { .. } => "action",
+      TraceEvent::Draw { .. } => "draw",
+      TraceEvent::RefreshStarted { .. } => "refresh_started",
+      TraceEvent::RefreshFinished { .. } => "refresh_finished",
+      TraceEvent::Command { .. } => "command",
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceRecord {
+  /// Unix time in milliseconds
+  pub at_ms: u64,
+  pub pid: u32,
+  #[serde(flatten)]
+  pub event: TraceEvent,
+}
+
+pub fn trace_path() -> Option<PathBuf> {
+  Some(config::data_dir()?.join(TRACE_FILE))
+}
+
+fn rotated_path(path: &Path) -> PathBuf {
+  filelock::sibling_path(path, "1")
+}
+
+/// Milliseconds in a duration, for the `ms` fields
+pub fn millis(duration: Duration) -> f64 {
+  duration.as_secs_f64() * 1000.0
+}
+
+/// Append an event when tracing is on. The event is only built then, and a
+/// failed write is dropped: tracing must never break the session.
+pub fn record(event: impl FnOnce() -> TraceEvent) {
+  if !enabled() {
+    return;
+  }
+  let record = TraceRecord {
+    at_ms: SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map_or(0, |d| d.as_millis() as u64),
+    pid: std::process::id(),
+    event: event(),
+  };
+  let Ok(line) = serde_json::to_string(&record) else {
+    return;
+  };
+  let Some(path) = trace_path() else {
+    return;
+  };
+  let mut writer = WRITER.lock().unwrap_or_else(|e| e.into_inner());
+  if writer.is_none() {
+    if let Some(dir) = path.parent() {
+      let _ = std::fs::create_dir_all(dir);
+    }
+    *writer = OpenOptions::new()
+      .create(true)
+      .append(true)
+      .open(&path)
+      .ok();
+  }
+  let Some(file) = writer.as_mut() else {
+    return;
+  };
+  if writeln!(file, "{}", line).is_err() {
+    *writer = None;
+    return;
+  }
+  if file.metadata().is_ok_and(|m| m.len() >= MAX_TRACE_BYTES) {
+    *writer = None;
+    let _ = std::fs::rename(&path, rotated_path(&path));
+  }
+}
+
+/// Every record in the rotated file and then the current one
+pub fn load() -> Vec<TraceRecord> {
+  let Some(path) = trace_path() else {
+    return Vec::new();
+  };
+  [rotated_path(&path), path]
+    .iter()
+    .filter_map(|p| std::fs::read_to_string(p).ok())
+    .flat_map(|contents| filelock::parse_jsonl(&contents))
+    .collect()
+}
+
+/// Strip what could identify the user or their devices: typed characters,
+/// the home directory and the user name
+fn redact(text: &str) -> String {
+  let mut text = text.to_string();
+  if let Some(home) = config::home_dir() {
+    let home = home.to_string_lossy();
+    if home.len() > 1 {
+      text = text.replace(home.as_ref(), "~");
+    }
+  }
+  let user = std::env::var("USER")
+    .or_else(|_| std::env::var("USERNAME"))
+    .unwrap_or_default();
+  if user.len() > 2 {
+    text = text.replace(&user, "<user>");
+  }
+  text
+}
+
+/// One event as a line of the summary
+fn describe(event: &TraceEvent) -> String {
+  match event {
+    TraceEvent::Key {
+      key,
+      modifiers,
+      kind,
+    } => {
+      // Typed characters could spell device or app names
+      let key = if key.chars().count() == 1 {
+        "<char>"
+      } else {
+        key.as_str()
+      };
+      match modifiers.as_str() {
+        "" => format!("key {} ({})", key, kind),
+        m => format!("key {}+{} ({})", m, key, kind),
+      }
+    }
+    TraceEvent::Action { action } => format!("action {}", action),
+    TraceEvent::Draw { ms } => format!("draw {:.1}ms", ms),
+    TraceEvent::RefreshStarted { platform } => format!("refresh {} started", platform),
+    TraceEvent::RefreshFinished {
+      platform,
+      ms,
+      entries,
+      error,
+    } => match (entries, error) {
+      (_, Some(e)) => format!(
+        "refresh {} failed after {:.0}ms: {}",
+        platform,
+        ms,
+        redact(e)
+      ),
+      (n, None) => format!(
+        "refresh {} finished in {:.0}ms, {} entries",
+        platform,
+        ms,
+        n.unwrap_or(0)
+      ),
+    },
+    TraceEvent::Command {
+      command,
+      ms,
+      outcome,
+    } => format!("{} ({:.0}ms, {})", redact(command), ms, outcome),
+  }
+}
+
+/// A summary of `records` to paste into an issue: counts, the slowest
+/// draws and commands, and the last few events, redacted
+pub fn format_summary(records: &[TraceRecord]) -> String {
+  if records.is_empty() {
+    return format!("No trace recorded yet; run with {}=1\n", TRACE_ENV);
+  }
+  let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+  let pids: BTreeSet<u32> = records.iter().map(|r| r.pid).collect();
+  let mut draws: Vec<f64> = Vec::new();
+  let mut commands: Vec<(&str, f64, &str)> = Vec::new();
+  for record in records {
+    *counts.entry(record.event.name()).or_default() += 1;
+    match &record.event {
+      TraceEvent::Draw { ms } => draws.push(*ms),
+      TraceEvent::Command {
+        command,
+        ms,
+        outcome,
+      } => commands.push((command, *ms, outcome)),
+      _ => {}
+    }
+  }
+  let span_secs = records
+    .last()
+    .map_or(0, |r| r.at_ms)
+    .saturating_sub(records[0].at_ms) as f64
+    / 1000.0;
+
+  let mut out = format!(
+    "emulaunch {} on {}, {} events from {} {} over {:.0}s\n",
+    env!("CARGO_PKG_VERSION"),
+    std::env::consts::OS,
+    records.len(),
+    pids.len(),
+    if pids.len() == 1 {
+      "process"
+    } else {
+      "processes"
+    },
+    span_secs
+  );
+  out.push_str(&format!(
+    "Events: {}\n",
+    counts
+      .iter()
+      .map(|(name, n)| format!("{} {}", name, n))
+      .collect::<Vec<_>>()
+      .join(", ")
+  ));
+  if let Some(max) = draws.iter().copied().reduce(f64::max) {
+    let slow = draws.iter().filter(|&&ms| ms >= millis(SLOW_DRAW)).count();
+    out.push_str(&format!(
+      "Draws: slowest {:.1}ms, {} over {}ms\n",
+      max,
+      slow,
+      SLOW_DRAW.as_millis()
+    ));
+  }
+  if !commands.is_empty() {
+    commands.sort_by(|a, b| b.1.total_cmp(&a.1));
+    out.push_str("Slowest commands:\n");
+    for (command, ms, outcome) in commands.iter().take(5) {
+      out.push_str(&format!(
+        "  {:>7.0}ms  {}  ({})\n",
+        ms,
+        redact(command),
+        outcome
+      ));
+    }
+  }
+  // Draws run every frame and would fill the tail; they are summed up above
+  let tail: Vec<&TraceRecord> = records
+    .iter()
+    .filter(|r| !matches!(r.event, TraceEvent::Draw { .. }))
+    .collect();
+  out.push_str(&format!("Last {} events:\n", SUMMARY_TAIL.min(tail.len())));
+  let last = records.last().map_or(0, |r| r.at_ms);
+  for record in tail.iter().skip(tail.len().saturating_sub(SUMMARY_TAIL)) {
+    out.push_str(&format!(
+      "  -{:>6.1}s  {}\n",
+      last.saturating_sub(record.at_ms) as f64 / 1000.0,
+      describe(&record.event)
+    ));
+  }
+  out
+}
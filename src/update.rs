@@ -0,0 +1,187 @@
+//! Opt-in check for a newer release on GitHub (`update_check = true`).
+//!
+//! The latest release tag is fetched at most once a day and cached in the
+//! data dir, so the notice keeps showing between fetches. Failures are
+//! silent everywhere except `version --check`. Builds without the
+//! `update-check` feature never touch the network.
+
+use crate::config;
+use crate::filelock;
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[cfg_attr(not(feature = "update-check"), allow(dead_code))]
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/ArnabXD/emulaunch/releases/latest";
+const RELEASES_PAGE: &str = "https://github.com/ArnabXD/emulaunch/releases";
+
+const CACHE_FILE: &str = "update-check.json";
+
+// How long a fetched (or failed) check is reused before asking GitHub again
+const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Bound on the whole request; also how long a command waits for the notice
+pub const FETCH_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[cfg_attr(feature = "update-check", allow(dead_code))]
+const ERR_NOT_BUILT: &str =
+  "this build can't check for updates; reinstall with `--features update-check`";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CheckCache {
+  /// Unix timestamp (seconds) of the last attempt, successful or not
+  checked_at: u64,
+  latest: Option<String>,
+}
+
+/// `major.minor.patch` from a tag like `v1.2.3`; pre-releases are ignored
+pub fn parse_version(tag: &str) -> Option<(u64, u64, u64)> {
+  let version = tag.trim().trim_start_matches('v');
+  let version = version.split('+').next()?;
+  if version.contains('-') {
+    return None;
+  }
+  let mut parts = version.split('.').map(|p| p.parse::<u64>().ok());
+  let parsed = (parts.next()??, parts.next()??, parts.next()??);
+  parts.next().is_none().then_some(parsed)
+}
+
+/// Whether `latest` is a strictly newer release than `current`
+pub fn is_newer(latest: &str, current: &str) -> bool {
+  match (parse_version(latest), parse_version(current)) {
+    (Some(latest), Some(current)) => latest > current,
+    _ => false,
+  }
+}
+
+#[cfg(feature = "update-check")]
+fn fetch_latest_tag() -> Result<String, String> {
+  #[derive(Deserialize)]
+  struct Release {
+    tag_name: String,
+  }
+
+  let agent: ureq::Agent = ureq::Agent::config_builder()
+    .timeout_global(Some(FETCH_TIMEOUT))
+    .build()
+    .into();
+  let body = agent
+    .get(LATEST_RELEASE_URL)
+    .header(
+      "User-Agent",
+      concat!("emulaunch/", env!("CARGO_PKG_VERSION")),
+    )
+    .header("Accept", "application/vnd.github+json")
+    .call()
+    .map_err(|e| format!("Failed to reach GitHub: {}", e))?
+    .body_mut()
+    .read_to_string()
+    .map_err(|e| format!("Failed to read the GitHub response: {}", e))?;
+  let release: Release =
+    serde_json::from_str(&body).map_err(|e| format!("Unexpected GitHub response: {}", e))?;
+  Ok(release.tag_name)
+}
+
+#[cfg(not(feature = "update-check"))]
+fn fetch_latest_tag() -> Result<String, String> {
+  Err(ERR_NOT_BUILT.to_string())
+}
+
+fn cache_path() -> Option<PathBuf> {
+  Some(config::data_dir()?.join(CACHE_FILE))
+}
+
+fn read_cache() -> Option<CheckCache> {
+  let contents = std::fs::read_to_string(cache_path()?).ok()?;
+  serde_json::from_str(&contents).ok()
+}
+
+fn write_cache(cache: &CheckCache) {
+  let Some(path) = cache_path() else {
+    return;
+  };
+  if let Some(dir) = path.parent() {
+    let _ = std::fs::create_dir_all(dir);
+  }
+  if let Ok(contents) = serde_json::to_string(cache) {
+    let _ = filelock::write_atomic(&path, &contents);
+  }
+}
+
+fn now_secs() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map_or(0, |d| d.as_secs())
+}
+
+/// The latest release tag, fetched only when the cached one is over a day
+/// old. A failed fetch is remembered too, so an offline machine doesn't
+/// retry on every run.
+fn latest_tag() -> Option<String> {
+  let cache = read_cache();
+  let now = now_secs();
+  if let Some(cache) = &cache {
+    if now.saturating_sub(cache.checked_at) < CHECK_INTERVAL.as_secs() {
+      return cache.latest.clone();
+    }
+  }
+  let latest = fetch_latest_tag()
+    .ok()
+    .or_else(|| cache.and_then(|c| c.latest));
+  write_cache(&CheckCache {
+    checked_at: now,
+    latest: latest.clone(),
+  });
+  latest
+}
+
+fn newer_release_notice(latest: &str) -> Option<String> {
+  is_newer(latest, CURRENT_VERSION).then(|| {
+    format!(
+      "emulaunch {} is available (you have {}): {}",
+      latest.trim_start_matches('v'),
+      CURRENT_VERSION,
+      RELEASES_PAGE
+    )
+  })
+}
+
+/// A one-line notice when `update_check` is on and a newer release exists
+pub fn notice() -> Option<String> {
+  let enabled = config::load_config()
+    .and_then(|c| c.update_check)
+    .unwrap_or(false);
+  if !cfg!(feature = "update-check") || !enabled {
+    return None;
+  }
+  newer_release_notice(&latest_tag()?)
+}
+
+/// Look for a newer release on a background thread; the receiver gets the
+/// notice if there is one and is disconnected otherwise
+pub fn spawn_notice() -> mpsc::Receiver<String> {
+  let (tx, rx) = mpsc::channel();
+  std::thread::spawn(move || {
+    if let Some(notice) = notice() {
+      let _ = tx.send(notice);
+    }
+  });
+  rx
+}
+
+/// `version --check`: ask GitHub now, whatever the cache or config says
+pub fn check_now() -> Result<String, String> {
+  let latest = fetch_latest_tag()?;
+  write_cache(&CheckCache {
+    checked_at: now_secs(),
+    latest: Some(latest.clone()),
+  });
+  Ok(
+    newer_release_notice(&latest)
+      .unwrap_or_else(|| format!("emulaunch {} is up to date", CURRENT_VERSION)),
+  )
+}
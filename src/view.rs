@@ -0,0 +1,347 @@
+//! List preparation for the TUI.
+//!
+//! Everything that changes which devices the list shows, or in what order,
+//! is a stage of `build`. The stages always run in this order:
+//!
+//! 1. hide: drop devices the user hid, and whole platforms
+//! 2. annotate: attach per-device data later stages use (last launch time)
+//! 3. group: one section per platform, in the order the sections were listed,
+//!    after a Recent section repeating the `recent` last launched devices
+//! 4. sort: order devices within each platform section (Recent stays in
+//!    launch order)
+//! 5. filter: keep devices matching the query, and the headers of sections
+//!    that still have one; the Recent section is left out while filtering.
+//!    A collapsed section keeps only its header
+//! 6. limit: show at most `limit` of the matching devices, in order; the
+//!    rest are only counted
+//!
+//! So a hidden device can't be found by the filter, sorting never moves a
+//! device to another section, filtering never changes the order, and the
+//! limit applies to what the filter left. Rows in the Recent section are
+//! copies, so they don't count towards the limit or the match count.
+
+use crate::config;
+use crate::emulators::{self, EmulatorEntry, EntryKey, Platform};
+use crate::text;
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{HashMap, HashSet};
+
+/// Header of the section repeating the last launched devices
+const RECENT_TITLE: &str = "Recent";
+
+/// Names `collapsed_sections` takes, and the sections they stand for
+pub const SECTION_NAMES: [(&str, &str); 3] = [
+  ("recent", RECENT_TITLE),
+  ("android", emulators::SECTION_ANDROID_EMULATORS),
+  ("ios", emulators::SECTION_IOS_SIMULATORS),
+];
+
+/// Config name of the section with this title
+pub fn section_name(title: &str) -> Option<&'static str> {
+  SECTION_NAMES
+    .iter()
+    .find(|(_, t)| *t == title)
+    .map(|(name, _)| *name)
+}
+
+/// Title of the section with this config name
+pub fn section_title(name: &str) -> Option<&'static str> {
+  SECTION_NAMES
+    .iter()
+    .find(|(n, _)| name.trim().eq_ignore_ascii_case(n))
+    .map(|(_, title)| *title)
+}
+
+/// Titles of the sections `collapsed_sections` in the config starts
+/// collapsed; unknown names are skipped
+pub fn configured_collapsed() -> HashSet<String> {
+  let names = config::load_config()
+    .and_then(|c| c.collapsed_sections)
+    .unwrap_or_default();
+  names
+    .iter()
+    .filter_map(|name| section_title(name))
+    .map(str::to_string)
+    .collect()
+}
+
+/// How devices are ordered within a section
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortMode {
+  /// Booted first, then by name
+  #[default]
+  State,
+  /// By name
+  Name,
+  /// Most recently launched first, then as `State`
+  Recent,
+}
+
+impl SortMode {
+  /// Every mode, in the order the TUI cycles through them
+  pub const ALL: [SortMode; 3] = [SortMode::State, SortMode::Name, SortMode::Recent];
+
+  /// Name in the config and the list title
+  pub fn name(self) -> &'static str {
+    match self {
+      SortMode::State => "state",
+      SortMode::Name => "name",
+      SortMode::Recent => "recent",
+    }
+  }
+
+  pub fn from_name(name: &str) -> Option<Self> {
+    Self::ALL
+      .into_iter()
+      .find(|mode| name.trim().eq_ignore_ascii_case(mode.name()))
+  }
+
+  /// `sort` from the config, or the default
+  pub fn configured() -> Self {
+    config::load_config()
+      .and_then(|c| c.sort)
+      .and_then(|name| Self::from_name(&name))
+      .unwrap_or_default()
+  }
+
+  /// The next mode; `Recent` is skipped while nothing has been launched
+  pub fn next(self, have_history: bool) -> Self {
+    let i = Self::ALL.iter().position(|&m| m == self).unwrap_or(0);
+    let next = Self::ALL[(i + 1) % Self::ALL.len()];
+    if next == SortMode::Recent && !have_history {
+      next.next(have_history)
+    } else {
+      next
+    }
+  }
+}
+
+/// Inputs for every stage
+#[derive(Debug, Clone, Default)]
+pub struct ViewOptions {
+  pub hidden: HashSet<EntryKey>,
+  /// Platforms whose section is hidden (`a`/`s` in the TUI)
+  pub hidden_platforms: HashSet<Platform>,
+  pub sort: SortMode,
+  /// Unix time of each device's last launch, for `SortMode::Recent`
+  pub last_launched: HashMap<EntryKey, u64>,
+  /// Devices in the Recent section; 0 leaves it out
+  pub recent: usize,
+  /// Titles of sections showing only their header
+  pub collapsed: HashSet<String>,
+  /// Filter text as typed
+  pub filter: String,
+  /// Most devices to show; `None` shows all
+  pub limit: Option<usize>,
+}
+
+/// The prepared list: all rows (headers included) and the positions of the
+/// ones that pass the filter and the limit
+pub struct View {
+  pub entries: Vec<EmulatorEntry>,
+  pub visible: Vec<usize>,
+  /// Devices that passed the filter, including any cut by the limit
+  pub matched: usize,
+  /// Devices in `visible`
+  pub shown: usize,
+  /// Rows at the start of `entries` that belong to the Recent section
+  pinned: usize,
+  /// Headers of collapsed sections, which stand without their devices
+  collapsed: HashSet<usize>,
+}
+
+/// A device and what `annotate` found out about it
+#[derive(Clone, Copy)]
+struct Device<'a> {
+  entry: &'a EmulatorEntry,
+  last_launched: Option<u64>,
+}
+
+/// One section being worked on
+struct Section<'a> {
+  title: &'static str,
+  /// The Recent section, whose devices are also listed in their own
+  recent: bool,
+  devices: Vec<Device<'a>>,
+}
+
+fn hide<'a>(
+  sections: &'a [(Platform, Vec<EmulatorEntry>)],
+  hidden: &HashSet<EntryKey>,
+  hidden_platforms: &HashSet<Platform>,
+) -> Vec<(Platform, Vec<&'a EmulatorEntry>)> {
+  sections
+    .iter()
+    .filter(|(platform, _)| !hidden_platforms.contains(platform))
+    .map(|(platform, entries)| {
+      let kept = entries
+        .iter()
+        .filter(|e| e.key().is_none_or(|k| !hidden.contains(&k)))
+        .collect();
+      (*platform, kept)
+    })
+    .collect()
+}
+
+fn annotate<'a>(
+  sections: Vec<(Platform, Vec<&'a EmulatorEntry>)>,
+  last_launched: &HashMap<EntryKey, u64>,
+) -> Vec<(Platform, Vec<Device<'a>>)> {
+  sections
+    .into_iter()
+    .map(|(platform, entries)| {
+      let devices = entries
+        .into_iter()
+        .map(|entry| Device {
+          entry,
+          last_launched: entry.key().and_then(|k| last_launched.get(&k).copied()),
+        })
+        .collect();
+      (platform, devices)
+    })
+    .collect()
+}
+
+/// Empty sections get no header
+fn group(sections: Vec<(Platform, Vec<Device<'_>>)>, recent: usize) -> Vec<Section<'_>> {
+  let mut launched: Vec<Device> = sections
+    .iter()
+    .flat_map(|(_, devices)| devices)
+    .filter(|d| d.last_launched.is_some())
+    .copied()
+    .collect();
+  launched.sort_by_key(|d| Reverse(d.last_launched));
+  launched.truncate(recent);
+  let recent = Section {
+    title: RECENT_TITLE,
+    recent: true,
+    devices: launched,
+  };
+  std::iter::once(recent)
+    .chain(sections.into_iter().map(|(platform, devices)| Section {
+      title: platform.section_title(),
+      recent: false,
+      devices,
+    }))
+    .filter(|section| !section.devices.is_empty())
+    .collect()
+}
+
+/// Ordering of two devices in the same section
+fn compare(mode: SortMode, a: &Device, b: &Device) -> Ordering {
+  let booted = |d: &Device| d.entry.state() == Some(emulators::STATE_BOOTED);
+  let by_name = || text::collate(a.entry.display_name(), b.entry.display_name());
+  let by_state = || booted(b).cmp(&booted(a)).then_with(by_name);
+  match mode {
+    SortMode::State => by_state(),
+    SortMode::Name => by_name(),
+    // Never-launched devices (`None`) sort after every launched one
+    SortMode::Recent => Reverse(a.last_launched)
+      .cmp(&Reverse(b.last_launched))
+      .then_with(by_state),
+  }
+}
+
+fn sort(sections: &mut [Section], mode: SortMode) {
+  for section in sections.iter_mut().filter(|s| !s.recent) {
+    section.devices.sort_by(|a, b| compare(mode, a, b));
+  }
+}
+
+/// Flatten sections into rows and pick the visible ones
+fn filter(sections: Vec<Section>, query: &str, collapsed_titles: &HashSet<String>) -> View {
+  let query = text::fold(query);
+  let mut entries = Vec::new();
+  let mut visible = Vec::new();
+  let mut pinned = 0;
+  let mut collapsed = HashSet::new();
+  for section in sections {
+    let header = entries.len();
+    let is_collapsed = collapsed_titles.contains(section.title);
+    if is_collapsed {
+      collapsed.insert(header);
+    }
+    if section.recent {
+      // Whatever the query finds is already in the platform sections
+      if query.is_empty() {
+        entries.push(EmulatorEntry::SectionHeader(section.title.to_string()));
+        entries.extend(section.devices.iter().map(|d| d.entry.clone()));
+        visible.push(header);
+        if !is_collapsed {
+          visible.extend(header + 1..entries.len());
+        }
+        pinned = entries.len();
+      }
+      continue;
+    }
+    entries.push(EmulatorEntry::SectionHeader(section.title.to_string()));
+    let mut any_visible = false;
+    for Device { entry: device, .. } in section.devices {
+      if text::matches(device.display_name(), &query) {
+        if !any_visible {
+          visible.push(header);
+          any_visible = true;
+        }
+        if !is_collapsed {
+          visible.push(entries.len());
+        }
+      }
+      entries.push(device.clone());
+    }
+  }
+  let matched = visible
+    .iter()
+    .filter(|&&i| i >= pinned && !entries[i].is_header())
+    .count();
+  View {
+    entries,
+    visible,
+    matched,
+    shown: matched,
+    pinned,
+    collapsed,
+  }
+}
+
+/// Keep the first `max` visible devices, and only the headers of sections
+/// that still show one (or are collapsed)
+fn limit(view: &mut View, max: Option<usize>) {
+  let Some(max) = max.filter(|&max| max < view.matched) else {
+    return;
+  };
+  let entries = &view.entries;
+  let empty_header = |i: usize| entries[i].is_header() && !view.collapsed.contains(&i);
+  let mut shown = 0;
+  let mut kept: Vec<usize> = Vec::new();
+  for &i in &view.visible {
+    if i < view.pinned {
+      kept.push(i);
+    } else if entries[i].is_header() {
+      // A header with nothing after it is replaced
+      if kept.last().is_some_and(|&last| empty_header(last)) {
+        kept.pop();
+      }
+      kept.push(i);
+    } else if shown < max {
+      kept.push(i);
+      shown += 1;
+    }
+  }
+  if kept.last().is_some_and(|&last| empty_header(last)) {
+    kept.pop();
+  }
+  view.visible = kept;
+  view.shown = shown;
+}
+
+/// Run every stage over freshly listed sections
+pub fn build(sections: &[(Platform, Vec<EmulatorEntry>)], options: &ViewOptions) -> View {
+  let kept = hide(sections, &options.hidden, &options.hidden_platforms);
+  let annotated = annotate(kept, &options.last_launched);
+  let mut grouped = group(annotated, options.recent);
+  sort(&mut grouped, options.sort);
+  let mut view = filter(grouped, &options.filter, &options.collapsed);
+  limit(&mut view, options.limit);
+  view
+}